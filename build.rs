@@ -0,0 +1,53 @@
+// Generates the D-Bus introspection XML of the stable interface
+#![allow(dead_code)]
+
+include!("src/daemon/dbus_api.rs");
+
+fn main() {
+    println!("cargo:rerun-if-changed=src/daemon/dbus_api.rs");
+
+    let out_dir = std::env::var("OUT_DIR").unwrap();
+    let file = std::path::Path::new(&out_dir).join("org.livebuds.Daemon1.xml");
+    std::fs::write(file, introspection_xml()).unwrap();
+}
+
+// Introspection XML loadable by gdbus-codegen or Gio.DBusNodeInfo
+fn introspection_xml() -> String {
+    let mut xml = String::from(
+        "<!DOCTYPE node PUBLIC \"-//freedesktop//DTD D-BUS Object Introspection 1.0//EN\"\n \
+         \"http://www.freedesktop.org/standards/dbus/1.0/introspect.dtd\">\n",
+    );
+    xml.push_str(&format!("<!-- API version {} -->\n<node>\n", API_VERSION));
+
+    for interface in INTERFACES {
+        xml.push_str(&format!("  <!-- {} -->\n", interface.doc));
+        xml.push_str(&format!("  <interface name=\"{}\">\n", interface.name));
+
+        for property in interface.properties {
+            xml.push_str(&format!("    <!-- {} -->\n", property.doc));
+            xml.push_str(&format!(
+                "    <property name=\"{}\" type=\"{}\" access=\"read\">\n",
+                property.name, property.signature
+            ));
+            xml.push_str("      <annotation name=\"org.freedesktop.DBus.Property.EmitsChangedSignal\" value=\"true\"/>\n");
+            xml.push_str("    </property>\n");
+        }
+
+        for method in interface.methods {
+            xml.push_str(&format!("    <!-- {} -->\n", method.doc));
+            xml.push_str(&format!("    <method name=\"{}\">\n", method.name));
+            for arg in method.args {
+                xml.push_str(&format!(
+                    "      <arg name=\"{}\" type=\"{}\" direction=\"{}\"/>\n",
+                    arg.name, arg.signature, arg.direction
+                ));
+            }
+            xml.push_str("    </method>\n");
+        }
+
+        xml.push_str("  </interface>\n");
+    }
+
+    xml.push_str("</node>\n");
+    xml
+}
@@ -0,0 +1,287 @@
+/*
+ * Async client of the earbuds daemon for GUI and tray applications. It takes
+ * care of the socket framing of the unix socket requests and returns typed
+ * results. Runs on async-std (default) or tokio, selected by the feature of
+ * the same name.
+ */
+
+mod runtime;
+
+use futures_lite::stream::{self, Boxed, StreamExt};
+use runtime::Connection;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use std::env;
+
+/// Client of the daemon. Each call opens its own connection
+#[derive(Debug, Clone)]
+pub struct Client {
+    path: String,
+}
+
+impl Default for Client {
+    /// Client of the daemon of the current user
+    fn default() -> Self {
+        match env::var("XDG_RUNTIME_DIR").ok().filter(|i| !i.is_empty()) {
+            Some(dir) => Self::new(format!("{}/earbuds.sock", dir)),
+            None => Self::new("/tmp/earbuds.sock"),
+        }
+    }
+}
+
+impl Client {
+    /// Client of the daemon listening on `path`
+    pub fn new<S: Into<String>>(path: S) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Returns the status of a device, or of the default one if `device` is None
+    pub async fn status(&self, device: Option<&str>) -> Result<Status, String> {
+        let request = Request::new("get_status", device);
+        self.request::<Status>(&request)
+            .await?
+            .ok_or_else(|| "Empty response".to_owned())
+    }
+
+    /// Enable or disable active noise cancelling
+    pub async fn set_anc(&self, device: Option<&str>, enabled: bool) -> Result<(), String> {
+        self.set_value(device, "noise_reduction", &enabled.to_string())
+            .await
+    }
+
+    /// Set a value like `earbuds set` does, eg. `equalizer` to `bass`
+    pub async fn set_value(
+        &self,
+        device: Option<&str>,
+        key: &str,
+        value: &str,
+    ) -> Result<(), String> {
+        let mut request = Request::new("set_value", device);
+        request.opt_param1 = Some(key);
+        request.opt_param2 = Some(value);
+        self.request::<Value>(&request).await.map(|_| ())
+    }
+
+    /// Stream the events of a device, or of all devices if `device` is None.
+    /// With `snapshot`, the current status of the devices is sent first. The
+    /// stream ends once the daemon closes the connection
+    pub async fn subscribe(
+        &self,
+        device: Option<&str>,
+        snapshot: bool,
+    ) -> Result<Boxed<Event>, String> {
+        let mut request = Request::new("subscribe", device);
+        if snapshot {
+            request.opt_param1 = Some("snapshot");
+        }
+        let connection = self.open(&request).await?;
+
+        let events = stream::unfold(connection, |mut connection| async move {
+            // Lines which aren't events get skipped
+            while let Ok(Some(line)) = connection.read_line().await {
+                if let Some(event) = Event::parse(&line) {
+                    return Some((event, connection));
+                }
+            }
+            None
+        });
+        Ok(events.boxed())
+    }
+
+    // Do a request and return the payload of its response
+    async fn request<T>(&self, request: &Request<'_>) -> Result<Option<T>, String>
+    where
+        T: DeserializeOwned,
+    {
+        let response = self
+            .open(request)
+            .await?
+            .read_to_end()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let response: Response<T> =
+            serde_json::from_str(&response).map_err(|e| format!("Invalid response: {}", e))?;
+        if response.status != "success" {
+            return Err(response.status_message.unwrap_or_default());
+        }
+        Ok(response.payload)
+    }
+
+    async fn open(&self, request: &Request<'_>) -> Result<Connection, String> {
+        let mut line = serde_json::to_string(request).map_err(|e| e.to_string())?;
+        line.push('\n');
+
+        Connection::open(&self.path, &line)
+            .await
+            .map_err(|e| format!("Can't connect to the daemon: {}", e))
+    }
+}
+
+/// Status of a device. Only the values clients commonly show are included
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Status {
+    pub address: String,
+    // Values are only known once the buds sent their settings
+    pub ready: bool,
+    pub model_name: String,
+    // Name of the device set in BlueZ
+    pub alias: Option<String>,
+    pub batt_left: i8,
+    pub batt_right: i8,
+    pub batt_case: i8,
+    // Placement of the buds as encoded by them
+    pub placement_left: u8,
+    pub placement_right: u8,
+    pub noise_reduction: bool,
+    pub ambient_sound_enabled: bool,
+    pub ambient_sound_volume: u8,
+    // Keys of the supported settings
+    pub capabilities: Vec<String>,
+}
+
+/// An event of a subscription
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Full status of a device, sent first if requested
+    Snapshot(Status),
+    /// Changed values of a device by their key in the status, eg. `batt_left`
+    Changed {
+        device: String,
+        changed: Map<String, Value>,
+    },
+    /// Any other event, eg. `connected` or `case_battery_low`
+    Named {
+        device: String,
+        name: String,
+        data: Value,
+    },
+}
+
+impl Event {
+    fn parse(line: &str) -> Option<Self> {
+        let raw: RawEvent = serde_json::from_str(line).ok()?;
+
+        if let Some(status) = raw.snapshot {
+            return Some(Event::Snapshot(status));
+        }
+        if let Some(changed) = raw.changed {
+            return Some(Event::Changed {
+                device: raw.device,
+                changed,
+            });
+        }
+
+        Some(Event::Named {
+            device: raw.device,
+            name: raw.event?,
+            data: raw.data,
+        })
+    }
+}
+
+// Events as sent by the daemon
+#[derive(Deserialize)]
+struct RawEvent {
+    device: String,
+    snapshot: Option<Status>,
+    changed: Option<Map<String, Value>>,
+    event: Option<String>,
+    #[serde(default)]
+    data: Value,
+}
+
+// Request of the unix socket
+#[derive(Serialize)]
+struct Request<'a> {
+    cmd: &'a str,
+    device: Option<&'a str>,
+    opt_param1: Option<&'a str>,
+    opt_param2: Option<&'a str>,
+    opt_param3: Option<&'a str>,
+}
+
+impl<'a> Request<'a> {
+    fn new(cmd: &'a str, device: Option<&'a str>) -> Self {
+        Self {
+            cmd,
+            device,
+            opt_param1: None,
+            opt_param2: None,
+            opt_param3: None,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct Response<T> {
+    status: String,
+    status_message: Option<String>,
+    payload: Option<T>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_snapshot() {
+        let line = json!({
+            "device": "AA:BB",
+            "snapshot": { "address": "AA:BB", "ready": true, "batt_left": 64, "debug": {} }
+        });
+
+        let status = match Event::parse(&line.to_string()) {
+            Some(Event::Snapshot(status)) => status,
+            event => panic!("Unexpected event {:?}", event),
+        };
+        assert_eq!(status.address, "AA:BB");
+        assert!(status.ready);
+        assert_eq!(status.batt_left, 64);
+    }
+
+    #[test]
+    fn test_parse_changed() {
+        let line = json!({ "device": "AA:BB", "changed": { "batt_case": 90 } });
+        let mut changed = Map::new();
+        changed.insert("batt_case".to_owned(), json!(90));
+
+        assert_eq!(
+            Event::parse(&line.to_string()),
+            Some(Event::Changed {
+                device: "AA:BB".to_owned(),
+                changed
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_named() {
+        let line = json!({ "device": "AA:BB", "event": "disconnected", "data": { "reason": "requested" } });
+
+        assert_eq!(
+            Event::parse(&line.to_string()),
+            Some(Event::Named {
+                device: "AA:BB".to_owned(),
+                name: "disconnected".to_owned(),
+                data: json!({ "reason": "requested" }),
+            })
+        );
+        assert_eq!(Event::parse("{\"device\": \"AA:BB\"}"), None);
+        assert_eq!(Event::parse("not json"), None);
+    }
+
+    #[test]
+    fn test_response() {
+        let response: Response<Status> = serde_json::from_str(
+            r#"{"status":"error","device":"","status_message":"Device not found","payload":null}"#,
+        )
+        .unwrap();
+        assert_eq!(response.status, "error");
+        assert_eq!(response.status_message.as_deref(), Some("Device not found"));
+        assert!(response.payload.is_none());
+    }
+}
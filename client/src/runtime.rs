@@ -0,0 +1,55 @@
+/*
+ * The socket of the selected runtime. Requests are a single json line, the
+ * daemon answers with one response or a stream of json lines and closes the
+ * connection afterwards.
+ */
+
+#[cfg(not(any(feature = "async-std", feature = "tokio")))]
+compile_error!("Enable either the async-std or the tokio feature");
+
+#[cfg(feature = "async-std")]
+use async_std::{
+    io::{prelude::*, BufReader},
+    os::unix::net::UnixStream,
+};
+#[cfg(all(feature = "tokio", not(feature = "async-std")))]
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::UnixStream,
+};
+
+use std::io;
+
+/// A connection to the daemon carrying a single request
+pub struct Connection {
+    reader: BufReader<UnixStream>,
+}
+
+impl Connection {
+    /// Connect to the daemon and send a request
+    pub async fn open(path: &str, request: &str) -> io::Result<Self> {
+        let mut stream = UnixStream::connect(path).await?;
+        stream.write_all(request.as_bytes()).await?;
+        stream.flush().await?;
+
+        Ok(Self {
+            reader: BufReader::new(stream),
+        })
+    }
+
+    /// Read until the daemon closes the connection
+    pub async fn read_to_end(mut self) -> io::Result<String> {
+        let mut response = String::new();
+        self.reader.read_to_string(&mut response).await?;
+        Ok(response)
+    }
+
+    /// Read the next line. Returns None once the daemon closed the connection
+    pub async fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line).await? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line))
+    }
+}
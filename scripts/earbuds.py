@@ -0,0 +1,117 @@
+"""Python client of the earbuds daemon, eg. for qtile widgets or waybar modules.
+
+Only needs the standard library. Copy it next to your config and use it like:
+
+    import earbuds
+    client = earbuds.Client()
+    status = client.status()
+    print(status["batt_left"], status["batt_right"])
+    client.set_anc(True)
+
+Run it directly to print the status as json.
+"""
+
+import json
+import os
+import socket
+
+
+def default_path():
+    """Socket of the daemon of the current user, like the earbuds cli uses it."""
+    runtime_dir = os.environ.get("XDG_RUNTIME_DIR")
+    if runtime_dir:
+        return os.path.join(runtime_dir, "earbuds.sock")
+    return "/tmp/earbuds.sock"
+
+
+class DaemonError(Exception):
+    """The daemon answered with an error, eg. because no device is connected."""
+
+
+class Client:
+    """Talks to the daemon over its unix socket. Each request uses its own
+    connection, since the daemon handles only one request per connection."""
+
+    def __init__(self, path=None, device=None, timeout=5.0):
+        self.path = path or default_path()
+        self.device = device
+        self.timeout = timeout
+
+    def status(self):
+        """Returns the status of the device as dict."""
+        return self.request("get_status")
+
+    def devices(self):
+        """Returns the connected and paired buds."""
+        return self.request("list_devices")
+
+    def get_value(self, key):
+        """Returns the value of a setting, eg. 'equalizer'."""
+        return self.request("get_value", key)
+
+    def set_value(self, key, value):
+        """Set a value like `earbuds set` does, eg. 'equalizer' to 'bass'."""
+        if isinstance(value, bool):
+            value = "true" if value else "false"
+        self.request("set_value", key, str(value))
+
+    def toggle(self, key):
+        """Invert a boolean setting, eg. 'noise_reduction'."""
+        self.request("toggle_value", key, "")
+
+    def set_anc(self, enabled):
+        self.set_value("noise_reduction", enabled)
+
+    def subscribe(self, snapshot=False, only=None):
+        """Yields events as dicts until the daemon closes the connection.
+        With `snapshot`, the current status is sent first. `only` limits
+        the events to categories like ['battery', 'wear', 'anc']."""
+        sock = self._send(
+            "subscribe", "snapshot" if snapshot else None, ",".join(only or [])
+        )
+        # Events can be minutes apart
+        sock.settimeout(None)
+        with sock, sock.makefile("r", encoding="utf-8") as lines:
+            for line in lines:
+                try:
+                    yield json.loads(line)
+                except ValueError:
+                    continue
+
+    def request(self, cmd, param1=None, param2=None):
+        """Do a request and return the payload of the response."""
+        with self._send(cmd, param1, param2) as sock:
+            chunks = []
+            while True:
+                chunk = sock.recv(4096)
+                if not chunk:
+                    break
+                chunks.append(chunk)
+
+        response = json.loads(b"".join(chunks).decode("utf-8"))
+        if response.get("status") != "success":
+            raise DaemonError(response.get("status_message") or "Unknown error")
+        return response.get("payload")
+
+    def _send(self, cmd, param1=None, param2=None):
+        request = {
+            "cmd": cmd,
+            "device": self.device,
+            "opt_param1": param1,
+            "opt_param2": param2,
+            "opt_param3": None,
+        }
+
+        sock = socket.socket(socket.AF_UNIX, socket.SOCK_STREAM)
+        sock.settimeout(self.timeout)
+        try:
+            sock.connect(self.path)
+            sock.sendall((json.dumps(request) + "\n").encode("utf-8"))
+        except OSError:
+            sock.close()
+            raise
+        return sock
+
+
+if __name__ == "__main__":
+    print(json.dumps(Client().status()))
@@ -0,0 +1,243 @@
+/*
+ * Simulates the buds side of the SPP protocol over a local unix socket.
+ * Start it with a scenario file and point the daemon to the socket using
+ * the EARBUDS_SIMULATOR environment variable.
+ */
+
+#[path = "../daemon/crc.rs"]
+mod crc;
+
+use crc::crc16;
+use serde::Deserialize;
+
+use std::io::{Read, Write};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::process::exit;
+use std::thread;
+use std::time::Duration;
+
+const DEFAULT_SOCKET: &str = "/tmp/budsim.sock";
+
+// Message framing (Buds+ and newer)
+const SOM: u8 = 0xFD;
+const EOM: u8 = 0xDD;
+
+// Message ids
+const STATUS_UPDATED: u8 = 0x60;
+const EXTENDED_STATUS_UPDATED: u8 = 0x61;
+
+/// A scenario the simulator plays to every connected client
+#[derive(Debug, Deserialize, Clone)]
+struct Scenario {
+    /// Seconds between two status updates
+    #[serde(default = "default_interval")]
+    interval: u64,
+    #[serde(default = "full_battery")]
+    battery_left: i8,
+    #[serde(default = "full_battery")]
+    battery_right: i8,
+    #[serde(default = "full_battery")]
+    battery_case: i8,
+    /// Battery percent drained per tick while a bud is worn
+    #[serde(default)]
+    drain: i8,
+    /// Battery percent charged per tick while a bud is in the case
+    #[serde(default)]
+    charge: i8,
+    /// Stop after this amount of ticks. Runs forever if not set
+    ticks: Option<u64>,
+    #[serde(default)]
+    events: Vec<WearEvent>,
+}
+
+/// Changes the placement of a bud at a given tick
+#[derive(Debug, Deserialize, Clone)]
+struct WearEvent {
+    tick: u64,
+    left: Option<String>,
+    right: Option<String>,
+}
+
+fn default_interval() -> u64 {
+    2
+}
+
+fn full_battery() -> i8 {
+    100
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 2 {
+        eprintln!("Usage: budsim <scenario.toml> [socket]");
+        exit(1);
+    }
+
+    let scenario = match load_scenario(&args[1]) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("Couldn't load scenario: {}", err);
+            exit(1);
+        }
+    };
+
+    let socket_path = args.get(2).map(|i| i.as_str()).unwrap_or(DEFAULT_SOCKET);
+    if Path::new(socket_path).exists() {
+        std::fs::remove_file(socket_path).expect("Can't delete old socket file");
+    }
+
+    let listener = UnixListener::bind(socket_path).expect("Can't bind simulator socket");
+    println!("Simulator listening on {}", socket_path);
+
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(s) => s,
+            Err(err) => {
+                eprintln!("Error accepting client: {:?}", err);
+                continue;
+            }
+        };
+
+        let scenario = scenario.clone();
+        thread::spawn(move || {
+            if let Err(err) = play(stream, scenario) {
+                println!("Client disconnected: {}", err);
+            }
+        });
+    }
+}
+
+fn load_scenario(path: &str) -> Result<Scenario, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    toml::from_str(&content).map_err(|e| e.to_string())
+}
+
+/// Play a scenario to a connected daemon
+fn play(mut stream: UnixStream, scenario: Scenario) -> Result<(), String> {
+    // The daemon sends requests (eg. debug data) we don't answer. Read and drop them
+    let mut reader = stream.try_clone().map_err(|e| e.to_string())?;
+    thread::spawn(move || {
+        let mut buf = [0u8; 512];
+        while let Ok(n) = reader.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+        }
+    });
+
+    let mut state = State {
+        battery_left: scenario.battery_left,
+        battery_right: scenario.battery_right,
+        battery_case: scenario.battery_case,
+        placement_left: placement_from_str("case"),
+        placement_right: placement_from_str("case"),
+    };
+
+    // Daemon marks the device as ready after the first extended status update
+    write_frame(
+        &mut stream,
+        EXTENDED_STATUS_UPDATED,
+        &state.extended_payload(),
+    )?;
+
+    let mut tick = 0;
+    loop {
+        for event in scenario.events.iter().filter(|i| i.tick == tick) {
+            if let Some(ref left) = event.left {
+                state.placement_left = placement_from_str(left);
+            }
+            if let Some(ref right) = event.right {
+                state.placement_right = placement_from_str(right);
+            }
+        }
+
+        state.battery_left = next_battery(state.battery_left, state.placement_left, &scenario);
+        state.battery_right = next_battery(state.battery_right, state.placement_right, &scenario);
+
+        write_frame(&mut stream, STATUS_UPDATED, &state.status_payload())?;
+
+        tick += 1;
+        if scenario.ticks.map(|i| tick >= i).unwrap_or(false) {
+            return Ok(());
+        }
+
+        thread::sleep(Duration::from_secs(scenario.interval));
+    }
+}
+
+/// Simulated state of the buds
+struct State {
+    battery_left: i8,
+    battery_right: i8,
+    battery_case: i8,
+    placement_left: u8,
+    placement_right: u8,
+}
+
+impl State {
+    fn status_payload(&self) -> Vec<u8> {
+        vec![
+            1, // revision
+            self.battery_left as u8,
+            self.battery_right as u8,
+            1, // coupled
+            0, // primary earbud
+            (self.placement_left << 4) | self.placement_right,
+            self.battery_case as u8,
+        ]
+    }
+
+    fn extended_payload(&self) -> Vec<u8> {
+        let mut payload = vec![
+            1, // revision
+            0, // ear type
+            self.battery_left as u8,
+            self.battery_right as u8,
+            1, // coupled
+            0, // primary earbud
+            (self.placement_left << 4) | self.placement_right,
+            self.battery_case as u8,
+        ];
+
+        // Remaining settings stay at their defaults
+        payload.resize(32, 0);
+        payload
+    }
+}
+
+// Calculate the battery for the next tick
+fn next_battery(battery: i8, placement: u8, scenario: &Scenario) -> i8 {
+    match placement {
+        1 => battery.saturating_sub(scenario.drain).clamp(0, 100),
+        3 | 4 => battery.saturating_add(scenario.charge).clamp(0, 100),
+        _ => battery,
+    }
+}
+
+fn placement_from_str(s: &str) -> u8 {
+    match s.to_lowercase().as_str() {
+        "ear" => 1,
+        "outside" => 2,
+        "case" | "open-case" => 3,
+        "closed-case" => 4,
+        _ => 0,
+    }
+}
+
+/// Write a single message frame to the stream
+fn write_frame(stream: &mut UnixStream, id: u8, payload: &[u8]) -> Result<(), String> {
+    let size = (payload.len() + 3) as u16;
+
+    let mut body = vec![id];
+    body.extend_from_slice(payload);
+    let crc = crc16(&body);
+
+    let mut frame = vec![SOM, (size & 0xFF) as u8, (size >> 8) as u8];
+    frame.extend_from_slice(&body);
+    frame.push((crc & 0xFF) as u8);
+    frame.push((crc >> 8) as u8);
+    frame.push(EOM);
+
+    stream.write_all(&frame).map_err(|e| e.to_string())
+}
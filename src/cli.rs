@@ -20,7 +20,8 @@ pub fn build<'a>(_s: &str) -> App<'a> {
                 .short('o')
                 .long("output")
                 .global(true)
-                .possible_values(&["json", "normal"]),
+                .possible_values(&["json", "normal", "waybar", "polybar", "i3blocks"])
+                .help("Output format. waybar, polybar and i3blocks are modules for status bars, only used by status"),
         )
         .arg(
             Arg::new("generator")
@@ -39,6 +40,12 @@ pub fn build<'a>(_s: &str) -> App<'a> {
                 .help("Don't fork the daemon")
                 .long("no-fork"),
         )
+        .arg(
+            Arg::new("takeover")
+                .help("Take over the devices of a running daemon, eg. after an upgrade")
+                .long("takeover")
+                .requires("daemon"),
+        )
         .arg(
             Arg::new("kill-daemon")
                 .help("Kill the daemon. If used together with -d, the daemon will get restarted")
@@ -52,6 +59,13 @@ pub fn build<'a>(_s: &str) -> App<'a> {
                 .global(true)
                 .long("quiet"),
         )
+        .arg(
+            Arg::new("timeout")
+                .help("Give up if the daemon doesn't answer within this many seconds")
+                .long("timeout")
+                .global(true)
+                .takes_value(true),
+        )
         .arg(
             Arg::new("device")
                 .global(true)
@@ -61,15 +75,66 @@ pub fn build<'a>(_s: &str) -> App<'a> {
                 .value_hint(ValueHint::Unknown)
                 .long("device"),
         )
+        .arg(
+            Arg::new("ascii")
+                .global(true)
+                .help("Only print ASCII characters, eg. for screen readers or limited terminals")
+                .long("ascii"),
+        )
+        .arg(
+            Arg::new("palette")
+                .global(true)
+                .help("Colors of the output. Defaults to 'default' on terminals unless NO_COLOR is set")
+                .long("palette")
+                .takes_value(true)
+                .possible_values(&["default", "colorblind", "none"]),
+        )
         .subcommand(
             App::new("status")
                 .setting(AppSettings::ColoredHelp)
                 .alias("info")
-                .help("Display informations for a given device"),
+                .help("Display informations for a given device")
+                .arg(
+                    Arg::new("brief")
+                        .long("brief")
+                        .short('b')
+                        .help("Print a single line like [ANC|Bass|L64 R71] for window manager bars"),
+                )
+                .arg(
+                    Arg::new("segments")
+                        .long("segments")
+                        .takes_value(true)
+                        .requires("brief")
+                        .help("Comma separated segments of --brief: anc, eq, battery, case, wear (default anc,eq,battery)"),
+                )
+                .arg(
+                    Arg::new("separator")
+                        .long("separator")
+                        .takes_value(true)
+                        .requires("brief")
+                        .help("Separator between the segments of --brief (default |)"),
+                )
+                .arg(
+                    Arg::new("offline")
+                        .long("offline")
+                        .takes_value(true)
+                        .requires("brief")
+                        .help("Text of --brief if no buds are connected (default -)"),
+                ),
         )
         .subcommand(
             App::new("set")
                 .setting(AppSettings::ArgRequiredElseHelp)
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Print the messages which would be sent without sending them"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Send the value even if it is already set"),
+                )
                 .setting(AppSettings::ColoredHelp)
                 .help("Turn on/off features and control the equalizer setting")
                 .arg(
@@ -82,7 +147,11 @@ pub fn build<'a>(_s: &str) -> App<'a> {
                             "touchpadlock",
                             "touchpad",
                             "ambientsound",
+                            "ambient",
                             "tap-action",
+                            "hold-action",
+                            "ambient-calls",
+                            "noise-control",
                         ]),
                 )
                 .arg(Arg::new("value").required(true).takes_value(true))
@@ -92,40 +161,162 @@ pub fn build<'a>(_s: &str) -> App<'a> {
                         .takes_value(true),
                 ),
         )
+        .subcommand(
+            App::new("apply")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Set multiple values from a json or toml file at once")
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Print the messages which would be sent without sending them"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Send values even if they are already set"),
+                )
+                .arg(
+                    Arg::new("file")
+                        .required(true)
+                        .takes_value(true)
+                        .value_hint(ValueHint::FilePath)
+                        .help("Settings file. Use '-' to read from stdin"),
+                ),
+        )
+        .subcommand(
+            App::new("pair")
+                .setting(AppSettings::ColoredHelp)
+                .help("Pair buds in pairing mode and add them to the config")
+                .arg(
+                    Arg::new("search")
+                        .long("search")
+                        .takes_value(true)
+                        .help("How long to search for buds. Defaults to 20"),
+                ),
+        )
+        .subcommand(
+            App::new("lost")
+                .setting(AppSettings::ColoredHelp)
+                .help("List buds which lost the connection while being worn"),
+        )
+        .subcommand(
+            App::new("import-wearable")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Import the settings of a Galaxy Wearable backup as desired state")
+                .arg(
+                    Arg::new("print")
+                        .long("print")
+                        .help("Print the settings as config table instead of saving them"),
+                )
+                .arg(
+                    Arg::new("backup")
+                        .required(true)
+                        .takes_value(true)
+                        .value_hint(ValueHint::FilePath)
+                        .help("Shared preferences xml of the buds plugin"),
+                ),
+        )
+        .subcommand(
+            App::new("get")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Print the current value of a setting")
+                .arg(
+                    Arg::new("key")
+                        .required(true)
+                        .takes_value(true)
+                        .possible_values(&[
+                            "equalizer",
+                            "anc",
+                            "touchpadlock",
+                            "touchpad",
+                            "ambientsound",
+                            "ambient",
+                            "tap-action",
+                            "hold-action",
+                            "ambient-calls",
+                            "noise-control",
+                        ]),
+                )
+                .arg(
+                    Arg::new("opt")
+                        .help("Provide additional input for some keys")
+                        .takes_value(true),
+                ),
+        )
         .subcommand(
             App::new("enable")
                 .setting(AppSettings::ArgRequiredElseHelp)
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Print the messages which would be sent without sending them"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Send the value even if it is already set"),
+                )
                 .setting(AppSettings::ColoredHelp)
                 .help("Turn off a given features")
                 .arg(
                     Arg::new("key")
                         .required(true)
                         .takes_value(true)
-                        .possible_values(&["anc", "touchpad"]),
+                        .possible_values(&["anc", "touchpad", "ambient", "ambient-calls"]),
                 ),
         )
         .subcommand(
             App::new("disable")
                 .setting(AppSettings::ArgRequiredElseHelp)
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Print the messages which would be sent without sending them"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Send the value even if it is already set"),
+                )
                 .setting(AppSettings::ColoredHelp)
                 .help("Turn off a given features")
                 .arg(
                     Arg::new("key")
                         .required(true)
                         .takes_value(true)
-                        .possible_values(&["equalizer", "anc", "touchpad"]),
+                        .possible_values(&[
+                            "equalizer",
+                            "anc",
+                            "touchpad",
+                            "ambient",
+                            "ambient-calls",
+                        ]),
                 ),
         )
         .subcommand(
             App::new("toggle")
                 .setting(AppSettings::ArgRequiredElseHelp)
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Print the messages which would be sent without sending them"),
+                )
                 .setting(AppSettings::ColoredHelp)
                 .help("Toggle the state of a feature")
                 .arg(
                     Arg::new("key")
                         .required(true)
                         .takes_value(true)
-                        .possible_values(&["anc", "touchpadlock", "touchpad"]),
+                        .possible_values(&[
+                            "anc",
+                            "touchpadlock",
+                            "touchpad",
+                            "ambient",
+                            "ambient-calls",
+                        ]),
                 ),
         )
         .subcommand(
@@ -147,11 +338,406 @@ pub fn build<'a>(_s: &str) -> App<'a> {
                                     "auto-play",
                                     "low-battery-notification",
                                     "smart-sink",
+                                    "touchpad-lock-in-case",
+                                    "connect-notification",
+                                    "session-summary-notification",
+                                    "auto-mic",
+                                    "auto-profile",
                                 ]),
                         )
                         .arg(Arg::new("value").required(true).takes_value(true)),
                 ),
         )
+        .subcommand(
+            App::new("settings")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Export or import all settings of your buds")
+                .subcommand(
+                    App::new("export")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Write all settings as json snapshot")
+                        .arg(
+                            Arg::new("file")
+                                .takes_value(true)
+                                .value_hint(ValueHint::FilePath)
+                                .help("File to write to. Prints to stdout if not set"),
+                        ),
+                )
+                .subcommand(
+                    App::new("import")
+                        .setting(AppSettings::ArgRequiredElseHelp)
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Apply a previously exported snapshot")
+                        .arg(
+                            Arg::new("file")
+                                .required(true)
+                                .takes_value(true)
+                                .value_hint(ValueHint::FilePath)
+                                .help("Snapshot file. Use '-' to read from stdin"),
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("debug")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Tools for debugging your setup")
+                .subcommand(
+                    App::new("rtt")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Measure the round trip time of requests to the buds")
+                        .arg(
+                            Arg::new("samples")
+                                .long("samples")
+                                .short('n')
+                                .takes_value(true)
+                                .help("Amount of requests to send (default 5, at most 50)"),
+                        ),
+                )
+                .subcommand(
+                    App::new("telemetry")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Print voltage, current and temperature of the buds as json lines")
+                        .arg(
+                            Arg::new("follow")
+                                .short('f')
+                                .long("follow")
+                                .help("Keep printing new samples"),
+                        )
+                        .arg(
+                            Arg::new("interval")
+                                .long("interval")
+                                .short('i')
+                                .takes_value(true)
+                                .help("Seconds between samples (default 2)"),
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("mic")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Manage the microphone of your buds")
+                .subcommand(
+                    App::new("list")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("List the input sources of your buds"),
+                )
+                .subcommand(
+                    App::new("set-default")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Use the mic of your buds as default input"),
+                ),
+        )
+        .subcommand(
+            App::new("mute-notifications")
+                .setting(AppSettings::ColoredHelp)
+                .help("Silence all notifications of your buds, eg. during a meeting")
+                .arg(
+                    Arg::new("for")
+                        .long("for")
+                        .takes_value(true)
+                        .conflicts_with("off")
+                        .help("Unmute automatically after a time like 30m or 2h"),
+                )
+                .arg(
+                    Arg::new("off")
+                        .long("off")
+                        .help("Show notifications again"),
+                ),
+        )
+        .subcommand(
+            App::new("dbus-xml")
+                .setting(AppSettings::ColoredHelp)
+                .help("Print the introspection XML of the stable D-Bus interface"),
+        )
+        .subcommand(
+            App::new("service")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Start the daemon automatically")
+                .subcommand(
+                    App::new("install")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Start the daemon with your session")
+                        .arg(
+                            Arg::new("autostart")
+                                .long("autostart")
+                                .help("Install an XDG autostart entry (for non-systemd sessions)"),
+                        ),
+                )
+                .subcommand(
+                    App::new("uninstall")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Remove installed autostart entries"),
+                ),
+        )
+        .subcommand(
+            App::new("stats")
+                .setting(AppSettings::ColoredHelp)
+                .help("Show statistics of the connection to your buds")
+                .subcommand(
+                    App::new("connections")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Summarize connection drops per day"),
+                ),
+        )
+        .subcommand(
+            App::new("top")
+                .setting(AppSettings::ColoredHelp)
+                .help("Show message rates, queue depths and handler latencies of the daemon")
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .short('i')
+                        .takes_value(true)
+                        .help("Seconds between refreshes (default 1)"),
+                ),
+        )
+        .subcommand(
+            App::new("remote")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Manage clients of the HTTP endpoint")
+                .subcommand(
+                    App::new("pair")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Show a one-time code to pair a new client"),
+                )
+                .subcommand(
+                    App::new("revoke")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Forget all paired clients"),
+                ),
+        )
+        .subcommand(
+            App::new("raw")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Send and watch raw messages, eg. to try undocumented ones")
+                .arg(
+                    Arg::new("i-know-what-im-doing")
+                        .long("i-know-what-im-doing")
+                        .global(true)
+                        .help("Confirm that wrong messages may change or break settings of the buds"),
+                )
+                .subcommand(
+                    App::new("send")
+                        .setting(AppSettings::ArgRequiredElseHelp)
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Send a message, eg. `earbuds raw send 0x78 01`")
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Print the frame which would be sent without sending it"),
+                        )
+                        .arg(
+                            Arg::new("id")
+                                .required(true)
+                                .takes_value(true)
+                                .help("Message id, eg. 0x78"),
+                        )
+                        .arg(
+                            Arg::new("payload")
+                                .takes_value(true)
+                                .multiple_values(true)
+                                .help("Payload as hex bytes, eg. 01 or '01 ff'"),
+                        ),
+                )
+                .subcommand(
+                    App::new("watch")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Print received messages as json lines")
+                        .arg(
+                            Arg::new("id")
+                                .takes_value(true)
+                                .help("Only print messages with this id"),
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("ambient")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Change the ambient sound level")
+                .subcommand(
+                    App::new("set")
+                        .setting(AppSettings::ArgRequiredElseHelp)
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Set the ambient sound level. Level 0 turns it off")
+                        .arg(Arg::new("level").required(true).takes_value(true)),
+                )
+                .subcommand(
+                    App::new("up")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Raise the ambient sound level by one"),
+                )
+                .subcommand(
+                    App::new("down")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Lower the ambient sound level by one. Level 0 turns it off"),
+                ),
+        )
+        .subcommand(
+            App::new("eq")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Compare equalizer presets")
+                .subcommand(
+                    App::new("cycle")
+                        .setting(AppSettings::ColoredHelp)
+                        .help("Step through all presets. The previous one gets restored afterwards or on Ctrl-C")
+                        .arg(
+                            Arg::new("interval")
+                                .long("interval")
+                                .short('i')
+                                .takes_value(true)
+                                .help("Seconds to play each preset (default 5)"),
+                        ),
+                ),
+        )
+        .subcommand(
+            App::new("fit-test")
+                .setting(AppSettings::ColoredHelp)
+                .help("Check how well your buds fit. Wear both buds while running it"),
+        )
+        .subcommand(
+            App::new("test-channels")
+                .setting(AppSettings::ColoredHelp)
+                .help("Play a tone on the left and right channel to check if the buds are swapped"),
+        )
+        .subcommand(
+            App::new("logs")
+                .setting(AppSettings::ColoredHelp)
+                .help("Show the recent log of the daemon")
+                .arg(
+                    Arg::new("follow")
+                        .short('f')
+                        .long("follow")
+                        .help("Keep printing new log lines"),
+                ),
+        )
+        .subcommand(
+            App::new("menu")
+                .setting(AppSettings::ColoredHelp)
+                .help("Pick a common action from a menu")
+                .arg(
+                    Arg::new("dmenu")
+                        .long("dmenu")
+                        .takes_value(true)
+                        .help("Show the menu with a dmenu compatible program, eg. 'rofi -dmenu'"),
+                ),
+        )
+        .subcommand(
+            App::new("devices")
+                .setting(AppSettings::ColoredHelp)
+                .help("List connected and paired buds"),
+        )
+        .subcommand(
+            App::new("find")
+                .setting(AppSettings::ColoredHelp)
+                .help("Let the buds beep to find them. Stops on Enter")
+                .arg(
+                    Arg::new("bud")
+                        .takes_value(true)
+                        .possible_values(&["left", "right", "both"])
+                        .help("Bud which should beep. Defaults to both"),
+                )
+                .arg(
+                    Arg::new("stop")
+                        .long("stop")
+                        .help("Only stop a beep started elsewhere"),
+                ),
+        )
+        .subcommand(
+            App::new("grab")
+                .setting(AppSettings::ColoredHelp)
+                .help("Take the buds over from another host like the phone by reconnecting"),
+        )
+        .subcommand(
+            App::new("keys")
+                .setting(AppSettings::ColoredHelp)
+                .help("List the settings and config options of the device with their values"),
+        )
+        .subcommand(
+            App::new("features")
+                .setting(AppSettings::ColoredHelp)
+                .help("List optional features of the daemon and whether they're active"),
+        )
+        .subcommand(
+            App::new("prompt")
+                .setting(AppSettings::ColoredHelp)
+                .help("Print a short battery segment for shell prompts from the status file")
+                .arg(
+                    Arg::new("icon")
+                        .long("icon")
+                        .takes_value(true)
+                        .help("Icon in front of the battery (default 🎧)"),
+                )
+                .arg(
+                    Arg::new("low-icon")
+                        .long("low-icon")
+                        .takes_value(true)
+                        .help("Icon if the battery is low (default 🪫)"),
+                )
+                .arg(
+                    Arg::new("low")
+                        .long("low")
+                        .takes_value(true)
+                        .help("Percentage from which the battery is low (default 20)"),
+                )
+                .arg(
+                    Arg::new("show-below")
+                        .long("show-below")
+                        .takes_value(true)
+                        .help("Only print the segment if the battery is at most this percentage"),
+                )
+                .arg(
+                    Arg::new("worn-only")
+                        .long("worn-only")
+                        .help("Only print the segment while the buds are worn"),
+                ),
+        )
+        .subcommand(
+            App::new("doctor")
+                .setting(AppSettings::ColoredHelp)
+                .help("Check which desktop integrations the daemon can use"),
+        )
+        .subcommand(
+            App::new("watch")
+                .setting(AppSettings::ColoredHelp)
+                .help("Print status changes as json lines as they happen")
+                .arg(
+                    Arg::new("snapshot")
+                        .long("snapshot")
+                        .help("Print the full status before the first change"),
+                )
+                .arg(
+                    Arg::new("only")
+                        .long("only")
+                        .takes_value(true)
+                        .help("Only print these comma separated categories (battery, wear, anc, ambient, connection) or events"),
+                ),
+        )
+        .subcommand(
+            App::new("watch-battery")
+                .setting(AppSettings::ArgRequiredElseHelp)
+                .setting(AppSettings::ColoredHelp)
+                .help("Wait until both buds are charged to a level, eg. in scripts")
+                .arg(
+                    Arg::new("until")
+                        .long("until")
+                        .required(true)
+                        .takes_value(true)
+                        .help("Battery level in percent both buds have to reach"),
+                )
+                .arg(
+                    Arg::new("notify")
+                        .long("notify")
+                        .help("Show a desktop notification once reached"),
+                ),
+        )
         // Connect
         .subcommand(
             App::new("connect")
@@ -0,0 +1,103 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::{parse, unix_socket::Response};
+
+use clap::ArgMatches;
+
+// Highest level of all models. The daemon checks the one of the connected model
+const MAX_LEVEL: u8 = 4;
+
+/// Raise or lower the ambient sound level by one step, eg. from a keybinding
+pub fn step(sc: &mut SocketClient, app: &ArgMatches, up: bool) {
+    let device = utils::get_device_from_app(&app);
+
+    let status = match sc.do_request(socket_client::new_status_request(device.clone())) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+    let status = socket_client::to_buds_info(status);
+    let info = match utils::unwrap_response(&status) {
+        Some(v) => v,
+        None => return,
+    };
+
+    // Level 0 means ambient sound is off
+    let current = if info.ambient_sound_enabled {
+        info.ambient_sound_volume
+    } else {
+        0
+    };
+    let max = info.get_max_ambientsound_volume_level();
+    let level = if up {
+        current.saturating_add(1).min(max)
+    } else {
+        current.saturating_sub(1)
+    };
+
+    if level == current {
+        println!("Ambient sound already at {}", level_str(level));
+        return;
+    }
+
+    // Each request needs its own connection
+    if let Err(err) = sc.reconnect() {
+        eprintln!("{:?}", err);
+        return;
+    }
+
+    send_level(sc, app, device, level);
+}
+
+/// Set the ambient sound level. The daemon checks the maximum level of the model
+pub fn set(sc: &mut SocketClient, app: &ArgMatches) {
+    let level = match parse::level(app.value_of("level").unwrap_or_default(), MAX_LEVEL) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    };
+
+    send_level(sc, app, utils::get_device_from_app(&app), level);
+}
+
+fn send_level(sc: &mut SocketClient, app: &ArgMatches, device: Option<String>, level: u8) {
+    let request = socket_client::new_set_value_request(
+        device,
+        "ambient_volume".to_owned(),
+        level.to_string(),
+        false,
+    );
+    let res = match sc.do_request(request) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    match Response::<serde_json::Value>::from_string(&res) {
+        Ok(res) if res.is_success() => println!("Ambient sound: {}", level_str(level)),
+        Ok(res) => println!("Error: {}", res.status_message.unwrap_or_default()),
+        Err(err) => println!("Error: {}", err),
+    }
+}
+
+fn level_str(level: u8) -> String {
+    if level == 0 {
+        "off".to_owned()
+    } else {
+        format!("level {}", level)
+    }
+}
@@ -0,0 +1,128 @@
+use super::{
+    set_value,
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::unix_socket::set_value::{BatchEntry, BatchResult};
+
+use clap::ArgMatches;
+use serde_json::Value;
+
+use std::io::Read;
+
+/// Set all values of a json or toml document in a single request. Keys and values are
+/// the ones 'set' accepts, tables set sides, eg.:
+///
+/// anc = true
+/// equalizer = "dynamic"
+/// [tap-action]
+/// left = "volume"
+pub fn apply(sc: &mut SocketClient, app: &ArgMatches) {
+    let file = app.value_of("file").unwrap();
+
+    let content = if file == "-" {
+        let mut s = String::new();
+        std::io::stdin().read_to_string(&mut s).map(|_| s)
+    } else {
+        std::fs::read_to_string(file)
+    };
+
+    let entries = match content
+        .map_err(|e| e.to_string())
+        .and_then(|s| parse(&s))
+        .and_then(|v| to_entries(&v))
+    {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("Couldn't read settings: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let mut request = socket_client::new_apply_request(utils::get_device_from_app(&app), &entries);
+    request.dry_run = app.is_present("dry-run");
+    request.force = app.is_present("force");
+
+    let res = match sc.do_request(request) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    if app.is_present("dry-run") {
+        set_value::print_dry_run(&res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Vec<BatchResult>>(&res);
+    let results = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    for result in results {
+        let name = match result.param {
+            Some(param) => format!("{} ({})", result.key, param),
+            None => result.key,
+        };
+        let state = if result.changed {
+            "changed"
+        } else {
+            "already set"
+        };
+        println!("{}:\t{}", name, state);
+    }
+}
+
+// Parse a json or toml document
+fn parse(s: &str) -> Result<Value, String> {
+    if let Ok(v) = serde_json::from_str(s) {
+        return Ok(v);
+    }
+
+    let v: toml::Value = toml::from_str(s).map_err(|e| e.to_string())?;
+    serde_json::to_value(v).map_err(|e| e.to_string())
+}
+
+// Convert a document into batch entries. Nested tables set a parameter, eg. the side
+fn to_entries(document: &Value) -> Result<Vec<BatchEntry>, String> {
+    let document = document.as_object().ok_or("Expected a table of settings")?;
+
+    let mut entries = Vec::new();
+    for (key, value) in document {
+        match value {
+            Value::Object(params) => {
+                for (param, value) in params {
+                    entries.push(to_entry(key, Some(param), value)?);
+                }
+            }
+            _ => entries.push(to_entry(key, None, value)?),
+        }
+    }
+
+    Ok(entries)
+}
+
+fn to_entry(key: &str, param: Option<&str>, value: &Value) -> Result<BatchEntry, String> {
+    let value = match value {
+        Value::Bool(v) => v.to_string(),
+        Value::Number(v) => v.to_string(),
+        Value::String(v) => v.clone(),
+        _ => return Err(format!("Invalid value for {}", key)),
+    };
+
+    let (key, value) = set_value::to_request_value(key, &value)?;
+    Ok(BatchEntry {
+        key,
+        param: param.map(|i| i.to_owned()),
+        value,
+    })
+}
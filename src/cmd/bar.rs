@@ -0,0 +1,161 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::buds_info::BudsInfoInner;
+
+use clap::ArgMatches;
+use galaxy_buds_rs::message::bud_property::Placement;
+use serde_json::json;
+
+pub const FORMATS: [&str; 3] = ["waybar", "polybar", "i3blocks"];
+
+const ICON: &str = "🎧";
+const LOW_BATTERY: i8 = 20;
+const CRITICAL_BATTERY: i8 = 5;
+const COLOR_LOW: &str = "#ffaa00";
+const COLOR_CRITICAL: &str = "#ff5555";
+// Makes i3blocks highlight the block
+const I3BLOCKS_URGENT: i32 = 33;
+
+/// Print the status in the format of a status bar module
+pub fn show(sc: &mut SocketClient, app: &ArgMatches, format: &str) {
+    let info = sc
+        .do_request(socket_client::new_status_request(
+            utils::get_device_from_app(&app),
+        ))
+        .ok()
+        .map(socket_client::to_buds_info)
+        .filter(|i| i.is_success())
+        .and_then(|i| i.payload);
+
+    // Bars keep showing the last output, so print something if disconnected
+    let info = match info {
+        Some(info) => info,
+        None => {
+            match format {
+                "waybar" => println!(
+                    "{}",
+                    json!({ "text": "", "tooltip": "No buds connected", "class": "disconnected" })
+                ),
+                _ => println!(),
+            }
+            return;
+        }
+    };
+
+    let text = format!(
+        "{} {} {}",
+        ICON,
+        battery(info.batt_left, info.placement_left),
+        battery(info.batt_right, info.placement_right),
+    );
+    let text = match noise_control(&info) {
+        (_, "Off") => text,
+        (_, name) => format!("{} {}", text, name),
+    };
+
+    match format {
+        "waybar" => {
+            let (alt, _) = noise_control(&info);
+            let mut class = vec![alt];
+            if let Some(level) = battery_level(&info) {
+                class.push(level);
+            }
+
+            println!(
+                "{}",
+                json!({
+                    "text": text,
+                    "alt": alt,
+                    "tooltip": tooltip(&info),
+                    "class": class,
+                    "percentage": info.batt_left.min(info.batt_right).max(0),
+                })
+            );
+        }
+        "polybar" => match color(&info) {
+            Some(color) => println!("%{{F{}}}{}%{{F-}}", color, text),
+            None => println!("{}", text),
+        },
+        "i3blocks" => {
+            // Full text, short text and color
+            println!("{}", text);
+            println!("{} {}%", ICON, info.batt_left.min(info.batt_right).max(0));
+            if let Some(color) = color(&info) {
+                println!("{}", color);
+            }
+
+            if battery_level(&info) == Some("critical") {
+                std::process::exit(I3BLOCKS_URGENT);
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+// Buds in the case are charging
+fn battery(level: i8, placement: Placement) -> String {
+    if in_case(placement) {
+        format!("⚡{}%", level)
+    } else {
+        format!("{}%", level)
+    }
+}
+
+// Returns the css class and name of the noise control mode
+fn noise_control(info: &BudsInfoInner) -> (&'static str, &'static str) {
+    if info.noise_reduction {
+        ("anc", "ANC")
+    } else if info.ambient_sound_enabled {
+        ("ambient", "Ambient")
+    } else {
+        ("off", "Off")
+    }
+}
+
+// Returns "low" or "critical" if a bud which isn't charging runs out of battery
+fn battery_level(info: &BudsInfoInner) -> Option<&'static str> {
+    let lowest = [
+        (info.batt_left, info.placement_left),
+        (info.batt_right, info.placement_right),
+    ]
+    .iter()
+    .filter(|(_, placement)| !in_case(*placement))
+    .map(|(level, _)| *level)
+    .min()?;
+
+    if lowest <= CRITICAL_BATTERY {
+        Some("critical")
+    } else if lowest <= LOW_BATTERY {
+        Some("low")
+    } else {
+        None
+    }
+}
+
+fn color(info: &BudsInfoInner) -> Option<&'static str> {
+    match battery_level(info)? {
+        "critical" => Some(COLOR_CRITICAL),
+        _ => Some(COLOR_LOW),
+    }
+}
+
+fn tooltip(info: &BudsInfoInner) -> String {
+    let mut lines = vec![
+        info.alias
+            .clone()
+            .unwrap_or_else(|| info.model_name.clone()),
+        format!("Left: {}%", info.batt_left),
+        format!("Right: {}%", info.batt_right),
+    ];
+    if in_case(info.placement_left) || in_case(info.placement_right) {
+        lines.push(format!("Case: {}%", info.batt_case));
+    }
+    lines.push(format!("Noise control: {}", noise_control(info).1));
+    lines.join("\n")
+}
+
+fn in_case(placement: Placement) -> bool {
+    placement == Placement::InOpenCase || placement == Placement::InCloseCase
+}
@@ -0,0 +1,94 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::buds_info::BudsInfoInner;
+
+use clap::ArgMatches;
+use galaxy_buds_rs::message::bud_property::{EqualizerType, Placement};
+
+pub const DEFAULT_SEGMENTS: &str = "anc,eq,battery";
+const SEGMENTS: [&str; 5] = ["anc", "eq", "battery", "case", "wear"];
+
+/// Print the status as a single line for window manager bars, eg. `[ANC|Bass|L64 R71]`
+pub fn show(sc: &mut SocketClient, app: &ArgMatches) {
+    let segments: Vec<&str> = app
+        .value_of("segments")
+        .unwrap_or(DEFAULT_SEGMENTS)
+        .split(',')
+        .map(|i| i.trim())
+        .filter(|i| !i.is_empty())
+        .collect();
+    if let Some(invalid) = segments.iter().find(|i| !SEGMENTS.contains(i)) {
+        eprintln!(
+            "Invalid segment '{}'. Use any of {}",
+            invalid,
+            SEGMENTS.join(", ")
+        );
+        return;
+    }
+
+    let status = match sc.do_request(socket_client::new_status_request(
+        utils::get_device_from_app(&app),
+    )) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // Bars keep showing the last line, so print something on errors as well
+    let status = socket_client::to_buds_info(status);
+    let info = match status.payload {
+        Some(info) if status.is_success() => info,
+        _ => {
+            println!("[{}]", app.value_of("offline").unwrap_or("-"));
+            return;
+        }
+    };
+
+    let separator = app.value_of("separator").unwrap_or("|");
+    let tokens: Vec<String> = segments
+        .iter()
+        .map(|i| segment(i, &info))
+        .filter(|i| !i.is_empty())
+        .collect();
+    println!("[{}]", tokens.join(separator));
+}
+
+// Format a single segment. Empty segments get left out
+fn segment(name: &str, info: &BudsInfoInner) -> String {
+    match name {
+        "anc" if info.noise_reduction => "ANC".to_owned(),
+        "anc" if info.ambient_sound_enabled => format!("Amb{}", info.ambient_sound_volume),
+        "anc" => "Off".to_owned(),
+        "eq" => equalizer_str(info.equalizer_type).to_owned(),
+        "battery" => format!("L{} R{}", info.batt_left, info.batt_right),
+        "case" if in_case(info) => format!("C{}", info.batt_case),
+        "wear" if is_worn(info) => "Worn".to_owned(),
+        _ => String::new(),
+    }
+}
+
+fn is_worn(info: &BudsInfoInner) -> bool {
+    info.placement_left == Placement::Ear || info.placement_right == Placement::Ear
+}
+
+// The case battery is only known while a bud is in it
+fn in_case(info: &BudsInfoInner) -> bool {
+    let in_case = |p: Placement| p == Placement::InOpenCase || p == Placement::InCloseCase;
+    in_case(info.placement_left) || in_case(info.placement_right)
+}
+
+fn equalizer_str(equalizer: EqualizerType) -> &'static str {
+    match equalizer {
+        EqualizerType::Normal => "Normal",
+        EqualizerType::BassBoost => "Bass",
+        EqualizerType::Soft => "Soft",
+        EqualizerType::Dynamic => "Dyn",
+        EqualizerType::Clear => "Clear",
+        EqualizerType::TrebleBoost => "Treble",
+        _ => "?",
+    }
+}
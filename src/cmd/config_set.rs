@@ -1,6 +1,6 @@
 use super::socket_client::{self, SocketClient};
 use super::utils;
-use crate::daemon::utils::{is_str_bool, str_to_bool};
+use crate::daemon::parse;
 
 use clap::ArgMatches;
 
@@ -17,16 +17,19 @@ pub fn set(sc: &mut SocketClient, app: &ArgMatches) {
     };
 
     // Check value input
-    if !is_value_ok(value) {
-        println!("invalid value: '{}' for key: '{}'", value, skey);
-        return;
-    }
+    let value = match parse::bool(value) {
+        Ok(v) => v,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
 
     // Build request payload
     let request = socket_client::new_set_config_request(
         utils::get_device_from_app(&app),
         key.value(),
-        get_value(value),
+        value.to_string(),
     );
 
     // Do unix_socket request
@@ -55,22 +58,17 @@ pub fn set(sc: &mut SocketClient, app: &ArgMatches) {
     }
 }
 
-/// Return true if the value is allowed for the given key
-fn get_value(value: &str) -> String {
-    str_to_bool(value).to_string()
-}
-
-/// Return true if the value is allowed for the given key
-fn is_value_ok(value: &str) -> bool {
-    is_str_bool(value)
-}
-
 #[derive(Debug, Copy, Clone)]
 enum Key {
     AutoPause,
     AutoPlay,
     LowBatteryNotification,
     SmartSink,
+    TouchpadLockInCase,
+    ConnectNotification,
+    SessionSummaryNotification,
+    AutoMic,
+    AutoProfile,
 }
 
 impl Key {
@@ -80,6 +78,11 @@ impl Key {
             Key::AutoPlay => "auto_play",
             Key::LowBatteryNotification => "low_battery_notification",
             Key::SmartSink => "smart_sink",
+            Key::TouchpadLockInCase => "touchpad_lock_in_case",
+            Key::ConnectNotification => "connect_notification",
+            Key::SessionSummaryNotification => "session_summary_notification",
+            Key::AutoMic => "auto_mic",
+            Key::AutoProfile => "auto_profile",
         })
     }
 
@@ -89,6 +92,11 @@ impl Key {
             "auto-play" => Key::AutoPlay,
             "smart-sink" => Key::SmartSink,
             "low-battery-notification" => Key::LowBatteryNotification,
+            "touchpad-lock-in-case" => Key::TouchpadLockInCase,
+            "connect-notification" => Key::ConnectNotification,
+            "session-summary-notification" => Key::SessionSummaryNotification,
+            "auto-mic" => Key::AutoMic,
+            "auto-profile" => Key::AutoProfile,
             _ => return None,
         })
     }
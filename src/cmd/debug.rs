@@ -0,0 +1,99 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::{
+    parse,
+    unix_socket::{rtt::RttResult, Response},
+};
+
+use clap::ArgMatches;
+
+/// Measure the round trip time of requests to the buds
+pub fn rtt(sc: &mut SocketClient, app: &ArgMatches) {
+    let samples = match app.value_of("samples").unwrap_or("5").parse::<usize>() {
+        Ok(v) if v > 0 => v,
+        _ => {
+            println!("Invalid amount of samples");
+            return;
+        }
+    };
+
+    let res = match sc.do_request(socket_client::new_rtt_request(
+        utils::get_device_from_app(&app),
+        samples,
+    )) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<RttResult>(&res);
+    let result = match utils::unwrap_response(&res) {
+        Some(r) => r,
+        None => return,
+    };
+
+    for (i, sample) in result.samples.iter().enumerate() {
+        println!("Sample {}:\t{:.1}ms", i + 1, sample);
+    }
+
+    if result.samples.is_empty() {
+        println!("No answers received ({} lost)", result.lost);
+        return;
+    }
+
+    let min = result.samples.iter().cloned().fold(f64::MAX, f64::min);
+    let max = result.samples.iter().cloned().fold(f64::MIN, f64::max);
+    let avg = result.samples.iter().sum::<f64>() / result.samples.len() as f64;
+
+    println!();
+    println!(
+        "min/avg/max: {:.1}/{:.1}/{:.1}ms, lost: {}",
+        min, avg, max, result.lost
+    );
+}
+
+/// Print sensor data of the buds as json lines
+pub fn telemetry(sc: &mut SocketClient, app: &ArgMatches) {
+    // The daemon samples in whole seconds
+    let interval = match parse::positive_duration(app.value_of("interval").unwrap_or("2")) {
+        Ok(v) => v.as_secs().max(1),
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
+    let request = socket_client::new_telemetry_request(
+        utils::get_device_from_app(&app),
+        app.is_present("follow"),
+        interval,
+    );
+
+    let res = sc.do_streaming_request(request, |line| {
+        // Errors are sent as regular response
+        match serde_json::from_str::<Response<()>>(line) {
+            Ok(res) if !res.is_success() => {
+                println!("{}", res.status_message.unwrap_or_default());
+                false
+            }
+            _ => {
+                println!("{}", line);
+                true
+            }
+        }
+    });
+
+    if let Err(err) = res {
+        eprintln!("{:?}", err);
+    }
+}
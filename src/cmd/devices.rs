@@ -0,0 +1,46 @@
+use super::{
+    socket_client::{self, SocketClient},
+    style::{Level, Style},
+    utils,
+};
+use crate::daemon::unix_socket::devices::DeviceEntry;
+
+use clap::ArgMatches;
+
+/// List connected and paired buds
+pub fn list(sc: &mut SocketClient, app: &ArgMatches) {
+    let res = match sc.do_request(socket_client::new_devices_request()) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Vec<DeviceEntry>>(&res);
+    let devices = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    if devices.is_empty() {
+        println!("No paired buds found");
+        return;
+    }
+
+    let style = Style::from_app(&app);
+    for device in devices {
+        let name = device.name.unwrap_or_else(|| "Unknown".to_owned());
+        let state = match device.state.as_str() {
+            "connected" => style.paint(Level::Good, &device.state),
+            _ => device.state,
+        };
+        println!("{}\t{}\t{}", device.address, state, name);
+    }
+}
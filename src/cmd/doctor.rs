@@ -0,0 +1,52 @@
+use super::{
+    socket_client::{self, SocketClient},
+    style::{Level, Style},
+    utils,
+};
+use crate::daemon::integrations::Integration;
+
+use clap::ArgMatches;
+
+/// Show which desktop integrations the daemon can use and what's degraded
+/// without the missing ones
+pub fn check(sc: &mut SocketClient, app: &ArgMatches) {
+    let res = match sc.do_request(socket_client::new_integrations_request()) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Vec<Integration>>(&res);
+    let integrations = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    let style = Style::from_app(&app);
+    for integration in integrations {
+        if integration.available {
+            println!(
+                "{:<16}{:<14}{}",
+                integration.name,
+                style.paint(Level::Good, "ok"),
+                integration.detail
+            );
+        } else {
+            println!(
+                "{:<16}{:<14}{}",
+                integration.name,
+                style.paint(Level::Bad, "missing"),
+                integration.detail
+            );
+            println!("{:<16}degraded: {}", "", integration.affects);
+        }
+    }
+}
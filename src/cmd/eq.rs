@@ -0,0 +1,110 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::{parse, unix_socket::Response};
+
+use clap::ArgMatches;
+use galaxy_buds_rs::message::bud_property::BudProperty;
+use nix::sys::signal::{self, SigHandler, Signal};
+
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_sigint(_: i32) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Play each equalizer preset for a while and restore the previous one
+pub fn cycle(sc: &mut SocketClient, app: &ArgMatches) {
+    let interval = match parse::positive_duration(app.value_of("interval").unwrap_or("5")) {
+        Ok(v) => v,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+    let device = utils::get_device_from_app(&app);
+
+    let status = match sc.do_request(socket_client::new_status_request(device.clone())) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+    let status = socket_client::to_buds_info(status);
+    let original = match utils::unwrap_response(&status) {
+        Some(v) => v.equalizer_type,
+        None => return,
+    };
+
+    // Restore the original preset instead of exiting right away
+    if let Err(err) = unsafe { signal::signal(Signal::SIGINT, SigHandler::Handler(handle_sigint)) }
+    {
+        eprintln!("Can't handle Ctrl-C: {}", err);
+        return;
+    }
+
+    let presets = utils::EQUALIZER_PRESETS;
+    for (i, (name, preset)) in presets.iter().enumerate() {
+        println!("[{}/{}] {}", i + 1, presets.len(), name);
+        if let Err(err) = set_equalizer(sc, device.clone(), preset.encode()) {
+            println!("Error: {}", err);
+            break;
+        }
+
+        if !wait(interval) {
+            println!();
+            break;
+        }
+    }
+
+    let name = presets
+        .iter()
+        .find(|i| i.1 == original)
+        .map(|i| i.0)
+        .unwrap_or("previous preset");
+    println!("Restoring {}", name);
+    if let Err(err) = set_equalizer(sc, device, original.encode()) {
+        println!("Error: {}", err);
+    }
+}
+
+// Send the preset through the daemon
+fn set_equalizer(sc: &mut SocketClient, device: Option<String>, preset: u8) -> Result<(), String> {
+    sc.reconnect().map_err(|e| e.to_string())?;
+
+    let request = socket_client::new_set_value_request(
+        device,
+        "equalizer".to_owned(),
+        preset.to_string(),
+        false,
+    );
+    let res = sc.do_request(request).map_err(|e| e.to_string())?;
+
+    let res = Response::<serde_json::Value>::from_string(&res).map_err(|e| e.to_string())?;
+    if !res.is_success() {
+        return Err(res.status_message.unwrap_or_default());
+    }
+
+    Ok(())
+}
+
+// Sleep for the given time. Returns false if interrupted
+fn wait(duration: Duration) -> bool {
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        if INTERRUPTED.load(Ordering::SeqCst) {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    !INTERRUPTED.load(Ordering::SeqCst)
+}
@@ -0,0 +1,50 @@
+use super::{
+    socket_client::{self, SocketClient},
+    style::{Level, Style},
+    utils,
+};
+use crate::daemon::unix_socket::features::FeatureEntry;
+
+use clap::ArgMatches;
+
+/// List the optional subsystems of the daemon, to find out why a config
+/// section seems to do nothing
+pub fn list(sc: &mut SocketClient, app: &ArgMatches) {
+    let res = match sc.do_request(socket_client::new_features_request()) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Vec<FeatureEntry>>(&res);
+    let features = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    let style = Style::from_app(&app);
+    for feature in features {
+        let state = match (feature.compiled, feature.active) {
+            (_, true) => style.paint(Level::Good, "active"),
+            (true, false) => "inactive".to_owned(),
+            (false, _) => style.paint(Level::Bad, "not compiled"),
+        };
+
+        if feature.active {
+            println!("{:<16}{}", feature.name, state);
+        } else {
+            println!(
+                "{:<16}{:<14}needs {}",
+                feature.name, state, feature.requires
+            );
+        }
+    }
+}
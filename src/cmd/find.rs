@@ -0,0 +1,54 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::unix_socket::Response;
+
+use clap::ArgMatches;
+
+use std::io::stdin;
+
+/// Let the buds beep until Enter gets pressed, or only stop the beep with `--stop`
+pub fn find(sc: &mut SocketClient, app: &ArgMatches) {
+    let device = utils::get_device_from_app(&app);
+    let bud = app.value_of("bud").unwrap_or("both");
+    let stop_only = app.is_present("stop");
+
+    if !send(sc, device.clone(), !stop_only, bud) || stop_only {
+        return;
+    }
+
+    println!("Beeping, press Enter to stop");
+    let mut line = String::new();
+    stdin().read_line(&mut line).ok();
+
+    // Each request needs its own connection
+    if let Err(err) = sc.reconnect() {
+        eprintln!("{:?}", err);
+        return;
+    }
+    send(sc, device, false, bud);
+}
+
+// Returns true on success
+fn send(sc: &mut SocketClient, device: Option<String>, start: bool, bud: &str) -> bool {
+    let res = match sc.do_request(socket_client::new_find_request(device, start, bud)) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return false;
+        }
+    };
+
+    match Response::<serde_json::Value>::from_string(&res) {
+        Ok(res) if res.is_success() => true,
+        Ok(res) => {
+            println!("Error: {}", res.status_message.unwrap_or_default());
+            false
+        }
+        Err(err) => {
+            println!("Error: {}", err);
+            false
+        }
+    }
+}
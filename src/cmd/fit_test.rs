@@ -0,0 +1,63 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::unix_socket::fit_test::{FitQuality, FitTestResult};
+
+use clap::ArgMatches;
+
+/// Check the seal of both buds
+pub fn run(sc: &mut SocketClient, app: &ArgMatches) {
+    let request = socket_client::new_fit_test_request(utils::get_device_from_app(&app));
+
+    // Show a progress bar unless the output gets parsed
+    let res = if app.is_present("quiet") || utils::print_as_json(&app) {
+        sc.do_request(request)
+    } else {
+        println!("Running fit test. Stay quiet for a few seconds...");
+        let mut shown = false;
+        let res = sc.do_progress_request(request, |progress| {
+            shown = true;
+            utils::print_progress(progress);
+        });
+        if shown {
+            utils::print_progress(100);
+        }
+        res
+    };
+
+    let res = match res {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<FitTestResult>(&res);
+    let result = match utils::unwrap_response(&res) {
+        Some(r) => r,
+        None => return,
+    };
+
+    println!("Left:\t{}", quality_str(result.left));
+    println!("Right:\t{}", quality_str(result.right));
+
+    if result.left == FitQuality::Bad || result.right == FitQuality::Bad {
+        println!("Try adjusting your buds or using other ear tips");
+    }
+}
+
+fn quality_str(quality: FitQuality) -> &'static str {
+    match quality {
+        FitQuality::Good => "good seal",
+        FitQuality::Bad => "bad seal",
+        FitQuality::Failed => "couldn't measure, try again",
+    }
+}
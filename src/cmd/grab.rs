@@ -0,0 +1,33 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use clap::ArgMatches;
+
+/// Move the buds from another host (eg. the phone) to this machine
+pub fn grab(sc: &mut SocketClient, app: &ArgMatches) {
+    let res = match sc.do_request(socket_client::new_grab_request(utils::get_device_from_app(
+        &app,
+    ))) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<bool>(&res);
+    if let Some(reconnected) = utils::unwrap_response(&res) {
+        if reconnected {
+            println!("Reconnected {} to this machine", res.device);
+        } else {
+            println!("Connected {} to this machine", res.device);
+        }
+    }
+}
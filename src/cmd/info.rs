@@ -1,8 +1,8 @@
 use super::socket_client::{self, SocketClient};
+use super::style::{Level, Style};
 use super::utils;
 use crate::daemon::buds_info::BudsInfoInner;
 
-use blurz::{BluetoothAdapter, BluetoothDevice, BluetoothSession};
 use clap::ArgMatches;
 use galaxy_buds_rs::message::bud_property::Placement;
 use galaxy_buds_rs::model::Feature::ExtTouchpadLock;
@@ -26,14 +26,22 @@ pub fn show(sc: &mut SocketClient, app: &ArgMatches) {
     let status = socket_client::to_buds_info(status);
     let res: BudsInfoInner = utils::unwrap_response(&status).unwrap();
 
-    let bt_name = get_bt_device_name(&res.address).unwrap_or_else(|| res.address.clone());
+    let style = Style::from_app(&app);
+    let bt_name = res.alias.clone().unwrap_or_else(|| res.address.clone());
 
     println!("Info for '{}':", bt_name);
     println!();
     if app.is_present("verbose") {
-        println!("Type:\t\t{:?}", res.model);
+        println!("Type:\t\t{}", res.model_name);
+        if let Some(firmware) = res.firmware {
+            println!("Firmware:\t{}", firmware);
+        }
     }
-    println!("Battery:\tL: {}%, R: {}%", res.batt_left, res.batt_right);
+    println!(
+        "Battery:\tL: {}, R: {}",
+        style.battery(res.batt_left),
+        style.battery(res.batt_right)
+    );
 
     // If one bean is not in the case, its batterystatus
     // can't be deterimned and the buds will always return 100%
@@ -42,7 +50,7 @@ pub fn show(sc: &mut SocketClient, app: &ArgMatches) {
         || res.placement_right == Placement::InCloseCase
         || res.placement_right == Placement::InOpenCase
     {
-        println!("Case:\t\t{}%", res.batt_case);
+        println!("Case:\t\t{}", style.battery(res.batt_case));
     }
 
     println!("Equalizer:\t{:?}", res.equalizer_type);
@@ -54,6 +62,23 @@ pub fn show(sc: &mut SocketClient, app: &ArgMatches) {
         }
     });
 
+    if res.other_host_active {
+        println!(
+            "Audio:\t\t{}",
+            style.paint(Level::Warn, "on another host (earbuds grab)")
+        );
+    }
+
+    if res.supports_ambient_during_calls() {
+        println!("Call ambient:\t{}", {
+            match res.ambient_during_calls {
+                Some(true) => "Enabled",
+                Some(false) => "Disabled",
+                None => "Unknown",
+            }
+        });
+    }
+
     let extendet_tp_lock = res.has_feature(ExtTouchpadLock);
 
     if extendet_tp_lock {
@@ -98,12 +123,14 @@ pub fn show(sc: &mut SocketClient, app: &ArgMatches) {
     println!("Right option:\t{:?}", res.touchpad_option_right);
 
     println!(
-        "Temp. left:\t{:?}°C",
-        (res.debug.temperature_left * 100_f32).floor() / 100_f32
+        "Temp. left:\t{:?}{}",
+        (res.debug.temperature_left * 100_f32).floor() / 100_f32,
+        style.celsius()
     );
     println!(
-        "Temp. right:\t{:?}°C",
-        (res.debug.temperature_right * 100_f32).floor() / 100_f32
+        "Temp. right:\t{:?}{}",
+        (res.debug.temperature_right * 100_f32).floor() / 100_f32,
+        style.celsius()
     );
 
     if app.is_present("verbose") {
@@ -125,24 +152,3 @@ pub fn show(sc: &mut SocketClient, app: &ArgMatches) {
         );
     }
 }
-
-fn get_bt_device_name<S: AsRef<str>>(dev_addr: S) -> Option<String> {
-    let session = BluetoothSession::create_session(None).ok()?;
-    let adapter = BluetoothAdapter::init(&session).ok()?;
-    let devices = adapter.get_device_list().ok()?;
-
-    for i in devices.into_iter() {
-        let dev = BluetoothDevice::new(&session, i);
-        if !dev.is_connected().ok()? {
-            continue;
-        }
-
-        if dev.get_address().ok()? != dev_addr.as_ref() {
-            continue;
-        }
-
-        return Some(dev.get_name().ok()?);
-    }
-
-    None
-}
@@ -0,0 +1,69 @@
+use super::{
+    socket_client::{self, SocketClient},
+    style::{Level, Style},
+    utils,
+};
+use crate::daemon::settings::{KeyInfo, ValueType};
+
+use clap::ArgMatches;
+
+/// List the keys which can be set on the device, with their values
+pub fn list(sc: &mut SocketClient, app: &ArgMatches) {
+    let res = match sc.do_request(socket_client::new_list_keys_request(
+        utils::get_device_from_app(&app),
+    )) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Vec<KeyInfo>>(&res);
+    let keys = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    let style = Style::from_app(&app);
+    for key in keys {
+        let name = if key.params.is_empty() {
+            key.key.clone()
+        } else {
+            format!("{} [{}]", key.key, key.params.join("|"))
+        };
+
+        if let Some(requirement) = key.requirement {
+            println!(
+                "{:<36}{}",
+                name,
+                style.paint(Level::Bad, &format!("unsupported: {}", requirement))
+            );
+            continue;
+        }
+
+        println!("{:<36}{:<8}{}", name, key.kind, values(&key));
+    }
+}
+
+// Values like 'on|off' or '0 (off)|1|2'
+fn values(key: &KeyInfo) -> String {
+    if key.value_type == ValueType::Bool {
+        return "on|off".to_owned();
+    }
+
+    key.values
+        .iter()
+        .map(|i| match i.name {
+            Some(ref name) => format!("{} ({})", i.value, name),
+            None => i.value.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join("|")
+}
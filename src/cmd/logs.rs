@@ -0,0 +1,46 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use clap::ArgMatches;
+
+/// Print the recent log lines of the daemon
+pub fn show(sc: &mut SocketClient, app: &ArgMatches) {
+    if app.is_present("follow") {
+        follow(sc);
+        return;
+    }
+
+    let res = match sc.do_request(socket_client::new_logs_request(false)) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Vec<String>>(&res);
+    if let Some(lines) = utils::unwrap_response(&res) {
+        for line in lines {
+            println!("{}", line);
+        }
+    }
+}
+
+// Print new lines until the daemon closes the connection
+fn follow(sc: &mut SocketClient) {
+    let res = sc.do_streaming_request(socket_client::new_logs_request(true), |line| {
+        println!("{}", line);
+        true
+    });
+
+    if let Err(err) = res {
+        eprintln!("{:?}", err);
+    }
+}
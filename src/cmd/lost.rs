@@ -0,0 +1,74 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::lost::LostEntry;
+
+use clap::ArgMatches;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// List buds which lost the connection while being worn
+pub fn list(sc: &mut SocketClient, app: &ArgMatches) {
+    let res = match sc.do_request(socket_client::new_lost_request()) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Vec<LostEntry>>(&res);
+    let entries = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    if entries.is_empty() {
+        println!("No buds got lost so far");
+        return;
+    }
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    for entry in entries {
+        let state = match entry.found_at {
+            Some(found) => format!(
+                "found {} later",
+                format_duration(found.saturating_sub(entry.time))
+            ),
+            None => "not found yet".to_owned(),
+        };
+        println!(
+            "{}\tlost {} ago, {}. Battery L: {}%, R: {}%",
+            entry.device,
+            format_duration(now.saturating_sub(entry.time)),
+            state,
+            entry.battery_left,
+            entry.battery_right
+        );
+
+        if app.is_present("verbose") {
+            if let Some(event) = entry.last_event {
+                println!("\tLast event: {}", event);
+            }
+        }
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    match secs {
+        s if s >= 24 * 60 * 60 => format!("{}d {}h", s / 86400, s % 86400 / 3600),
+        s if s >= 60 * 60 => format!("{}h {}m", s / 3600, s % 3600 / 60),
+        s => format!("{}m", (s + 59) / 60),
+    }
+}
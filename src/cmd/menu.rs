@@ -0,0 +1,174 @@
+use super::{
+    socket_client::{self, SocketClient},
+    style::Style,
+    utils,
+};
+use crate::daemon::unix_socket::{Request, Response};
+
+use clap::ArgMatches;
+use galaxy_buds_rs::message::bud_property::{BudProperty, EqualizerType};
+
+use std::{
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+/// Let the user pick a common action and apply it
+pub fn show(sc: &mut SocketClient, app: &ArgMatches) {
+    let device = utils::get_device_from_app(&app);
+
+    let status = match sc.do_request(socket_client::new_status_request(device.clone())) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+    let status = socket_client::to_buds_info(status);
+    let info = match utils::unwrap_response(&status) {
+        Some(v) => v,
+        None => return,
+    };
+
+    let actions = get_actions(
+        Style::from_app(&app),
+        device,
+        info.noise_reduction,
+        info.touchpads_blocked,
+        info.equalizer_type,
+    );
+    let labels: Vec<&str> = actions.iter().map(|i| i.0.as_str()).collect();
+
+    let selection = match app.value_of("dmenu") {
+        Some(cmd) => select_dmenu(cmd, &labels),
+        None => select_terminal(&labels),
+    };
+
+    let request = match selection.and_then(|i| actions.into_iter().find(|a| a.0 == i)) {
+        Some(action) => action.1,
+        None => return,
+    };
+
+    if let Err(err) = sc.reconnect() {
+        eprintln!("{:?}", err);
+        return;
+    }
+
+    let res = match sc.do_request(request) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // Connection changes don't respond with json
+    if let Ok(res) = Response::<serde_json::Value>::from_string(&res) {
+        if !res.is_success() {
+            println!("Error: {}", res.status_message.unwrap_or_default());
+        }
+    }
+}
+
+// Labels and requests of all actions
+fn get_actions(
+    style: Style,
+    device: Option<String>,
+    anc: bool,
+    touchpads_blocked: bool,
+    equalizer: EqualizerType,
+) -> Vec<(String, Request)> {
+    let toggle = |key: &str| {
+        socket_client::new_set_value_request(device.clone(), key.to_owned(), String::new(), true)
+    };
+
+    let mut actions = vec![
+        (
+            format!(
+                "ANC: {}",
+                if anc {
+                    format!("on {} off", style.arrow())
+                } else {
+                    format!("off {} on", style.arrow())
+                }
+            ),
+            toggle("noise_reduction"),
+        ),
+        (
+            format!(
+                "Touchpad: {}",
+                if touchpads_blocked {
+                    format!("locked {} unlocked", style.arrow())
+                } else {
+                    format!("unlocked {} locked", style.arrow())
+                }
+            ),
+            toggle("lock_touchpad"),
+        ),
+    ];
+
+    for (name, preset) in utils::EQUALIZER_PRESETS.iter() {
+        let current = if *preset == equalizer {
+            " (current)"
+        } else {
+            ""
+        };
+        actions.push((
+            format!("Equalizer: {}{}", name, current),
+            socket_client::new_set_value_request(
+                device.clone(),
+                "equalizer".to_owned(),
+                preset.encode().to_string(),
+                false,
+            ),
+        ));
+    }
+
+    actions.push((
+        "Disconnect".to_owned(),
+        socket_client::new_disconnect_request(device),
+    ));
+
+    actions
+}
+
+// Run a dmenu compatible program (eg. 'rofi -dmenu') and return the selected line
+fn select_dmenu(cmd: &str, labels: &[&str]) -> Option<String> {
+    let mut parts = cmd.split_whitespace();
+    let mut child = Command::new(parts.next()?)
+        .args(parts)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| eprintln!("Can't run {}: {}", cmd, e))
+        .ok()?;
+
+    child
+        .stdin
+        .take()?
+        .write_all(labels.join("\n").as_bytes())
+        .ok()?;
+
+    let output = child.wait_with_output().ok()?;
+    let selection = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+    if selection.is_empty() {
+        None
+    } else {
+        Some(selection)
+    }
+}
+
+// Print a numbered list and read the choice from stdin
+fn select_terminal(labels: &[&str]) -> Option<String> {
+    for (i, label) in labels.iter().enumerate() {
+        println!("{:>2}) {}", i + 1, label);
+    }
+    print!("Select: ");
+    io::stdout().flush().ok()?;
+
+    let mut input = String::new();
+    io::stdin().read_line(&mut input).ok()?;
+
+    let choice = input.trim().parse::<usize>().ok()?;
+    labels.get(choice.checked_sub(1)?).map(|i| i.to_string())
+}
@@ -0,0 +1,55 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::mic::MicSource;
+
+use clap::ArgMatches;
+
+/// List the input sources of the buds
+pub fn list(sc: &mut SocketClient, app: &ArgMatches) {
+    let sources = match request(sc, app, "list") {
+        Some(v) => v,
+        None => return,
+    };
+
+    if sources.is_empty() {
+        println!("No input sources found. Is a headset profile active?");
+        return;
+    }
+
+    for source in sources {
+        let default = if source.is_default { " (default)" } else { "" };
+        println!("{}{}\n\t{}", source.description, default, source.name);
+    }
+}
+
+/// Make the buds mic the default input source
+pub fn set_default(sc: &mut SocketClient, app: &ArgMatches) {
+    if let Some(source) = request(sc, app, "set_default").and_then(|i| i.into_iter().next()) {
+        println!("Default input: {}", source.description);
+    }
+}
+
+// Run a mic request. Returns None if the response was already printed
+fn request(sc: &mut SocketClient, app: &ArgMatches, action: &str) -> Option<Vec<MicSource>> {
+    let res = match sc.do_request(socket_client::new_mic_request(
+        utils::get_device_from_app(&app),
+        action,
+    )) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return None;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return None;
+    }
+
+    let res = socket_client::to_response::<Vec<MicSource>>(&res);
+    utils::unwrap_response(&res)
+}
@@ -1,6 +1,37 @@
+pub mod ambient;
+pub mod apply;
+pub mod bar;
+pub mod brief;
 pub mod config_set;
 pub mod connection;
+pub mod debug;
+pub mod devices;
+pub mod doctor;
+pub mod eq;
+pub mod features;
+pub mod find;
+pub mod fit_test;
+pub mod grab;
 pub mod info;
+pub mod keys;
+pub mod logs;
+pub mod lost;
+pub mod menu;
+pub mod mic;
+pub mod notifications;
+pub mod pair;
+pub mod prompt;
+pub mod raw;
+pub mod remote;
+pub mod service;
 pub mod set_value;
+pub mod settings;
 pub mod socket_client;
+pub mod stats;
+pub mod style;
+pub mod test_channels;
+pub mod top;
 mod utils;
+pub mod watch;
+pub mod watch_battery;
+pub mod wearable;
@@ -0,0 +1,72 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::parse;
+use crate::daemon::unix_socket::mute::MuteState;
+
+use clap::ArgMatches;
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Mute the notifications of the buds temporarily or until unmuted
+pub fn mute(sc: &mut SocketClient, app: &ArgMatches) {
+    let device = utils::get_device_from_app(&app);
+
+    let request = if app.is_present("off") {
+        socket_client::new_unmute_notifications_request(device)
+    } else {
+        let secs = match app.value_of("for").map(parse::positive_duration) {
+            None => None,
+            Some(Ok(duration)) => Some(duration.as_secs().max(1)),
+            Some(Err(err)) => {
+                eprintln!("{}", err);
+                return;
+            }
+        };
+        socket_client::new_mute_notifications_request(device, secs)
+    };
+
+    let res = match sc.do_request(request) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<MuteState>(&res);
+    let state = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    match (state.muted, state.until) {
+        (false, _) => println!("Notifications unmuted"),
+        (true, None) => println!("Notifications muted until unmuted with --off"),
+        (true, Some(until)) => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|i| i.as_secs())
+                .unwrap_or_default();
+            println!(
+                "Notifications muted for {}",
+                format_duration(until.saturating_sub(now))
+            );
+        }
+    }
+}
+
+fn format_duration(secs: u64) -> String {
+    if secs >= 60 * 60 {
+        format!("{}h {}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}m", (secs + 59) / 60)
+    }
+}
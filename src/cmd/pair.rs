@@ -0,0 +1,56 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::{parse, unix_socket::pair::PairedEntry};
+
+use clap::ArgMatches;
+
+const DEFAULT_SEARCH: u64 = 20;
+
+/// Pair new buds which are in pairing mode and add them to the config
+pub fn pair(sc: &mut SocketClient, app: &ArgMatches) {
+    let search = match app.value_of("search").map(parse::positive_duration) {
+        Some(Ok(v)) => v.as_secs().max(1),
+        Some(Err(err)) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        None => DEFAULT_SEARCH,
+    };
+
+    if !utils::print_as_json(&app) && !app.is_present("quiet") {
+        println!("Searching buds for up to {}s...", search);
+    }
+
+    let res = match sc.do_request(socket_client::new_pair_request(search)) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Vec<PairedEntry>>(&res);
+    let entries = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    for entry in entries {
+        if entry.connected {
+            println!("Paired and connected {} ({})", entry.name, entry.address);
+        } else {
+            println!(
+                "Paired {} ({}), but couldn't connect yet",
+                entry.name, entry.address
+            );
+        }
+    }
+}
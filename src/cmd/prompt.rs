@@ -0,0 +1,76 @@
+use super::utils;
+use crate::daemon::{parse, status_file};
+
+use clap::ArgMatches;
+
+use std::{collections::HashMap, fs};
+
+const ICON: &str = "🎧";
+const LOW_ICON: &str = "🪫";
+const LOW_BATTERY: i8 = 20;
+
+/// Print a short segment like `🎧 64%` for shell prompts. It only reads the
+/// status file, so it returns instantly and prints nothing without one
+pub fn show(app: &ArgMatches) {
+    let low = match parse_percent(app, "low", LOW_BATTERY) {
+        Some(v) => v,
+        None => return,
+    };
+    let show_below = match parse_percent(app, "show-below", 100) {
+        Some(v) => v,
+        None => return,
+    };
+
+    let content = match fs::read_to_string(status_file::get_path()) {
+        Ok(content) => content,
+        Err(_) => return,
+    };
+
+    let device = utils::get_device_from_app(app);
+    let status = match content
+        .lines()
+        .map(parse_line)
+        .find(|i| device.is_none() || i.get("device") == device.as_deref())
+    {
+        Some(status) => status,
+        None => return,
+    };
+
+    let level = |key: &str| status.get(key).and_then(|i| i.parse::<i8>().ok());
+    let battery = match (level("left"), level("right")) {
+        (Some(left), Some(right)) => left.min(right).max(0),
+        _ => return,
+    };
+
+    if battery > show_below || (app.is_present("worn-only") && status.get("worn") != Some("1")) {
+        return;
+    }
+
+    let icon = if battery <= low {
+        app.value_of("low-icon").unwrap_or(LOW_ICON)
+    } else {
+        app.value_of("icon").unwrap_or(ICON)
+    };
+    println!("{} {}%", icon, battery);
+}
+
+// Parse a line of the status file like `device=.. left=64 right=71`
+fn parse_line(line: &str) -> HashMap<&str, &str> {
+    line.split_whitespace()
+        .filter_map(|i| {
+            let mut split = i.splitn(2, '=');
+            Some((split.next()?, split.next()?))
+        })
+        .collect()
+}
+
+fn parse_percent(app: &ArgMatches, name: &str, default: i8) -> Option<i8> {
+    match app.value_of(name).map(parse::percent) {
+        None => Some(default),
+        Some(Ok(v)) => Some(v as i8),
+        Some(Err(err)) => {
+            eprintln!("{}", err);
+            None
+        }
+    }
+}
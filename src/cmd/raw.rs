@@ -0,0 +1,80 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+
+use clap::ArgMatches;
+
+const CONFIRM_FLAG: &str = "i-know-what-im-doing";
+
+/// Send a raw message to the buds
+pub fn send(sc: &mut SocketClient, app: &ArgMatches) {
+    if !confirmed(app) {
+        return;
+    }
+
+    let payload: Vec<&str> = app
+        .values_of("payload")
+        .map(|i| i.collect())
+        .unwrap_or_default();
+    let request = socket_client::new_raw_send_request(
+        utils::get_device_from_app(&app),
+        app.value_of("id").unwrap(),
+        &payload.join(" "),
+        app.is_present("dry-run"),
+    );
+
+    let res = match sc.do_request(request) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<String>(&res);
+    if let Some(frame) = utils::unwrap_response(&res) {
+        if app.is_present("dry-run") {
+            println!("Would send {}", frame);
+        } else {
+            println!("Sent {}", frame);
+        }
+    }
+}
+
+/// Print received raw messages until interrupted
+pub fn watch(sc: &mut SocketClient, app: &ArgMatches) {
+    if !confirmed(app) {
+        return;
+    }
+
+    let request =
+        socket_client::new_raw_watch_request(utils::get_device_from_app(&app), app.value_of("id"));
+
+    let res = sc.do_streaming_request(request, |line| {
+        println!("{}", line);
+        true
+    });
+
+    if let Err(err) = res {
+        eprintln!("{:?}", err);
+    }
+}
+
+// Raw messages bypass all checks of the daemon
+fn confirmed(app: &ArgMatches) -> bool {
+    if !app.is_present(CONFIRM_FLAG) {
+        eprintln!(
+            "Raw messages aren't checked and may change or break settings of your buds. Pass --{} to use them anyway",
+            CONFIRM_FLAG
+        );
+        return false;
+    }
+    true
+}
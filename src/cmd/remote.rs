@@ -0,0 +1,51 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+
+use clap::ArgMatches;
+
+/// Show a one-time code to pair a remote client with the HTTP endpoint
+pub fn pair(sc: &mut SocketClient, app: &ArgMatches) {
+    let res = match sc.do_request(socket_client::new_pairing_code_request()) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<String>(&res);
+    if let Some(code) = utils::unwrap_response(&res) {
+        println!("Pairing code: {} (valid for 5 minutes)", code);
+        println!("Exchange it for a token with `POST /api/pair` and send the token as `Authorization: Bearer <token>`");
+    }
+}
+
+/// Forget all paired remote clients
+pub fn revoke(sc: &mut SocketClient, app: &ArgMatches) {
+    let res = match sc.do_request(socket_client::new_revoke_tokens_request()) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<usize>(&res);
+    if let Some(count) = utils::unwrap_response(&res) {
+        println!("Revoked {} token(s)", count);
+    }
+}
@@ -0,0 +1,74 @@
+use std::{env, fs, path::PathBuf, process::exit};
+
+const AUTOSTART_FILE: &str = "earbuds.desktop";
+
+/// Install the daemon to start with the session
+pub fn install(app: &clap::ArgMatches) {
+    if !app.is_present("autostart") {
+        eprintln!("Only autostart entries are supported yet. Use --autostart");
+        exit(1);
+    }
+
+    if let Err(err) = install_autostart() {
+        eprintln!("Can't install autostart entry: {}", err);
+        exit(1);
+    }
+}
+
+/// Remove all installed autostart entries
+pub fn uninstall() {
+    let file = match get_autostart_dir() {
+        Some(dir) => dir.join(AUTOSTART_FILE),
+        None => return,
+    };
+
+    if !file.exists() {
+        println!("No autostart entry installed");
+        return;
+    }
+
+    match fs::remove_file(&file) {
+        Ok(_) => println!("Removed {}", file.display()),
+        Err(err) => {
+            eprintln!("Can't remove {}: {}", file.display(), err);
+            exit(1);
+        }
+    }
+}
+
+// Write an XDG autostart desktop entry launching the daemon
+fn install_autostart() -> Result<(), String> {
+    let exe = env::current_exe().map_err(|e| e.to_string())?;
+    let dir = get_autostart_dir().ok_or("Can't find config directory")?;
+
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let entry = format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=earbuds\n\
+         Comment=Daemon controlling your Galaxy Buds\n\
+         Exec={} -d -q\n\
+         Terminal=false\n\
+         NoDisplay=true\n\
+         X-GNOME-Autostart-enabled=true\n",
+        exe.display()
+    );
+
+    let file = dir.join(AUTOSTART_FILE);
+    fs::write(&file, entry).map_err(|e| e.to_string())?;
+    println!("Installed {}", file.display());
+    Ok(())
+}
+
+fn get_autostart_dir() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+        .filter(|i| !i.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env::var_os("HOME")
+                .filter(|i| !i.is_empty())
+                .map(|i| PathBuf::from(i).join(".config"))
+        })
+        .map(|i| i.join("autostart"))
+}
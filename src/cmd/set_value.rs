@@ -1,6 +1,8 @@
 use super::socket_client::{self, SocketClient};
 use super::utils;
-use crate::daemon::utils::{is_str_bool, str_to_bool};
+use crate::daemon::parse;
+use crate::daemon::settings::{noise_control, Value};
+use crate::daemon::utils::{str_to_touchpad_option, touchpad_option_to_str};
 
 use clap::ArgMatches;
 use galaxy_buds_rs::message::bud_property::{BudProperty, EqualizerType, TouchpadOption};
@@ -9,24 +11,28 @@ use galaxy_buds_rs::message::bud_property::{BudProperty, EqualizerType, Touchpad
 pub fn set(sc: &mut SocketClient, app: &ArgMatches, toggle: bool, value: &str) {
     let skey = app.value_of("key").unwrap();
     let key = match Key::parse(skey) {
-        Some(k) => k,
+        Some(k) => k.for_value(value),
         None => {
             println!("Invalid key: {}", skey);
             return;
         }
     };
 
-    // Check value input
-    if !toggle && !is_value_ok(key, value) {
-        println!("invalid value: '{}' for key: '{}'", value, skey);
-        return;
-    }
+    // Check value input. Toggling doesn't need one
+    let value = match get_value(key, value) {
+        Ok(v) => v,
+        Err(_) if toggle => String::new(),
+        Err(err) => {
+            println!("{}: {}", skey, err);
+            return;
+        }
+    };
 
     // Build request payload
     let mut request = socket_client::new_set_value_request(
         utils::get_device_from_app(&app),
         key.value(),
-        get_value(key, value),
+        value,
         toggle,
     );
 
@@ -35,6 +41,10 @@ pub fn set(sc: &mut SocketClient, app: &ArgMatches, toggle: bool, value: &str) {
         request.opt_param3 = app.value_of("opt").map(|s| s.to_owned());
     }
 
+    request.dry_run = request_dry_run(app);
+    // Toggling always changes the value
+    request.force = !toggle && app.is_present("force");
+
     // Do unix_socket request
     let res = match sc.do_request(request) {
         Ok(k) => k,
@@ -50,10 +60,19 @@ pub fn set(sc: &mut SocketClient, app: &ArgMatches, toggle: bool, value: &str) {
         return;
     }
 
+    // Print the messages which would have been sent
+    if request_dry_run(app) {
+        print_dry_run(&res);
+        return;
+    }
+
     // Print response in a human readable way
     let res = socket_client::to_response::<String>(&res);
     if res.is_success() {
-        println!("Success");
+        match res.status_message {
+            Some(msg) => println!("Success ({})", msg),
+            None => println!("Success"),
+        }
     } else if let Some(err_msg) = res.status_message {
         println!("Error: {}", err_msg);
     } else {
@@ -61,39 +80,122 @@ pub fn set(sc: &mut SocketClient, app: &ArgMatches, toggle: bool, value: &str) {
     }
 }
 
-/// Return the actual value required for the payload
-fn get_value(key: Key, value: &str) -> String {
-    match key {
-        Key::Anc | Key::Touchpadlock => str_to_bool(value).to_string(),
-        Key::Touchpad => (!str_to_bool(value)).to_string(),
-        Key::Equalizer => parse_equalizer(value).encode().to_string(),
-        Key::TapAction => parse_tap_action(value).encode().to_string(),
-        Key::AmbientSound => value.to_string(),
+/// Returns the settings key and payload value of a key and value passed to 'set'
+pub fn to_request_value(key: &str, value: &str) -> Result<(String, String), String> {
+    let parsed = Key::parse(key)
+        .ok_or(format!("Invalid key: {}", key))?
+        .for_value(value);
+    let value = get_value(parsed, value).map_err(|e| format!("{}: {}", key, e))?;
+
+    Ok((parsed.value(), value))
+}
+
+/// Print the current value of a setting
+pub fn get(sc: &mut SocketClient, app: &ArgMatches) {
+    let skey = app.value_of("key").unwrap();
+    let key = match Key::parse(skey) {
+        Some(k) => k,
+        None => {
+            println!("Invalid key: {}", skey);
+            return;
+        }
+    };
+
+    let mut request =
+        socket_client::new_get_value_request(utils::get_device_from_app(&app), key.value());
+    request.opt_param3 = app.value_of("opt").map(|s| s.to_owned());
+
+    let res = match sc.do_request(request) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Value>(&res);
+    if let Some(value) = utils::unwrap_response(&res) {
+        println!("{}", display_value(key, value));
     }
 }
 
-/// Return true if the value is allowed for the given key
-fn is_value_ok(key: Key, value: &str) -> bool {
+// Format a value the way it would be passed to 'set'
+fn display_value(key: Key, value: Value) -> String {
     match key {
-        Key::Touchpadlock | Key::Touchpad | Key::Anc => is_str_bool(value),
-        Key::Equalizer => parse_equalizer(value) != EqualizerType::Undetected,
-        Key::TapAction => parse_tap_action(value) != TouchpadOption::Undetected,
-        Key::AmbientSound => utils::is_number(value),
+        Key::Touchpad => (!value.as_bool()).to_string(),
+        Key::Equalizer => format!("{:?}", EqualizerType::decode(value.as_number())),
+        Key::TapAction => {
+            touchpad_option_to_str(TouchpadOption::decode(value.as_number())).to_owned()
+        }
+        Key::NoiseControl => noise_control::name(value.as_number()).to_owned(),
+        _ => value.to_string(),
     }
 }
 
-// parse equalizer strings to enum variants
-fn parse_tap_action(value: &str) -> TouchpadOption {
-    match value.to_lowercase().as_str() {
-        "volume" => TouchpadOption::Volume,
-        "spotify" => TouchpadOption::Spotify,
-        "voice-command" => TouchpadOption::VoiceCommand,
-        "anc" => TouchpadOption::NoiseCanceling,
-        "disconnect" => TouchpadOption::Disconnect,
-        _ => TouchpadOption::Undetected,
+fn request_dry_run(app: &ArgMatches) -> bool {
+    app.is_present("dry-run")
+}
+
+// Print a dry run response
+pub fn print_dry_run(res: &str) {
+    let res = socket_client::to_response::<Vec<String>>(res);
+    if !res.is_success() {
+        println!("Error: {}", res.status_message.unwrap_or_default());
+        return;
+    }
+
+    let messages = res.payload.unwrap_or_default();
+    if messages.is_empty() {
+        println!("Nothing would be sent");
+    }
+
+    for msg in messages {
+        println!("Would send: {}", msg);
     }
 }
 
+/// Return the actual value required for the payload or an error if the
+/// value isn't allowed for the given key
+fn get_value(key: Key, value: &str) -> Result<String, String> {
+    let invalid = || format!("Invalid value '{}'", value);
+    Ok(match key {
+        Key::Anc | Key::Touchpadlock | Key::Ambient | Key::AmbientCalls => {
+            parse::bool(value)?.to_string()
+        }
+        Key::Touchpad => (!parse::bool(value)?).to_string(),
+        Key::Equalizer => match parse_equalizer(value) {
+            EqualizerType::Undetected => return Err(invalid()),
+            eq => eq.encode().to_string(),
+        },
+        Key::TapAction => match parse_tap_action(value) {
+            TouchpadOption::Undetected if value.starts_with("volume-") => {
+                return Err(format!(
+                    "{}. There is a single 'volume' action, holding left lowers and right raises the volume",
+                    invalid()
+                ))
+            }
+            TouchpadOption::Undetected => return Err(invalid()),
+            option => option.encode().to_string(),
+        },
+        // The daemon checks the maximum level of the connected model
+        Key::AmbientSound => parse::level(value, 4)?.to_string(),
+        Key::NoiseControl => noise_control::parse(value)
+            .ok_or_else(|| format!("{}. Use off, ambient, low or high", invalid()))?
+            .to_string(),
+    })
+}
+
+// parse touch and hold strings to enum variants
+fn parse_tap_action(value: &str) -> TouchpadOption {
+    str_to_touchpad_option(value)
+}
+
 // parse equalizer strings to enum variants
 fn parse_equalizer(value: &str) -> EqualizerType {
     match value.to_lowercase().as_str() {
@@ -115,6 +217,9 @@ enum Key {
     Touchpad, // I prefer 'set touchpad 1' over 'set touchpadlock 0'
     TapAction,
     AmbientSound,
+    Ambient,
+    AmbientCalls,
+    NoiseControl,
 }
 
 impl Key {
@@ -126,17 +231,33 @@ impl Key {
             Key::Touchpad => "lock_touchpad",
             Key::TapAction => "touchpad_action",
             Key::AmbientSound => "ambient_volume",
+            Key::Ambient => "ambient_sound",
+            Key::AmbientCalls => "ambient_during_calls",
+            Key::NoiseControl => "noise_control",
         })
     }
 
+    // 'anc low', 'anc high' and 'anc ambient' need the noise controls of newer buds
+    fn for_value(self, value: &str) -> Key {
+        match self {
+            Key::Anc if parse::bool(value).is_err() && noise_control::parse(value).is_some() => {
+                Key::NoiseControl
+            }
+            key => key,
+        }
+    }
+
     fn parse(key: &str) -> Option<Key> {
         Some(match key.to_string().to_lowercase().as_str() {
             "anc" => Key::Anc,
             "equalizer" => Key::Equalizer,
             "touchpadlock" => Key::Touchpadlock,
             "touchpad" => Key::Touchpad,
-            "tap-action" => Key::TapAction,
+            "tap-action" | "hold-action" => Key::TapAction,
             "ambientsound" => Key::AmbientSound,
+            "ambient" => Key::Ambient,
+            "ambient-calls" => Key::AmbientCalls,
+            "noise-control" => Key::NoiseControl,
             _ => return None,
         })
     }
@@ -0,0 +1,175 @@
+use super::socket_client::{self, SocketClient};
+use super::utils;
+use crate::daemon::{
+    buds_info::BudsInfoInner,
+    utils::{str_to_touchpad_option, touchpad_option_to_str},
+};
+
+use clap::ArgMatches;
+use galaxy_buds_rs::{message::bud_property::BudProperty, model::Feature};
+use serde::{Deserialize, Serialize};
+
+use std::io::Read;
+
+/// All settings of a device which can be restored later on
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SettingsSnapshot {
+    pub equalizer: u8,
+    pub noise_reduction: Option<bool>,
+    pub touchpads_blocked: bool,
+    pub touchpad_option_left: String,
+    pub touchpad_option_right: String,
+    pub ambient_sound_volume: Option<u8>,
+    pub ambient_during_calls: Option<bool>,
+}
+
+impl SettingsSnapshot {
+    /// Create a snapshot from the status of a device
+    pub fn from_status(status: &BudsInfoInner) -> Self {
+        let ambient_volume = if status.ambient_sound_enabled {
+            status.ambient_sound_volume
+        } else {
+            0
+        };
+
+        Self {
+            equalizer: status.equalizer_type.encode(),
+            noise_reduction: status
+                .has_feature(Feature::Anc)
+                .then(|| status.noise_reduction),
+            touchpads_blocked: status.touchpads_blocked,
+            touchpad_option_left: touchpad_option_to_str(status.touchpad_option_left).to_owned(),
+            touchpad_option_right: touchpad_option_to_str(status.touchpad_option_right).to_owned(),
+            ambient_sound_volume: status
+                .has_feature(Feature::AmbientSound)
+                .then(|| ambient_volume),
+            ambient_during_calls: status
+                .ambient_during_calls
+                .filter(|_| status.supports_ambient_during_calls()),
+        }
+    }
+
+    /// Returns (key, value, opt_param3) triples of set_value requests restoring this snapshot
+    pub fn to_requests(&self) -> Vec<(String, String, Option<String>)> {
+        let mut requests = vec![
+            ("equalizer".to_owned(), self.equalizer.to_string(), None),
+            (
+                "lock_touchpad".to_owned(),
+                self.touchpads_blocked.to_string(),
+                None,
+            ),
+        ];
+
+        for (side, option) in &[
+            ("left", &self.touchpad_option_left),
+            ("right", &self.touchpad_option_right),
+        ] {
+            let option = str_to_touchpad_option(option.as_str());
+            requests.push((
+                "touchpad_action".to_owned(),
+                option.encode().to_string(),
+                Some(side.to_string()),
+            ));
+        }
+
+        if let Some(anc) = self.noise_reduction {
+            requests.push(("noise_reduction".to_owned(), anc.to_string(), None));
+        }
+
+        if let Some(volume) = self.ambient_sound_volume {
+            requests.push(("ambient_volume".to_owned(), volume.to_string(), None));
+        }
+
+        if let Some(enabled) = self.ambient_during_calls {
+            requests.push(("ambient_during_calls".to_owned(), enabled.to_string(), None));
+        }
+
+        requests
+    }
+}
+
+/// Export all settings of a device as json
+pub fn export(sc: &mut SocketClient, app: &ArgMatches) {
+    let status = sc
+        .do_request(socket_client::new_status_request(
+            utils::get_device_from_app(&app),
+        ))
+        .unwrap();
+
+    let status = socket_client::to_buds_info(status);
+    let status: BudsInfoInner = utils::unwrap_response(&status).unwrap();
+
+    let snapshot = SettingsSnapshot::from_status(&status);
+    let json = serde_json::to_string_pretty(&snapshot).unwrap();
+
+    match app.value_of("file") {
+        Some(file) => {
+            if let Err(err) = std::fs::write(file, json) {
+                eprintln!("Couldn't write {}: {}", file, err);
+                std::process::exit(1);
+            }
+        }
+        None => println!("{}", json),
+    }
+}
+
+/// Push a previously exported snapshot back to the device
+pub fn import(sc: &mut SocketClient, app: &ArgMatches) {
+    let file = app.value_of("file").unwrap();
+
+    let content = if file == "-" {
+        let mut s = String::new();
+        std::io::stdin().read_to_string(&mut s).map(|_| s)
+    } else {
+        std::fs::read_to_string(file)
+    };
+
+    let snapshot: SettingsSnapshot = match content
+        .map_err(|e| e.to_string())
+        .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+    {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("Couldn't read snapshot: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let mut failed = false;
+    for (key, value, opt) in snapshot.to_requests() {
+        let mut request = socket_client::new_set_value_request(
+            utils::get_device_from_app(&app),
+            key.clone(),
+            value,
+            false,
+        );
+        request.opt_param3 = opt;
+
+        let res = sc
+            .reconnect()
+            .and_then(|_| sc.do_request(request))
+            .map(|res| socket_client::to_response::<String>(&res));
+
+        match res {
+            Ok(res) if res.is_success() => (),
+            Ok(res) => {
+                failed = true;
+                println!(
+                    "Error setting {}: {}",
+                    key,
+                    res.status_message.unwrap_or_default()
+                );
+            }
+            Err(err) => {
+                eprintln!("{:?}", err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+
+    println!("Success");
+}
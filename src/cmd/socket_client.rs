@@ -1,26 +1,64 @@
 use std::error::Error;
-use std::io::prelude::*;
+use std::fmt;
+use std::io::{prelude::*, BufReader, ErrorKind};
 use std::os::unix::net::UnixStream;
 use std::path::Path;
+use std::time::Duration;
 
 use crate::daemon::buds_info::BudsInfoInner;
-use crate::daemon::unix_socket::{Request, Response};
+use crate::daemon::unix_socket::{set_value::BatchEntry, Progress, Request, Response};
 
 pub struct SocketClient {
-    #[allow(dead_code)]
     path: String,
     socket: UnixStream,
+    timeout: Option<Duration>,
 }
 
+/// The daemon didn't answer within the timeout
+pub struct Unresponsive;
+
+impl fmt::Display for Unresponsive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The daemon is unresponsive. Try restarting it with `earbuds -k -d`"
+        )
+    }
+}
+
+impl fmt::Debug for Unresponsive {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Error for Unresponsive {}
+
 impl SocketClient {
     // Create a new SocketClient
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error>> {
         Ok(Self {
             path: path.as_ref().to_str().unwrap().to_owned(),
             socket: UnixStream::connect(path)?,
+            timeout: None,
         })
     }
 
+    /// Fail requests which aren't answered within the given time
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<(), Box<dyn Error>> {
+        self.timeout = timeout;
+        self.socket.set_read_timeout(timeout)?;
+        Ok(())
+    }
+
+    /// Open a new connection to the daemon. Required since
+    /// the daemon handles only one request per connection
+    pub fn reconnect(&mut self) -> Result<(), Box<dyn Error>> {
+        self.socket = UnixStream::connect(&self.path)?;
+        self.socket.set_read_timeout(self.timeout)?;
+        Ok(())
+    }
+
     /// Do a request to the daemon
     pub fn do_request(&mut self, request: Request) -> Result<String, Box<dyn Error>> {
         let mut stream = &self.socket;
@@ -31,9 +69,81 @@ impl SocketClient {
 
         // wait for response
         let mut response = String::new();
-        stream.read_to_string(&mut response)?;
+        if let Err(err) = stream.read_to_string(&mut response) {
+            return match err.kind() {
+                ErrorKind::WouldBlock | ErrorKind::TimedOut => Err(Box::new(Unresponsive)),
+                _ => Err(err.into()),
+            };
+        }
         Ok(response)
     }
+
+    /// Do a request for a long operation. `f` gets called with the progress
+    /// (in percent) until the final response arrives
+    pub fn do_progress_request<F>(
+        &mut self,
+        mut request: Request,
+        mut f: F,
+    ) -> Result<String, Box<dyn Error>>
+    where
+        F: FnMut(u8),
+    {
+        let mut stream = &self.socket;
+        request.progress = true;
+
+        // send request
+        stream.write_all(request.sendable()?.as_bytes())?;
+        stream.flush()?;
+
+        // handle progress frames until the response
+        let mut response = String::new();
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    return match err.kind() {
+                        ErrorKind::WouldBlock | ErrorKind::TimedOut => Err(Box::new(Unresponsive)),
+                        _ => Err(err.into()),
+                    }
+                }
+            };
+
+            match serde_json::from_str::<Progress>(&line) {
+                Ok(frame) => f(frame.progress),
+                Err(_) => response.push_str(&line),
+            }
+        }
+        Ok(response)
+    }
+
+    /// Do a request which gets answered with multiple lines. `f` gets
+    /// called for each line until it returns false or the daemon closes the connection
+    pub fn do_streaming_request<F>(
+        &mut self,
+        request: Request,
+        mut f: F,
+    ) -> Result<(), Box<dyn Error>>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut stream = &self.socket;
+
+        // Streams stay open without sending anything for a long time
+        stream.set_read_timeout(None)?;
+
+        // send request
+        stream.write_all(request.sendable()?.as_bytes())?;
+        stream.flush()?;
+
+        // handle each line
+        for line in BufReader::new(stream).lines() {
+            if !f(line?.as_str()) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
 }
 
 pub fn to_response<'de, T>(response_str: &'de str) -> Response<T>
@@ -53,16 +163,136 @@ pub fn new_status_request(device: Option<String>) -> Request {
     Request::new("get_status".to_owned(), device)
 }
 
+// Create new subscribe request
+pub fn new_subscribe_request(
+    device: Option<String>,
+    snapshot: bool,
+    only: Option<String>,
+) -> Request {
+    let mut request = Request::new("subscribe".to_owned(), device);
+    if snapshot {
+        request.opt_param1 = Some("snapshot".to_owned());
+    }
+    request.opt_param2 = only;
+    request
+}
+
+// Create new round trip measurement request
+pub fn new_rtt_request(device: Option<String>, samples: usize) -> Request {
+    let mut request = Request::new("measure_rtt".to_owned(), device);
+    request.opt_param1 = Some(samples.to_string());
+    request
+}
+
+// Create new request for debug sensor data
+pub fn new_telemetry_request(device: Option<String>, follow: bool, interval: u64) -> Request {
+    let mut request = Request::new("telemetry".to_owned(), device);
+    if follow {
+        request.opt_param1 = Some("follow".to_owned());
+    }
+    request.opt_param2 = Some(interval.to_string());
+    request
+}
+
+// Create new mic request
+pub fn new_mic_request(device: Option<String>, action: &str) -> Request {
+    let mut request = Request::new("mic".to_owned(), device);
+    request.opt_param1 = Some(action.to_owned());
+    request
+}
+
+// Create new request muting the notifications of a device. None mutes until unmuted
+pub fn new_mute_notifications_request(device: Option<String>, secs: Option<u64>) -> Request {
+    let mut request = Request::new("mute_notifications".to_owned(), device);
+    request.opt_param1 = secs.map(|i| i.to_string());
+    request
+}
+
+// Create new request showing the notifications of a device again
+pub fn new_unmute_notifications_request(device: Option<String>) -> Request {
+    let mut request = Request::new("mute_notifications".to_owned(), device);
+    request.opt_param1 = Some("off".to_owned());
+    request
+}
+
+// Create new request for the daemon logs
+pub fn new_logs_request(follow: bool) -> Request {
+    let mut request = Request::new("get_logs".to_owned(), None);
+    if follow {
+        request.opt_param1 = Some("follow".to_owned());
+    }
+    request
+}
+
+// Create new request for the connection statistics
+pub fn new_stats_request(device: Option<String>) -> Request {
+    Request::new("get_stats".to_owned(), device)
+}
+
+// Create new request for the counters shown by `earbuds top`
+pub fn new_load_request() -> Request {
+    Request::new("get_load".to_owned(), None)
+}
+
+// Create new request for the disconnects per day
+pub fn new_connection_stats_request() -> Request {
+    Request::new("connection_stats".to_owned(), None)
+}
+
+// Create new request for a one-time code pairing a remote client
+pub fn new_pairing_code_request() -> Request {
+    Request::new("pairing_code".to_owned(), None)
+}
+
+// Create new request forgetting all paired remote clients
+pub fn new_revoke_tokens_request() -> Request {
+    Request::new("revoke_tokens".to_owned(), None)
+}
+
+// Create new request running a fit test
+pub fn new_fit_test_request(device: Option<String>) -> Request {
+    Request::new("fit_test".to_owned(), device)
+}
+
+// Create new request listing all paired devices
+pub fn new_devices_request() -> Request {
+    Request::new("list_devices".to_owned(), None)
+}
+
 // Create new connect request
 pub fn new_connect_request(device: Option<String>) -> Request {
     Request::new("connect".to_owned(), device)
 }
 
+// Create new request taking the buds over from another host
+pub fn new_grab_request(device: Option<String>) -> Request {
+    Request::new("grab".to_owned(), device)
+}
+
+// Create new request asking the daemon to hand off to a new one
+pub fn new_handoff_request() -> Request {
+    Request::new("handoff".to_owned(), None)
+}
+
 // Create new disconnect request
 pub fn new_disconnect_request(device: Option<String>) -> Request {
     Request::new("disconnect".to_owned(), device)
 }
 
+// Create new request setting multiple values at once
+pub fn new_apply_request(device: Option<String>, entries: &[BatchEntry]) -> Request {
+    let mut request = Request::new("apply_settings".to_owned(), device);
+    request.opt_param1 = serde_json::to_string(entries).ok();
+    request
+}
+
+// Create new get_value request
+pub fn new_get_value_request(device: Option<String>, key: String) -> Request {
+    let mut request = Request::new("get_value".to_owned(), device);
+    request.opt_param1 = Some(key);
+    request
+}
+
 // Create new set_value request
 pub fn new_set_value_request(
     device: Option<String>,
@@ -86,6 +316,69 @@ pub fn new_set_value_request(
     request
 }
 
+// Create new request to merge settings into the desired state of a device
+pub fn new_set_desired_state_request(device: Option<String>, state: String) -> Request {
+    let mut request = Request::new("set_desired_state".to_owned(), device);
+    request.opt_param1 = Some(state);
+    request
+}
+
+// Create new request to start or stop the locator beep of the given bud
+pub fn new_find_request(device: Option<String>, start: bool, bud: &str) -> Request {
+    let mut request = Request::new("find_device".to_owned(), device);
+    request.opt_param1 = Some(if start { "start" } else { "stop" }.to_owned());
+    request.opt_param2 = Some(bud.to_owned());
+    request
+}
+
+// Create new request sending a raw message
+pub fn new_raw_send_request(
+    device: Option<String>,
+    id: &str,
+    payload: &str,
+    dry_run: bool,
+) -> Request {
+    let mut request = Request::new("raw_send".to_owned(), device);
+    request.opt_param1 = Some(id.to_owned());
+    request.opt_param2 = Some(payload.to_owned());
+    request.dry_run = dry_run;
+    request
+}
+
+// Create new request streaming received raw messages
+pub fn new_raw_watch_request(device: Option<String>, id: Option<&str>) -> Request {
+    let mut request = Request::new("raw_watch".to_owned(), device);
+    request.opt_param1 = id.map(|i| i.to_owned());
+    request
+}
+
+// Create new request for the keys the device supports
+pub fn new_list_keys_request(device: Option<String>) -> Request {
+    Request::new("list_keys".to_owned(), device)
+}
+
+// Create new request for the optional subsystems of the daemon
+pub fn new_features_request() -> Request {
+    Request::new("get_features".to_owned(), None)
+}
+
+// Create new request for the desktop integrations the daemon can reach
+pub fn new_integrations_request() -> Request {
+    Request::new("get_integrations".to_owned(), None)
+}
+
+// Create new request for devices lost while being worn
+pub fn new_lost_request() -> Request {
+    Request::new("get_lost".to_owned(), None)
+}
+
+// Create new request to pair buds in pairing mode
+pub fn new_pair_request(timeout: u64) -> Request {
+    let mut request = Request::new("pair_device".to_owned(), None);
+    request.opt_param1 = Some(timeout.to_string());
+    request
+}
+
 // Create new set_config request
 pub fn new_set_config_request(device: Option<String>, key: String, value: String) -> Request {
     let mut request = Request::new("set_config".to_owned(), device);
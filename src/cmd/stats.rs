@@ -0,0 +1,91 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::{connection_history::DaySummary, models::UpdateMode, stats::Stats};
+
+use clap::ArgMatches;
+
+/// Show the statistics of a connection
+pub fn show(sc: &mut SocketClient, app: &ArgMatches) {
+    if let Some(subcommand) = app.subcommand_matches("connections") {
+        connections(sc, subcommand);
+        return;
+    }
+
+    let res = match sc.do_request(socket_client::new_stats_request(
+        utils::get_device_from_app(&app),
+    )) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Stats>(&res);
+    let stats = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    println!("Frames received:\t{}", stats.frames_received);
+    println!("Frames skipped:\t\t{}", stats.frames_skipped);
+    println!("Frames sent:\t\t{}", stats.frames_sent);
+    println!("Write retries:\t\t{}", stats.write_retries);
+    println!("Failed writes:\t\t{}", stats.write_failures);
+    println!(
+        "Update mode:\t\t{}",
+        match (stats.update_mode, stats.polling) {
+            (UpdateMode::Push, false) => "push",
+            (UpdateMode::Push, true) => "push, polling while needed",
+            (UpdateMode::Poll, _) => "poll",
+        }
+    );
+    println!("Data requests:\t\t{}", stats.debug_requests);
+}
+
+// Print the disconnects per day
+fn connections(sc: &mut SocketClient, app: &ArgMatches) {
+    let res = match sc.do_request(socket_client::new_connection_stats_request()) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<Vec<DaySummary>>(&res);
+    let days = match utils::unwrap_response(&res) {
+        Some(v) => v,
+        None => return,
+    };
+
+    if days.is_empty() {
+        println!("No disconnects recorded yet");
+        return;
+    }
+
+    println!("Day (UTC)\tDrops\tDisconnects\tAvg. connected");
+    for day in days {
+        println!(
+            "{}\t{}\t{}\t\t{}m",
+            day.day,
+            day.drops,
+            day.disconnects,
+            day.avg_duration_secs / 60
+        );
+    }
+}
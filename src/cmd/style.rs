@@ -0,0 +1,109 @@
+use clap::ArgMatches;
+
+use std::env;
+
+// Battery levels below these are shown as warning or critical
+const BATTERY_WARN: u8 = 40;
+const BATTERY_LOW: u8 = 15;
+const GAUGE_WIDTH: usize = 5;
+
+/// Colors used for good, warning and critical values
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Palette {
+    /// Green, yellow and red
+    Default,
+    /// Blue, orange and vermillion of the Okabe-Ito palette, distinguishable
+    /// with red-green and blue-yellow color blindness
+    ColorBlind,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Level {
+    Good,
+    Warn,
+    Bad,
+}
+
+/// How to render human readable output of status, tables and `top`
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    pub ascii: bool,
+    pub palette: Palette,
+}
+
+impl Style {
+    /// Read `--ascii` and `--palette`. Without a palette, colors are only
+    /// used on terminals and if NO_COLOR isn't set
+    pub fn from_app(app: &ArgMatches) -> Style {
+        let palette = match app.value_of("palette") {
+            Some("colorblind") => Palette::ColorBlind,
+            Some("none") => Palette::None,
+            Some(_) => Palette::Default,
+            None if env::var_os("NO_COLOR").is_some() || !is_terminal() => Palette::None,
+            None => Palette::Default,
+        };
+
+        Style {
+            ascii: app.is_present("ascii"),
+            palette,
+        }
+    }
+
+    /// Color a text by its level
+    pub fn paint(&self, level: Level, text: &str) -> String {
+        let code = match (self.palette, level) {
+            (Palette::None, _) => return text.to_owned(),
+            (Palette::Default, Level::Good) => "32",
+            (Palette::Default, Level::Warn) => "33",
+            (Palette::Default, Level::Bad) => "31",
+            (Palette::ColorBlind, Level::Good) => "38;5;32",
+            (Palette::ColorBlind, Level::Warn) => "38;5;214",
+            (Palette::ColorBlind, Level::Bad) => "1;38;5;166",
+        };
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    }
+
+    /// Format a battery level with a gauge, eg. `64% ▰▰▰▱▱` or `64% [###--]`
+    pub fn battery(&self, level: u8) -> String {
+        let level = level.min(100);
+        let filled = (level as usize * GAUGE_WIDTH + 50) / 100;
+        let gauge = if self.ascii {
+            format!(
+                "[{}{}]",
+                "#".repeat(filled),
+                "-".repeat(GAUGE_WIDTH - filled)
+            )
+        } else {
+            format!("{}{}", "▰".repeat(filled), "▱".repeat(GAUGE_WIDTH - filled))
+        };
+
+        let level_str = format!("{}% {}", level, gauge);
+        match level {
+            l if l < BATTERY_LOW => self.paint(Level::Bad, &level_str),
+            l if l < BATTERY_WARN => self.paint(Level::Warn, &level_str),
+            _ => self.paint(Level::Good, &level_str),
+        }
+    }
+
+    /// Arrow between an old and a new state
+    pub fn arrow(&self) -> &'static str {
+        if self.ascii {
+            "->"
+        } else {
+            "→"
+        }
+    }
+
+    pub fn celsius(&self) -> &'static str {
+        if self.ascii {
+            "C"
+        } else {
+            "°C"
+        }
+    }
+}
+
+fn is_terminal() -> bool {
+    unsafe { libc::isatty(libc::STDOUT_FILENO) == 1 }
+}
@@ -0,0 +1,125 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+
+use clap::ArgMatches;
+use galaxy_buds_rs::message::bud_property::Placement;
+
+use std::{
+    f32::consts::PI,
+    io::Write,
+    process::{Command, Stdio},
+    thread,
+    time::Duration,
+};
+
+const SAMPLE_RATE: usize = 44100;
+const TONE_MILLIS: usize = 1000;
+
+// A low tone on the left and a high one on the right
+const TONES: [(&str, bool, f32); 2] = [("left", true, 440.0), ("right", false, 880.0)];
+
+/// Play a tone on each channel of the default sink to check whether the buds are swapped
+pub fn run(sc: &mut SocketClient, app: &ArgMatches) {
+    let device = utils::get_device_from_app(&app);
+
+    for (i, (name, left, frequency)) in TONES.iter().enumerate() {
+        // Each request needs its own connection
+        if i > 0 {
+            thread::sleep(Duration::from_millis(500));
+            if let Err(err) = sc.reconnect() {
+                eprintln!("{:?}", err);
+                return;
+            }
+        }
+
+        if !print_wear_state(sc, device.clone()) {
+            return;
+        }
+
+        println!("Playing a tone on the {} channel", name);
+        if let Err(err) = play(&tone(*left, *frequency)) {
+            println!("Error: {}", err);
+            return;
+        }
+    }
+
+    println!("The low tone should have played in your left ear and the high one in your right ear. If not, swap your buds");
+}
+
+// Print where both buds are. Returns false on errors
+fn print_wear_state(sc: &mut SocketClient, device: Option<String>) -> bool {
+    let status = match sc.do_request(socket_client::new_status_request(device)) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return false;
+        }
+    };
+    let status = socket_client::to_buds_info(status);
+    let info = match utils::unwrap_response(&status) {
+        Some(v) => v,
+        None => return false,
+    };
+
+    println!(
+        "Left bud: {}, right bud: {}",
+        placement_str(info.placement_left),
+        placement_str(info.placement_right)
+    );
+    true
+}
+
+fn placement_str(placement: Placement) -> &'static str {
+    match placement {
+        Placement::Ear => "in ear",
+        Placement::InOpenCase | Placement::InCloseCase => "in case",
+        _ => "not worn",
+    }
+}
+
+// Generate a stereo s16le sine tone on a single channel. Fades in and out to avoid clicks
+fn tone(left: bool, frequency: f32) -> Vec<u8> {
+    let samples = SAMPLE_RATE * TONE_MILLIS / 1000;
+    let fade = samples / 20;
+
+    let mut data = Vec::with_capacity(samples * 4);
+    for i in 0..samples {
+        let envelope = i.min(samples - i).min(fade) as f32 / fade as f32;
+        let phase = 2.0 * PI * frequency * i as f32 / SAMPLE_RATE as f32;
+        let value = (phase.sin() * envelope * 0.3 * i16::MAX as f32) as i16;
+
+        let (l, r) = if left { (value, 0) } else { (0, value) };
+        data.extend_from_slice(&l.to_le_bytes());
+        data.extend_from_slice(&r.to_le_bytes());
+    }
+    data
+}
+
+// Play raw audio through the default sink. Works with pulseaudio and pipewire-pulse
+fn play(data: &[u8]) -> Result<(), String> {
+    let mut child = Command::new("paplay")
+        .args(&[
+            "--raw",
+            "--format=s16le",
+            "--rate=44100",
+            "--channels=2",
+            "--channel-map=front-left,front-right",
+        ])
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Can't run paplay: {}", e))?;
+
+    // Closing stdin ends the playback
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(data).map_err(|e| e.to_string())?;
+    }
+
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(format!("paplay exited with {}", status));
+    }
+
+    Ok(())
+}
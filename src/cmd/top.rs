@@ -0,0 +1,150 @@
+use super::{
+    socket_client::{self, SocketClient},
+    style::{Level, Style},
+    utils,
+};
+use crate::daemon::{
+    parse,
+    stats::Stats,
+    unix_socket::load::{DeviceLoad, Load},
+};
+
+use clap::ArgMatches;
+
+use std::{
+    collections::HashMap,
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Redraw message rates, queue depths and handler latencies until interrupted
+pub fn run(sc: &mut SocketClient, app: &ArgMatches) {
+    let interval = match parse::positive_duration(app.value_of("interval").unwrap_or("1")) {
+        Ok(v) => v,
+        Err(err) => {
+            println!("{}", err);
+            return;
+        }
+    };
+
+    let style = Style::from_app(&app);
+    let mut previous: Option<(Load, Instant)> = None;
+    loop {
+        // Each request needs its own connection
+        if previous.is_some() {
+            if let Err(err) = sc.reconnect() {
+                eprintln!("{:?}", err);
+                return;
+            }
+        }
+
+        let res = match sc.do_request(socket_client::new_load_request()) {
+            Ok(v) => v,
+            Err(err) => {
+                eprintln!("{:?}", err);
+                return;
+            }
+        };
+        let res = socket_client::to_response::<Load>(&res);
+        let load = match utils::unwrap_response(&res) {
+            Some(v) => v,
+            None => return,
+        };
+
+        draw(&load, previous.as_ref(), interval, style);
+        previous = Some((load, Instant::now()));
+        thread::sleep(interval);
+    }
+}
+
+fn draw(load: &Load, previous: Option<&(Load, Instant)>, interval: Duration, style: Style) {
+    // Clear the screen and move to the top
+    print!("\x1b[2J\x1b[H");
+    println!(
+        "earbuds top - every {}s, Ctrl-C to quit",
+        interval.as_secs()
+    );
+    println!();
+
+    let last: HashMap<&str, &DeviceLoad> = previous
+        .map(|(l, _)| l.devices.iter().map(|i| (i.address.as_str(), i)).collect())
+        .unwrap_or_default();
+    let secs = previous
+        .map(|(_, time)| time.elapsed().as_secs_f64())
+        .unwrap_or(0.0);
+
+    println!(
+        "{:<20} {:>8} {:>8} {:>8} {:>8} {:>10} {:>8} {:>8}",
+        "Device", "Recv/s", "Skip/s", "Sent/s", "Retries", "Reconnects", "Avg ms", "Max ms"
+    );
+    if load.devices.is_empty() {
+        println!("No connected device");
+    }
+
+    for device in &load.devices {
+        let stats = &device.stats;
+        let old = last.get(device.address.as_str()).map(|i| &i.stats);
+        let delta = |f: fn(&Stats) -> u64| old.map_or(0, |o| f(stats).saturating_sub(f(o)));
+        let rate = |d: u64| if secs > 0.0 { d as f64 / secs } else { 0.0 };
+
+        // Average of the frames handled since the last refresh
+        let handled = delta(|s| s.frames_received - s.frames_skipped);
+        let avg = if handled > 0 {
+            delta(|s| s.handler_micros) as f64 / handled as f64 / 1000.0
+        } else {
+            0.0
+        };
+
+        println!(
+            "{:<20} {:>8.1} {:>8.1} {:>8.1} {} {} {:>8.2} {:>8.2}",
+            name(device),
+            rate(delta(|s| s.frames_received)),
+            rate(delta(|s| s.frames_skipped)),
+            rate(delta(|s| s.frames_sent)),
+            highlight(style, Level::Warn, stats.write_retries, 8),
+            highlight(style, Level::Warn, device.reconnects, 10),
+            avg,
+            stats.handler_max_micros as f64 / 1000.0
+        );
+    }
+
+    println!();
+    println!(
+        "{:<20} {:<8} {:>8} {:>8}",
+        "Subscriber", "Kind", "Queued", "Dropped"
+    );
+    for subscriber in &load.subscribers {
+        println!(
+            "{:<20} {:<8} {:>8} {}",
+            subscriber.device.as_deref().unwrap_or("all devices"),
+            if subscriber.passive {
+                "passive"
+            } else {
+                "client"
+            },
+            subscriber.queued,
+            highlight(style, Level::Bad, subscriber.dropped, 8)
+        );
+    }
+}
+
+// Right align a counter and color it if it's not zero. Colors don't count for the width
+fn highlight(style: Style, level: Level, value: u64, width: usize) -> String {
+    let text = format!("{:>width$}", value, width = width);
+    if value > 0 {
+        style.paint(level, &text)
+    } else {
+        text
+    }
+}
+
+// Shorten the name to fit the column
+fn name(device: &DeviceLoad) -> String {
+    device
+        .alias
+        .clone()
+        .unwrap_or_else(|| device.address.clone())
+        .chars()
+        .take(20)
+        .collect()
+}
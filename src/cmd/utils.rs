@@ -1,5 +1,16 @@
 use crate::daemon::unix_socket::Response;
 use clap::ArgMatches;
+use galaxy_buds_rs::message::bud_property::EqualizerType;
+
+/// Equalizer presets with their display names
+pub const EQUALIZER_PRESETS: [(&str, EqualizerType); 6] = [
+    ("Normal", EqualizerType::Normal),
+    ("Bass boost", EqualizerType::BassBoost),
+    ("Soft", EqualizerType::Soft),
+    ("Dynamic", EqualizerType::Dynamic),
+    ("Clear", EqualizerType::Clear),
+    ("Treble boost", EqualizerType::TrebleBoost),
+];
 
 // return ture if user wants the data in json
 pub fn print_as_json(app: &ArgMatches) -> bool {
@@ -28,7 +39,19 @@ where
     resp.payload.clone()
 }
 
-/// Returns true if passed 'input' is parsable to an i32
-pub fn is_number<S: AsRef<str>>(input: S) -> bool {
-    input.as_ref().parse::<i32>().is_ok()
+/// Draw a progress bar, replacing the previous one
+pub fn print_progress(progress: u8) {
+    const WIDTH: usize = 30;
+    let progress = progress.min(100);
+    let filled = WIDTH * progress as usize / 100;
+
+    eprint!(
+        "\r[{}{}] {:>3}%",
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled),
+        progress
+    );
+    if progress == 100 {
+        eprintln!();
+    }
 }
@@ -0,0 +1,23 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use clap::ArgMatches;
+
+/// Print status changes as they happen
+pub fn watch(sc: &mut SocketClient, app: &ArgMatches) {
+    let request = socket_client::new_subscribe_request(
+        utils::get_device_from_app(&app),
+        app.is_present("snapshot"),
+        app.value_of("only").map(|i| i.to_owned()),
+    );
+
+    let res = sc.do_streaming_request(request, |event| {
+        println!("{}", event);
+        true
+    });
+
+    if let Err(err) = res {
+        eprintln!("{:?}", err);
+    }
+}
@@ -0,0 +1,93 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+use crate::daemon::parse;
+
+use clap::ArgMatches;
+use notify_rust::Notification;
+
+use std::process::exit;
+
+/// Block until both buds are charged to at least `--until` percent, eg. in scripts
+/// which should only continue once the buds are ready
+pub fn watch(sc: &mut SocketClient, app: &ArgMatches) {
+    let target = match parse::percent(app.value_of("until").unwrap_or_default()) {
+        Ok(v) => v as i8,
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(1);
+        }
+    };
+
+    // Subscriptions to all devices would mix up their batteries
+    let status = match sc.do_request(socket_client::new_status_request(
+        utils::get_device_from_app(&app),
+    )) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            exit(1);
+        }
+    };
+    let status = socket_client::to_buds_info(status);
+    let address = match utils::unwrap_response(&status) {
+        Some(v) => v.address,
+        None => exit(1),
+    };
+
+    if let Err(err) = sc.reconnect() {
+        eprintln!("{:?}", err);
+        exit(1);
+    }
+
+    let (mut left, mut right) = (0, 0);
+    let mut reached = false;
+    let request = socket_client::new_subscribe_request(Some(address), true, None);
+    let res = sc.do_streaming_request(request, |event| {
+        let event: serde_json::Value = match serde_json::from_str(event) {
+            Ok(v) => v,
+            Err(_) => return true,
+        };
+
+        // The snapshot contains all values, later events only the changed ones
+        let values = match event.get("snapshot").or_else(|| event.get("changed")) {
+            Some(v) => v,
+            None => return true,
+        };
+        let battery = |key: &str| values.get(key).and_then(|i| i.as_i64()).map(|i| i as i8);
+        left = battery("batt_left").unwrap_or(left);
+        right = battery("batt_right").unwrap_or(right);
+
+        reached = left >= target && right >= target;
+        !reached
+    });
+
+    if let Err(err) = res {
+        eprintln!("{:?}", err);
+        exit(1);
+    }
+    if !reached {
+        eprintln!("The buds disconnected before reaching {}%", target);
+        exit(1);
+    }
+
+    let message = format!(
+        "Both buds reached {}% (L: {}%, R: {}%)",
+        target, left, right
+    );
+    if !app.is_present("quiet") {
+        println!("{}", message);
+    }
+
+    if app.is_present("notify") {
+        if let Err(err) = Notification::new()
+            .summary("Buds charged")
+            .body(&message)
+            .icon("battery-full-charged")
+            .show()
+        {
+            eprintln!("Can't show notification: {:?}", err);
+        }
+    }
+}
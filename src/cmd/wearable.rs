@@ -0,0 +1,137 @@
+use super::{
+    socket_client::{self, SocketClient},
+    utils,
+};
+
+use clap::ArgMatches;
+
+use std::collections::BTreeMap;
+
+// Preference keys of the Galaxy Wearable buds plugins with the setting and
+// parameter they map to. Values use the same numbers as the buds protocol
+const KEYS: [(&str, &str, Option<&str>); 9] = [
+    ("equalizer_type", "equalizer", None),
+    ("eq_preset", "equalizer", None),
+    ("noise_reduction", "noise_reduction", None),
+    ("active_noise_canceling", "noise_reduction", None),
+    ("ambient_sound_volume", "ambient_volume", None),
+    ("lock_touchpad", "lock_touchpad", None),
+    ("touchpad_locked", "lock_touchpad", None),
+    ("touchpad_option_left", "touchpad_action", Some("left")),
+    ("touchpad_option_right", "touchpad_action", Some("right")),
+];
+
+/// Read the settings of a Galaxy Wearable backup (the shared preferences xml of
+/// the buds plugin) and save them as desired state of the device
+pub fn import(sc: &mut SocketClient, app: &ArgMatches) {
+    let file = app.value_of("backup").unwrap();
+    let content = match std::fs::read_to_string(file) {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("Couldn't read backup: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    let (state, unknown) = parse(&content);
+    if app.is_present("verbose") && !unknown.is_empty() {
+        eprintln!("Skipped unknown preferences: {}", unknown.join(", "));
+    }
+    if state.is_empty() {
+        eprintln!("No buds settings found in the backup");
+        std::process::exit(1);
+    }
+
+    // Let the user review or copy the settings into the config
+    if app.is_present("print") {
+        let mut table = toml::value::Table::new();
+        table.insert("desired_state".to_owned(), toml::Value::Table(state));
+        println!("{}", toml::to_string_pretty(&table).unwrap_or_default());
+        return;
+    }
+
+    let state = serde_json::to_string(&state).unwrap();
+    let res = match sc.do_request(socket_client::new_set_desired_state_request(
+        utils::get_device_from_app(&app),
+        state,
+    )) {
+        Ok(k) => k,
+        Err(err) => {
+            eprintln!("{:?}", err);
+            return;
+        }
+    };
+
+    // print as json if user desires so
+    if utils::print_as_json(&app) {
+        println!("{}", res);
+        return;
+    }
+
+    let res = socket_client::to_response::<String>(&res);
+    if res.is_success() {
+        println!("Imported. The settings get applied with the next change or connect");
+    } else {
+        println!("Error: {}", res.status_message.unwrap_or_default());
+    }
+}
+
+// Collect the known settings as desired_state table. Returns the names of unknown preferences as well
+fn parse(content: &str) -> (toml::value::Table, Vec<String>) {
+    let mut state = toml::value::Table::new();
+    let mut sides: BTreeMap<String, toml::value::Table> = BTreeMap::new();
+    let mut unknown = Vec::new();
+
+    for (name, value) in preferences(content) {
+        let (key, param) = match KEYS.iter().find(|i| i.0.eq_ignore_ascii_case(&name)) {
+            Some((_, key, param)) => (*key, *param),
+            None => {
+                unknown.push(name);
+                continue;
+            }
+        };
+
+        match param {
+            Some(param) => {
+                sides
+                    .entry(key.to_owned())
+                    .or_default()
+                    .insert(param.to_owned(), value);
+            }
+            None => {
+                state.insert(key.to_owned(), value);
+            }
+        }
+    }
+
+    for (key, params) in sides {
+        state.insert(key, toml::Value::Table(params));
+    }
+    (state, unknown)
+}
+
+// Name and value of the int, long and boolean entries of an Android shared preferences
+// file, eg. `<int name="equalizer_type" value="2" />`
+fn preferences(content: &str) -> Vec<(String, toml::Value)> {
+    content
+        .split('<')
+        .filter_map(|tag| {
+            let (kind, attrs) = tag.split_once(' ')?;
+            let name = attribute(attrs, "name")?;
+            let value = attribute(attrs, "value")?;
+
+            let value = match kind {
+                "int" | "long" => toml::Value::Integer(value.parse().ok()?),
+                "boolean" => toml::Value::Boolean(value.parse().ok()?),
+                _ => return None,
+            };
+            Some((name.to_owned(), value))
+        })
+        .collect()
+}
+
+fn attribute<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    attrs
+        .split_whitespace()
+        .find_map(|i| i.strip_prefix(name)?.strip_prefix("=\"")?.split('"').next())
+}
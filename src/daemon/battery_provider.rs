@@ -0,0 +1,287 @@
+/*
+ * Reports the battery of the buds to UPower, so the battery widgets of GNOME
+ * and KDE show them. UPower can't be handed batteries directly but picks up
+ * the ones BlueZ knows, so the daemon registers as a BlueZ battery provider
+ * (org.bluez.BatteryProviderManager1). BlueZ exposes the level as Battery1 of
+ * the device. Older BlueZ versions need bluetoothd --experimental for this.
+ */
+
+use super::bluetooth::bt_connection_listener;
+use super::bluetooth::rfcomm_connector::{ConnectionData, Subscriber};
+use super::buds_config::Config;
+use super::buds_info::BudsInfoInner;
+
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use galaxy_buds_rs::message::bud_property::Placement;
+use log::{error, info, warn};
+use zbus::{
+    dbus_interface,
+    zvariant::{ObjectPath, OwnedObjectPath, OwnedValue, Value},
+    Connection, ConnectionBuilder, SignalContext,
+};
+
+use std::{collections::HashMap, convert::TryFrom};
+
+const BLUEZ: &str = "org.bluez";
+const PROVIDER_PATH: &str = "/org/livebuds/battery";
+const PROVIDER_INTERFACE: &str = "org.bluez.BatteryProvider1";
+// Shown by BlueZ as origin of the level
+const SOURCE: &str = "earbuds";
+
+// Interfaces of an object with their properties
+type Interfaces = HashMap<String, HashMap<String, OwnedValue>>;
+
+/// Root of the provider. BlueZ finds the batteries through the object manager
+struct Provider {
+    batteries: HashMap<String, Battery>,
+}
+
+#[dbus_interface(name = "org.freedesktop.DBus.ObjectManager")]
+impl Provider {
+    fn get_managed_objects(&self) -> HashMap<OwnedObjectPath, Interfaces> {
+        self.batteries
+            .iter()
+            .map(|(address, battery)| (battery_path(address), battery.interfaces()))
+            .collect()
+    }
+
+    #[dbus_interface(signal)]
+    async fn interfaces_added(
+        ctxt: &SignalContext<'_>,
+        object_path: OwnedObjectPath,
+        interfaces_and_properties: Interfaces,
+    ) -> zbus::Result<()>;
+
+    #[dbus_interface(signal)]
+    async fn interfaces_removed(
+        ctxt: &SignalContext<'_>,
+        object_path: OwnedObjectPath,
+        interfaces: Vec<String>,
+    ) -> zbus::Result<()>;
+}
+
+#[derive(Debug, Clone)]
+struct Battery {
+    device: OwnedObjectPath,
+    percentage: u8,
+}
+
+impl Battery {
+    fn interfaces(&self) -> Interfaces {
+        let mut properties: HashMap<String, OwnedValue> = HashMap::new();
+        properties.insert(
+            "Device".to_owned(),
+            Value::from(self.device.clone().into_inner()).into(),
+        );
+        properties.insert("Percentage".to_owned(), Value::from(self.percentage).into());
+        properties.insert("Source".to_owned(), Value::from(SOURCE).into());
+
+        let mut interfaces = HashMap::new();
+        interfaces.insert(PROVIDER_INTERFACE.to_owned(), properties);
+        interfaces
+    }
+}
+
+#[dbus_interface(name = "org.bluez.BatteryProvider1")]
+impl Battery {
+    /// BlueZ object of the buds
+    #[dbus_interface(property)]
+    fn device(&self) -> OwnedObjectPath {
+        self.device.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn percentage(&self) -> u8 {
+        self.percentage
+    }
+
+    #[dbus_interface(property)]
+    fn source(&self) -> String {
+        SOURCE.to_owned()
+    }
+}
+
+/// Provide the battery levels of the connected buds to BlueZ
+pub async fn run(cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) {
+    if !config.lock().await.battery_provider() {
+        return;
+    }
+
+    let adapter = match task::spawn_blocking(bt_connection_listener::get_adapter_path).await {
+        Ok(adapter) => adapter,
+        Err(err) => {
+            warn!("Can't report the battery to UPower: {}", err);
+            return;
+        }
+    };
+
+    let connection = match connect(&adapter).await {
+        Ok(c) => c,
+        Err(err) => {
+            warn!(
+                "Can't register as BlueZ battery provider, the battery won't show up in UPower: {}",
+                err
+            );
+            return;
+        }
+    };
+    info!("Registered as BlueZ battery provider on {}", adapter);
+
+    // The levels come with the extended status updates, which get published
+    let (subscriber, receiver) = Subscriber::new(None, true);
+    cd.lock().await.subscribers.push(subscriber);
+
+    let mut exported: HashMap<String, Battery> = HashMap::new();
+
+    loop {
+        let levels: HashMap<String, u8> = cd
+            .lock()
+            .await
+            .data
+            .values()
+            .filter(|i| i.inner.ready)
+            .map(|i| (i.inner.address.clone(), percentage(&i.inner)))
+            .collect();
+
+        if let Err(err) = sync(&connection, &adapter, &mut exported, levels).await {
+            error!("Can't update the battery provider: {}", err);
+        }
+
+        if receiver.recv().await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn connect(adapter: &str) -> zbus::Result<Connection> {
+    let connection = ConnectionBuilder::system()?
+        .serve_at(
+            PROVIDER_PATH,
+            Provider {
+                batteries: HashMap::new(),
+            },
+        )?
+        .build()
+        .await?;
+
+    let provider = ObjectPath::try_from(PROVIDER_PATH)?;
+    connection
+        .call_method(
+            Some(BLUEZ),
+            adapter,
+            Some("org.bluez.BatteryProviderManager1"),
+            "RegisterBatteryProvider",
+            &(&provider,),
+        )
+        .await?;
+
+    Ok(connection)
+}
+
+// Export batteries of new devices, remove disconnected ones and update changed levels
+async fn sync(
+    connection: &Connection,
+    adapter: &str,
+    exported: &mut HashMap<String, Battery>,
+    levels: HashMap<String, u8>,
+) -> zbus::Result<()> {
+    let server = connection.object_server();
+    let mut added = vec![];
+    let mut removed = vec![];
+
+    let gone: Vec<String> = exported
+        .keys()
+        .filter(|i| !levels.contains_key(*i))
+        .cloned()
+        .collect();
+    for address in gone {
+        exported.remove(&address);
+        server.remove::<Battery, _>(battery_path(&address)).await?;
+        removed.push(address);
+    }
+
+    for (address, percentage) in levels {
+        let path = battery_path(&address);
+
+        match exported.get_mut(&address) {
+            Some(battery) if battery.percentage != percentage => {
+                battery.percentage = percentage;
+                let iface_ref = server.interface::<_, Battery>(path).await?;
+                let mut iface = iface_ref.get_mut().await;
+                iface.percentage = percentage;
+                iface.percentage_changed(iface_ref.signal_context()).await?;
+            }
+            Some(_) => (),
+            None => {
+                let battery = Battery {
+                    device: device_path(adapter, &address),
+                    percentage,
+                };
+                server.at(path, battery.clone()).await?;
+                exported.insert(address.clone(), battery);
+                added.push(address);
+            }
+        }
+    }
+
+    if added.is_empty() && removed.is_empty() {
+        return Ok(());
+    }
+
+    let iface_ref = server.interface::<_, Provider>(PROVIDER_PATH).await?;
+    iface_ref.get_mut().await.batteries = exported.clone();
+    let ctxt = iface_ref.signal_context();
+
+    for address in removed {
+        Provider::interfaces_removed(
+            ctxt,
+            battery_path(&address),
+            vec![PROVIDER_INTERFACE.to_owned()],
+        )
+        .await?;
+    }
+    for address in added {
+        Provider::interfaces_added(
+            ctxt,
+            battery_path(&address),
+            exported[&address].interfaces(),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// BlueZ knows a single level per device, so report the bud running out first.
+// Buds in the case only count if both are in there
+fn percentage(info: &BudsInfoInner) -> u8 {
+    let buds = [
+        (info.batt_left, info.placement_left),
+        (info.batt_right, info.placement_right),
+    ];
+    let in_case = |placement: Placement| {
+        placement == Placement::InOpenCase || placement == Placement::InCloseCase
+    };
+
+    buds.iter()
+        .filter(|(_, placement)| !in_case(*placement))
+        .map(|(level, _)| *level)
+        .min()
+        .unwrap_or_else(|| info.batt_left.min(info.batt_right))
+        .clamp(0, 100) as u8
+}
+
+// Object path of a battery, eg. /org/livebuds/battery/AA_BB_CC_DD_EE_FF
+fn battery_path(address: &str) -> OwnedObjectPath {
+    let path = format!("{}/{}", PROVIDER_PATH, address.replace(':', "_"));
+    OwnedObjectPath::try_from(path).unwrap()
+}
+
+// BlueZ object of a device, eg. /org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF
+fn device_path(adapter: &str, address: &str) -> OwnedObjectPath {
+    let path = format!("{}/dev_{}", adapter, address.replace(':', "_"));
+    OwnedObjectPath::try_from(path).unwrap()
+}
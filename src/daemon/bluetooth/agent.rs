@@ -0,0 +1,105 @@
+use zbus::{
+    dbus_interface, fdo,
+    zvariant::{ObjectPath, OwnedObjectPath, Value},
+    Connection, ConnectionBuilder,
+};
+
+use std::convert::TryFrom;
+
+const BLUEZ: &str = "org.bluez";
+const AGENT_PATH: &str = "/org/bluez/agent/livebuds";
+// Buds have neither display nor keyboard, so bluez falls back to "just works"
+const CAPABILITY: &str = "NoInputNoOutput";
+
+/// BlueZ agent accepting just-works pairing requests. It only gets asked for
+/// devices paired through its own connection
+struct Agent;
+
+#[dbus_interface(name = "org.bluez.Agent1")]
+impl Agent {
+    fn release(&self) {}
+
+    fn request_confirmation(&self, _device: OwnedObjectPath, _passkey: u32) {}
+
+    fn request_authorization(&self, _device: OwnedObjectPath) {}
+
+    fn authorize_service(&self, _device: OwnedObjectPath, _uuid: String) {}
+
+    fn request_pin_code(&self, _device: OwnedObjectPath) -> fdo::Result<String> {
+        Err(fdo::Error::AccessDenied(
+            "PIN codes aren't supported".to_owned(),
+        ))
+    }
+
+    fn request_passkey(&self, _device: OwnedObjectPath) -> fdo::Result<u32> {
+        Err(fdo::Error::AccessDenied(
+            "Passkeys aren't supported".to_owned(),
+        ))
+    }
+
+    fn display_passkey(&self, _device: OwnedObjectPath, _passkey: u32, _entered: u16) {}
+
+    fn display_pin_code(&self, _device: OwnedObjectPath, _pincode: String) {}
+
+    fn cancel(&self) {}
+}
+
+/// Pair and trust the device at the bluez object `device_path` while
+/// the agent is registered
+pub async fn pair_and_trust(device_path: &str) -> zbus::Result<()> {
+    let connection = ConnectionBuilder::system()?
+        .serve_at(AGENT_PATH, Agent)?
+        .build()
+        .await?;
+
+    let agent = ObjectPath::try_from(AGENT_PATH)?;
+    call_agent_manager(&connection, "RegisterAgent", &(&agent, CAPABILITY)).await?;
+
+    let res = pair(&connection, device_path).await;
+
+    // The agent goes away with the connection anyway
+    call_agent_manager(&connection, "UnregisterAgent", &(&agent,))
+        .await
+        .ok();
+    res
+}
+
+async fn pair(connection: &Connection, device_path: &str) -> zbus::Result<()> {
+    connection
+        .call_method(
+            Some(BLUEZ),
+            device_path,
+            Some("org.bluez.Device1"),
+            "Pair",
+            &(),
+        )
+        .await?;
+
+    // Trusted devices can reconnect without asking
+    connection
+        .call_method(
+            Some(BLUEZ),
+            device_path,
+            Some("org.freedesktop.DBus.Properties"),
+            "Set",
+            &("org.bluez.Device1", "Trusted", Value::from(true)),
+        )
+        .await?;
+    Ok(())
+}
+
+async fn call_agent_manager<B>(connection: &Connection, method: &str, body: &B) -> zbus::Result<()>
+where
+    B: serde::ser::Serialize + zbus::zvariant::DynamicType,
+{
+    connection
+        .call_method(
+            Some(BLUEZ),
+            "/org/bluez",
+            Some("org.bluez.AgentManager1"),
+            method,
+            body,
+        )
+        .await?;
+    Ok(())
+}
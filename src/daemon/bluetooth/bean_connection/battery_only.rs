@@ -0,0 +1,106 @@
+/*
+ * Listener for Samsung devices of an unknown model. Only the battery levels of
+ * the status frame, which all models share, get read and nothing gets sent to
+ * the device. Each new kind of frame gets logged to help adding full support.
+ */
+
+use super::super::{
+    super::{
+        buds_info::BudsInfo, connection_history::DisconnectReason, diagnostics, models,
+        raw_message, status_diff,
+    },
+    bt_connection_listener::BudsConnection,
+    rfcomm_connector::ConnHandler,
+};
+
+use async_std::{io::prelude::*, sync::Mutex};
+use galaxy_buds_rs::message::{ids, status_updated::StatusUpdate, Message};
+use log::{info, warn};
+
+use std::{collections::HashSet, sync::Arc};
+
+const BUFF_SIZE: usize = 2048;
+
+/// Read the battery of a device of an unknown model
+pub async fn start_listen(
+    connection: BudsConnection,
+    ch: Arc<Mutex<ConnHandler>>,
+    last_frame: Arc<std::sync::Mutex<Vec<u8>>>,
+) {
+    let mut stream = connection.stream.clone();
+    let mut buffer: Vec<u8> = vec![0u8; BUFF_SIZE];
+    let model = models::UNKNOWN_MODEL_TEMPLATE;
+    let mut logged_ids = HashSet::new();
+
+    warn!(
+        "{} is an unknown model. Please report the following frames to add support for it",
+        connection.addr
+    );
+
+    loop {
+        let bytes_read = match stream.read(&mut buffer).await {
+            Ok(v) => v,
+            Err(_) => {
+                let mut c = ch.lock().await;
+                c.remove_device(connection.addr.as_str(), DisconnectReason::ConnectionLost)
+                    .await;
+                return;
+            }
+        };
+        let frame = &buffer[0..bytes_read];
+
+        // Keep the frame around for panic reports
+        if let Ok(mut last) = last_frame.lock() {
+            last.clear();
+            last.extend_from_slice(frame);
+        }
+        diagnostics::record_frame(&connection.addr, frame);
+
+        let message = Message::new(frame, model);
+        if !message.is_message() || !message.check_crc() {
+            continue;
+        }
+
+        // Log each kind of frame once
+        let id = message.get_id();
+        if logged_ids.insert(id) {
+            info!(
+                "Frame 0x{:02X} ({}) of unknown model {}: [{}]",
+                id,
+                raw_message::ids::name(id).unwrap_or("UNKNOWN"),
+                connection.addr,
+                raw_message::to_hex(frame)
+            );
+        }
+
+        if id != ids::STATUS_UPDATED {
+            continue;
+        }
+        let update: StatusUpdate = message.into();
+
+        let connection_handler = ch.lock().await;
+        let mut lock = connection_handler.connection_data.lock().await;
+
+        let info = lock.add_device(&connection.addr, || {
+            let mut info = BudsInfo::new(
+                stream.clone(),
+                &connection.addr,
+                model,
+                connection.alias.clone(),
+            );
+            info.inner.model_name = "unknown".to_owned();
+            info.inner.battery_only = true;
+            info
+        });
+        info.stats.frames_received += 1;
+
+        let previous = info.inner.clone();
+        info.inner.batt_left = update.battery_left;
+        info.inner.batt_right = update.battery_right;
+        info.inner.batt_case = update.battery_case;
+
+        if let Some(changes) = status_diff::diff_event(&previous, &info.inner) {
+            lock.publish(&connection.addr, &changes);
+        }
+    }
+}
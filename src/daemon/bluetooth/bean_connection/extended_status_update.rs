@@ -1,5 +1,11 @@
-use super::super::super::buds_info::BudsInfo;
-use galaxy_buds_rs::{message::extended_status_updated::ExtendedStatusUpdate, model::Model};
+use super::super::super::{
+    buds_config::Config, buds_info::BudsInfo, models, notifications::Category,
+};
+use super::super::bt_connection_listener::BudsConnection;
+use super::utils;
+
+use async_std::sync::{Arc, Mutex};
+use galaxy_buds_rs::message::extended_status_updated::ExtendedStatusUpdate;
 
 pub fn handle(update: ExtendedStatusUpdate, info: &mut BudsInfo) {
     // Update values from extended update
@@ -11,6 +17,28 @@ pub fn handle(update: ExtendedStatusUpdate, info: &mut BudsInfo) {
     }
 }
 
+/// Show a notification once a device got connected
+pub async fn notify_connected(
+    info: &mut BudsInfo,
+    config: &Arc<Mutex<Config>>,
+    connection: &BudsConnection,
+) {
+    let cfg = config.lock().await;
+    let enabled = cfg
+        .get_device_config(&connection.addr)
+        .map(|i| i.connect_notification())
+        .unwrap_or(false);
+
+    if enabled {
+        let notification = utils::get_connect_notification(
+            info.inner.batt_left,
+            info.inner.batt_right,
+            info.inner.batt_case,
+        );
+        info.notifier.show(Category::Connect, notification, &cfg);
+    }
+}
+
 // Update a BudsInfo to the values of an extended_status_update
 fn update_extended_status(update: ExtendedStatusUpdate, info: &mut BudsInfo) {
     info.inner.batt_left = update.battery_left;
@@ -26,7 +54,7 @@ fn update_extended_status(update: ExtendedStatusUpdate, info: &mut BudsInfo) {
     info.inner.tab_lock_status = update.tap_lock_status;
 
     // Ambient mode for buds
-    if info.inner.model == Model::BudsPlus {
+    if models::get(info.inner.model).ambient_in_extended_status() {
         info.inner.ambient_sound_volume = update.ambient_sound_volume as u8; // TODO make u8
         info.inner.ambient_sound_enabled = update.ambient_sound_enabled;
         info.inner.extra_high_ambient_volume = update.extra_high_ambient;
@@ -0,0 +1,53 @@
+use super::super::super::{
+    buds_info::BudsInfo,
+    settings::{self, Value},
+    status_diff,
+};
+
+use serde_json::{json, Map};
+
+/// Detect settings changed by someone else (eg. the Galaxy Wearable app) by comparing
+/// the settings before a message of the buds got handled with the current ones
+pub fn detect(
+    before: &[(&'static str, Option<&'static str>, Value)],
+    info: &BudsInfo,
+) -> Vec<(&'static str, Option<&'static str>, Value)> {
+    // Changes matching what the daemon has set aren't external
+    settings::snapshot(&info.inner)
+        .into_iter()
+        .filter(|i| !before.contains(i))
+        .filter(|(key, param, value)| info.desired_settings.get(&(*key, *param)) != Some(value))
+        .collect()
+}
+
+/// Publish the external changes and which of them got set back
+pub fn publish(
+    changed: Vec<(&'static str, Option<&'static str>, Value)>,
+    written: &[String],
+    info: &mut BudsInfo,
+    address: &str,
+) {
+    if changed.is_empty() {
+        return;
+    }
+
+    let mut values = Map::new();
+    let mut reasserted = Vec::new();
+
+    for (key, param, value) in changed {
+        let name = match param {
+            Some(param) => format!("{}:{}", key, param),
+            None => key.to_string(),
+        };
+        if written.contains(&name) {
+            reasserted.push(name.clone());
+        }
+        values.insert(name, json!(value));
+    }
+
+    info.events.push(status_diff::named_event(
+        address,
+        "settings_changed_externally",
+        json!({ "changed": values, "reasserted": reasserted }),
+    ));
+}
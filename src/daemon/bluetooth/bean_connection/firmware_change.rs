@@ -0,0 +1,75 @@
+use super::super::super::{
+    buds_config::Config, buds_info::BudsInfo, history, notifications::Category, status_diff,
+};
+use super::utils;
+
+use async_std::sync::{Arc, Mutex};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+/// A firmware version seen on a device
+#[derive(Debug, Serialize, Deserialize)]
+struct FirmwareEntry {
+    device: String,
+    firmware: String,
+    // Version seen before, None for new devices
+    previous: Option<String>,
+}
+
+/// Compare the reported firmware with the last one recorded in the history.
+/// Changes (eg. after an update through the phone) get published and recorded
+pub async fn handle(info: &mut BudsInfo, config: &Arc<Mutex<Config>>) {
+    let firmware = match info.inner.firmware {
+        Some(v) => v.to_string(),
+        None => return,
+    };
+
+    let previous = match history::read::<FirmwareEntry>("firmware").await {
+        Ok(entries) => entries
+            .into_iter()
+            .filter(|i| i.device == info.inner.address)
+            .last()
+            .map(|i| i.firmware),
+        Err(err) => {
+            eprintln!("Can't read firmware history: {}", err);
+            return;
+        }
+    };
+
+    if previous.as_deref() == Some(firmware.as_str()) {
+        return;
+    }
+
+    let entry = FirmwareEntry {
+        device: info.inner.address.clone(),
+        firmware: firmware.clone(),
+        previous: previous.clone(),
+    };
+    if let Err(err) = history::append("firmware", &entry).await {
+        eprintln!("Can't write firmware to history: {}", err);
+    }
+
+    // Nothing changed for devices seen the first time
+    let previous = match previous {
+        Some(v) => v,
+        None => return,
+    };
+
+    info.events.push(status_diff::named_event(
+        &info.inner.address,
+        "firmware_changed",
+        json!({ "previous": previous, "current": firmware }),
+    ));
+
+    let cfg = config.lock().await;
+    let enabled = cfg
+        .get_device_config(&info.inner.address)
+        .map(|i| i.firmware_change_notification())
+        .unwrap_or(false);
+
+    if enabled {
+        let notification = utils::get_firmware_change_notification(&previous, &firmware);
+        info.notifier
+            .show(Category::FirmwareChanged, notification, &cfg);
+    }
+}
@@ -1,10 +1,21 @@
 use super::{
     super::{
-        super::{buds_config::Config, buds_info::BudsInfo},
+        super::{
+            buds_config::Config,
+            buds_info::BudsInfo,
+            connection_history::DisconnectReason,
+            diagnostics,
+            firmware::FirmwareVersion,
+            lost, polling,
+            raw_message::{self, RawMessage},
+            runtime_state, settings, status_diff,
+            unix_socket::fit_test::FitTestResult,
+        },
         bt_connection_listener::BudsConnection,
         rfcomm_connector::ConnHandler,
     },
-    ambient_mode, anc, extended_status_update, get_all_data, status_update, touchpad,
+    ambient_mode, anc, extended_status_update, external_change, firmware_change, get_all_data,
+    sink, status_update, touchpad, write_back,
 };
 
 use async_std::{io::prelude::*, sync::Mutex};
@@ -13,18 +24,29 @@ use galaxy_buds_rs::{
     model::Model,
 };
 
-use std::{process::exit, sync::Arc};
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+    process::exit,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 const BUFF_SIZE: usize = 2048;
 
+// Identical consecutive status updates within this time are skipped. Handle one
+// once in a while anyway since debug data only gets polled while handling a message
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(1);
+
 /// Read buds data
 pub async fn start_listen(
     connection: BudsConnection,
     config: Arc<Mutex<Config>>,
     ch: Arc<Mutex<ConnHandler>>,
     model: Model,
+    last_frame: Arc<std::sync::Mutex<Vec<u8>>>,
 ) {
-    let mut stream = connection.socket.get_stream();
+    let mut stream = connection.stream.clone();
     let mut buffer: Vec<u8> = vec![0u8; BUFF_SIZE];
 
     // Check config errors
@@ -36,19 +58,43 @@ pub async fn start_listen(
         }
     }
 
+    // Query the current settings right away, they might have been changed from a phone
+    let request = RawMessage::new(raw_message::ids::EXTENDED_STATUS_UPDATED, Vec::new());
+    if let Err(err) = stream.write(&request.to_byte_array(model)).await {
+        eprintln!("Error requesting settings: {}", err);
+    }
+
+    // Settings might depend on the firmware version
+    let request = RawMessage::new(raw_message::ids::VERSION_INFO, Vec::new());
+    if let Err(err) = stream.write(&request.to_byte_array(model)).await {
+        eprintln!("Error requesting version: {}", err);
+    }
+
     let mut requested_debug = false;
     let mut first_msg = true;
 
+    // Hash and handling time of the last status update
+    let mut last_status: Option<(u64, Instant)> = None;
+    let mut skipped = 0;
+
     loop {
         let bytes_read = match stream.read(&mut buffer).await {
             Ok(v) => v,
             Err(_) => {
                 let mut c = ch.lock().await;
-                c.remove_device(connection.addr.as_str()).await;
+                c.remove_device(connection.addr.as_str(), DisconnectReason::ConnectionLost)
+                    .await;
                 return;
             }
         };
 
+        // Keep the frame around for panic reports
+        if let Ok(mut frame) = last_frame.lock() {
+            frame.clear();
+            frame.extend_from_slice(&buffer[0..bytes_read]);
+        }
+        diagnostics::record_frame(&connection.addr, &buffer[0..bytes_read]);
+
         // The received message from the buds
         let message = Message::new(&buffer[0..bytes_read], model);
 
@@ -57,6 +103,20 @@ pub async fn start_listen(
             continue;
         }
 
+        // Chatty buds repeat their status, skip it before parsing and locking
+        if message.get_id() == ids::STATUS_UPDATED {
+            let hash = frame_hash(&buffer[0..bytes_read]);
+            if let Some((last, time)) = last_status {
+                if last == hash && time.elapsed() < DUPLICATE_WINDOW {
+                    skipped += 1;
+                    continue;
+                }
+            }
+            last_status = Some((hash, Instant::now()));
+        } else {
+            last_status = None;
+        }
+
         // validate crc checksum
         if !message.check_crc() {
             // First received message always throws an CRC error. Since its nothing important we
@@ -72,15 +132,31 @@ pub async fn start_listen(
         // following scope. This is necessary because the 'lock' can't be borrowed twice at the
         // same time. Yes, I do hate me for this.
         let mut disconnect_afterwards = false;
+        let handling = Instant::now();
 
         {
             let connection_handler = ch.lock().await;
             let mut lock = connection_handler.connection_data.lock().await;
+            let has_subscribers = lock.has_active_subscribers(&connection.addr);
+
+            let info = lock.add_device(&connection.addr, || {
+                let mut info = BudsInfo::new(
+                    stream.clone(),
+                    &connection.addr,
+                    model,
+                    connection.alias.clone(),
+                );
+                runtime_state::restore(&mut info);
+                info
+            });
 
-            let info = lock
-                .data
-                .entry(connection.addr.clone())
-                .or_insert_with(|| BudsInfo::new(stream.clone(), &connection.addr, model));
+            info.stats.frames_received += 1 + skipped;
+            info.stats.frames_skipped += skipped;
+            skipped = 0;
+
+            // Remember the previous state to notify subscribers about changes
+            let previous = info.inner.clone();
+            let previous_settings = settings::snapshot(&info.inner);
 
             match message.get_id() {
                 ids::TOUCHPAD_ACTION => {
@@ -94,7 +170,12 @@ pub async fn start_listen(
                 }
 
                 ids::EXTENDED_STATUS_UPDATED => {
+                    let was_ready = info.inner.ready;
                     extended_status_update::handle(message.into(), info);
+                    info.handled_extended_status();
+                    if !was_ready {
+                        extended_status_update::notify_connected(info, &config, &connection).await;
+                    }
 
                     // Respond with set manager
                     stream
@@ -104,6 +185,8 @@ pub async fn start_listen(
                 }
 
                 ids::DEBUG_GET_ALL_DATA => {
+                    info.handled_debug_data();
+
                     let dbg_data: Option<GetAllData> = message.into();
                     if let Some(data) = dbg_data {
                         get_all_data::handle(data, info);
@@ -118,6 +201,22 @@ pub async fn start_listen(
                     anc::handle(message.into(), info);
                 }
 
+                raw_message::ids::NOISE_CONTROLS_UPDATE => {
+                    settings::noise_control::handle_update(
+                        &message.get_payload_bytes(),
+                        &mut info.inner,
+                    );
+                }
+
+                raw_message::ids::VERSION_INFO => {
+                    info.inner.firmware = FirmwareVersion::decode(&message.get_payload_bytes());
+                    firmware_change::handle(info, &config).await;
+                }
+
+                raw_message::ids::CHECK_FIT_RESULT => {
+                    info.fit_test = FitTestResult::decode(&message.get_payload_bytes());
+                }
+
                 ids::USAGE_REPORT => {
                     let report = UsageReport::new(message.get_payload_bytes());
                     println!("{report:#?}");
@@ -126,16 +225,75 @@ pub async fn start_listen(
                 _ => (),
             };
 
+            let settings_changed = settings::snapshot(&info.inner) != previous_settings;
+
+            // The first extended status only loads the current settings
+            let external = if previous.ready {
+                external_change::detect(&previous_settings, info)
+            } else {
+                Vec::new()
+            };
+
+            // Bring the buds back to the wanted state on connect and after changes
+            let written = if info.inner.ready && (!previous.ready || settings_changed) {
+                write_back::handle(&external, info, &config, &connection).await
+            } else {
+                Vec::new()
+            };
+            external_change::publish(external, &written, info, &connection.addr);
+
+            // Buds lost while being worn are back
+            if info.inner.ready && !previous.ready {
+                lost::handle_reconnect(info, &connection_handler.connection_data, &config).await;
+            }
+
+            // Account the time since the last message to the session
+            info.session.update(&previous);
+
+            // Collect changed values and events for subscribers
+            let changes = status_diff::diff_event(&previous, &info.inner);
+            let events = std::mem::take(&mut info.events);
+            if let Some(last) = events.last().or_else(|| changes.as_ref()) {
+                info.last_event = Some(last.clone());
+            }
+
             // Send debug request at an appropriate interval
-            if !requested_debug || info.last_debug.elapsed().unwrap_or_default().as_secs() >= 8 {
+            let interval = polling::interval(has_subscribers, info, &config).await;
+            info.stats.polling = interval.is_some();
+            let due = interval.map_or(false, |i| {
+                info.last_debug.elapsed().unwrap_or_default() >= i
+            });
+            if !requested_debug || due {
                 if let Err(err) = info.request_debug_data().await {
                     println!("Error sending debug request {:?}", err);
                 }
+
+                // Ask again until the settings are known
+                if !info.inner.ready {
+                    if let Err(err) = info.request_extended_status().await {
+                        println!("Error requesting settings {:?}", err);
+                    }
+                }
+
+                // Check for a headset profile at the same interval
+                sink::handle_auto_mic(info, &connection_handler.connection_data, &config);
             }
 
             if !requested_debug {
                 requested_debug = true;
             }
+
+            // Notify subscribers
+            if let Some(changes) = changes {
+                lock.publish(&connection.addr, &changes);
+            }
+            for event in events {
+                lock.publish(&connection.addr, &event);
+            }
+
+            if let Some(info) = lock.get_device_mut(&connection.addr) {
+                info.stats.record_handling(handling.elapsed());
+            }
         }
 
         if first_msg {
@@ -145,8 +303,17 @@ pub async fn start_listen(
         // Disconnect from device
         if disconnect_afterwards {
             println!("Disconnecting from device {}", connection.addr);
-            ch.lock().await.remove_device(&connection.addr).await;
+            ch.lock()
+                .await
+                .remove_device(&connection.addr, DisconnectReason::Requested)
+                .await;
             return;
         }
     }
 }
+
+fn frame_hash(frame: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    frame.hash(&mut hasher);
+    hasher.finish()
+}
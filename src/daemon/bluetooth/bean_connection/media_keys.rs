@@ -0,0 +1,48 @@
+use super::super::super::{
+    buds_config::MediaKeysConfig,
+    buds_info::BudsInfo,
+    media_keys::{self, MediaKey},
+};
+use super::utils;
+
+use galaxy_buds_rs::message::{
+    bud_property::Side, status_updated::StatusUpdate, touchpad_action::TouchAction,
+};
+
+/// Emit the configured keys when the buds get put on or taken off
+pub fn handle_wear(update: &StatusUpdate, info: &BudsInfo, config: &MediaKeysConfig) {
+    let was_wearing =
+        utils::is_wearing_state(info.inner.placement_left, info.inner.placement_right);
+    let is_wearing = utils::is_wearing_state(update.placement_left, update.placement_right);
+    let was_some_wearing =
+        utils::is_some_wearing_state(info.inner.placement_left, info.inner.placement_right);
+    let is_some_wearing =
+        utils::is_some_wearing_state(update.placement_left, update.placement_right);
+
+    if !was_wearing && is_wearing {
+        emit(&config.put_on);
+    } else if was_some_wearing && !is_some_wearing {
+        emit(&config.take_off);
+    }
+}
+
+/// Emit the configured key for a touch and hold
+pub fn handle_touch(tap_info: &TouchAction, config: &MediaKeysConfig) {
+    // The buds report a touch and hold as touch count 7
+    if tap_info.touch_count != 7 {
+        return;
+    }
+
+    match tap_info.side {
+        Side::Left => emit(&config.hold_left),
+        Side::Right => emit(&config.hold_right),
+    }
+}
+
+fn emit(key: &Option<String>) {
+    if let Some(key) = key.as_deref().and_then(MediaKey::parse) {
+        if let Err(err) = media_keys::press(key) {
+            eprintln!("Can't emit media key: {}", err);
+        }
+    }
+}
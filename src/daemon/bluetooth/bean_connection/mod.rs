@@ -1,9 +1,16 @@
 mod ambient_mode;
 mod anc;
+mod battery_only;
 mod extended_status_update;
+mod external_change;
+mod firmware_change;
 mod get_all_data;
 pub mod listener;
+mod media_keys;
+pub mod session_end;
 mod sink;
 mod status_update;
+pub mod supervisor;
 mod touchpad;
-mod utils;
+pub mod utils;
+mod write_back;
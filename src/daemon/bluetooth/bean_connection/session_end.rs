@@ -0,0 +1,47 @@
+use super::super::super::{
+    buds_config::Config, buds_info::BudsInfo, history, notifications::Category, status_diff,
+};
+use super::super::rfcomm_connector::ConnectionData;
+use super::utils;
+
+use async_std::sync::{Arc, Mutex};
+
+/// Summarize the session of a disconnected device
+pub async fn handle(
+    mut info: BudsInfo,
+    connection_data: &Arc<Mutex<ConnectionData>>,
+    config: &Arc<Mutex<Config>>,
+) {
+    let summary = info.session.summary(&info.inner);
+
+    // Ignore devices which disconnected right away
+    if !info.inner.ready {
+        return;
+    }
+
+    let event = status_diff::named_event(
+        &summary.device,
+        "session_summary",
+        serde_json::to_value(&summary).unwrap_or_default(),
+    );
+    connection_data
+        .lock()
+        .await
+        .publish(&summary.device, &event);
+
+    if let Err(err) = history::append("session", &summary).await {
+        eprintln!("Can't write session to history: {}", err);
+    }
+
+    let cfg = config.lock().await;
+    let enabled = cfg
+        .get_device_config(&summary.device)
+        .map(|i| i.session_summary_notification())
+        .unwrap_or(false);
+
+    if enabled {
+        let notification = utils::get_session_summary_notification(&summary);
+        info.notifier
+            .show(Category::SessionSummary, notification, &cfg);
+    }
+}
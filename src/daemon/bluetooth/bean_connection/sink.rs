@@ -1,7 +1,22 @@
+use super::super::rfcomm_connector::ConnectionData;
 use super::utils;
+use crate::daemon::buds_config::Config;
+#[cfg(feature = "pulse-sink")]
+use crate::daemon::buds_config::MediaControlBackend;
 use crate::daemon::buds_info::BudsInfo;
+#[cfg(feature = "pulse-sink")]
+use crate::daemon::media_control;
+use crate::daemon::mic;
+use crate::daemon::notifications::Category;
+#[cfg(feature = "pulse-sink")]
+use crate::daemon::sink_mute;
+use crate::daemon::status_diff;
 
-use galaxy_buds_rs::message::status_updated::StatusUpdate;
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use galaxy_buds_rs::message::{bud_property::Placement, status_updated::StatusUpdate};
 
 #[cfg(feature = "pulse-sink")]
 use pulsectl::controllers::{types::DeviceInfo, DeviceControl, SinkController};
@@ -20,10 +35,27 @@ pub fn make_sink_default(info: &BudsInfo) -> Option<()> {
     None
 }
 
-pub fn handle_low_battery(update: &StatusUpdate, info: &mut BudsInfo) {
+pub fn handle_low_battery(update: &StatusUpdate, info: &mut BudsInfo, config: &Config) {
     let l_batt = update.battery_left;
     let r_batt = update.battery_right;
 
+    // Warn once more if a bud is about to turn off
+    if (l_batt <= utils::CRITICAL_BATTERY || r_batt <= utils::CRITICAL_BATTERY)
+        && l_batt > 0
+        && r_batt > 0
+    {
+        if !info.did_critical_battery_notify {
+            info.did_critical_battery_notify = true;
+            info.notifier.show(
+                Category::CriticalBattery,
+                utils::get_critical_battery_notification(l_batt, r_batt),
+                config,
+            );
+        }
+    } else if l_batt > 10 && r_batt > 10 {
+        info.did_critical_battery_notify = false;
+    }
+
     // Reset battery notify lock
     if l_batt > 30 && r_batt > 30 && info.inner.did_battery_notify {
         info.inner.did_battery_notify = false;
@@ -36,12 +68,120 @@ pub fn handle_low_battery(update: &StatusUpdate, info: &mut BudsInfo) {
     }
 
     // Display a notification below 20% (both have to be above 0%)
-    if l_batt < 20 || r_batt < 20 && (l_batt * r_batt > 0) {
+    if l_batt < utils::LOW_BATTERY || r_batt < utils::LOW_BATTERY && (l_batt * r_batt > 0) {
         info.inner.did_battery_notify = true;
-        utils::get_desktop_notification(l_batt, r_batt)
-            .show()
-            .unwrap();
+        info.notifier.show(
+            Category::LowBattery,
+            utils::get_desktop_notification(l_batt, r_batt),
+            config,
+        );
+    }
+}
+
+/// Tell how long charging takes once it can be estimated for both buds in the case
+pub fn handle_charging(info: &mut BudsInfo, config: &Config) {
+    let charging = match info.inner.charging {
+        Some(ref v) if !info.charge.notified => v.clone(),
+        _ => return,
+    };
+
+    let in_case = |p: Placement| p == Placement::InOpenCase || p == Placement::InCloseCase;
+    let complete = (charging.left.is_some() || !in_case(info.inner.placement_left))
+        && (charging.right.is_some() || !in_case(info.inner.placement_right));
+    if !complete {
+        return;
+    }
+
+    info.charge.notified = true;
+    info.notifier.show(
+        Category::Charging,
+        utils::get_charging_notification(&charging),
+        config,
+    );
+}
+
+/// Remind the user to charge the case once its battery drops below the threshold
+pub fn handle_case_battery(
+    update: &StatusUpdate,
+    info: &mut BudsInfo,
+    threshold: i8,
+    config: &Config,
+) {
+    let c_batt = update.battery_case;
+
+    // The case battery is only known while the buds are inside
+    if c_batt <= 0 {
+        return;
+    }
+
+    // Reset once the case got charged
+    if c_batt > threshold {
+        info.did_case_battery_notify = false;
+        return;
+    }
+
+    if info.did_case_battery_notify {
+        return;
     }
+
+    info.did_case_battery_notify = true;
+    info.events.push(status_diff::named_event(
+        &info.inner.address,
+        "case_battery_low",
+        serde_json::json!({ "battery": c_batt, "threshold": threshold }),
+    ));
+    info.notifier.show(
+        Category::CaseBattery,
+        utils::get_case_battery_notification(c_batt, threshold),
+        config,
+    );
+}
+
+/// Make the buds mic the default input once a headset profile got activated.
+/// Talking to pulseaudio blocks, so it happens in the background without
+/// holding the connection data lock
+pub fn handle_auto_mic(
+    info: &BudsInfo,
+    cd: &Arc<Mutex<ConnectionData>>,
+    config: &Arc<Mutex<Config>>,
+) {
+    let address = info.inner.address.clone();
+    let cd = Arc::clone(cd);
+    let config = Arc::clone(config);
+
+    task::spawn(async move {
+        let enabled = config
+            .lock()
+            .await
+            .get_device_config(&address)
+            .map(|i| i.auto_mic())
+            .unwrap_or(false);
+
+        if !enabled {
+            return;
+        }
+
+        let addr = address.clone();
+        let has_source = task::spawn_blocking(move || {
+            mic::list_sources(&addr)
+                .map(|i| !i.is_empty())
+                .unwrap_or(false)
+        })
+        .await;
+
+        let was_active = match cd.lock().await.get_device_mut(&address) {
+            Some(info) => std::mem::replace(&mut info.mic_source_active, has_source),
+            None => return,
+        };
+
+        // Only switch once, so users can still choose another input
+        if has_source && !was_active {
+            let res = task::spawn_blocking(move || mic::set_default(&address)).await;
+            if let Err(err) = res {
+                eprintln!("Can't set default input: {}", err);
+            }
+        }
+    });
 }
 
 // Return true if Earbuds are currently the default output device
@@ -54,7 +194,11 @@ pub fn is_default(handler: &mut SinkController, info: &BudsInfo) -> Option<bool>
 
 // Change the default output sink to fallback if buds are placed into the case
 #[cfg(feature = "pulse-sink")]
-pub fn fallback_to_sink(info: &mut BudsInfo, update: &StatusUpdate) -> Option<()> {
+pub fn fallback_to_sink(
+    info: &mut BudsInfo,
+    update: &StatusUpdate,
+    backend: MediaControlBackend,
+) -> Option<()> {
     let was_in_case = utils::is_placed_state(info.inner.placement_left, info.inner.placement_right);
     let is_in_case = utils::is_placed_state(update.placement_left, update.placement_right);
 
@@ -76,9 +220,13 @@ pub fn fallback_to_sink(info: &mut BudsInfo, update: &StatusUpdate) -> Option<()
         // TODO make configurable
         // Continue music if stopped by putting into case
         if info.inner.paused_music_earlier {
-            utils::try_play();
+            media_control::resume(backend);
             info.inner.paused_music_earlier = false;
         }
+        if info.muted_sink {
+            sink_mute::unmute();
+            info.muted_sink = false;
+        }
     }
 
     None
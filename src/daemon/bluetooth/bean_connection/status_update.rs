@@ -1,13 +1,20 @@
-use std::process::exit;
+use std::{process::exit, time::Instant};
 
-use super::super::super::buds_config::{BudsConfig, Config};
+use super::super::super::buds_config::{BudsConfig, Config, MediaControlBackend, PauseTrigger};
 use super::super::super::buds_info::BudsInfo;
+use super::super::super::charging;
+use super::super::super::media_control::{self, Paused};
+use super::super::super::pactl;
+use super::super::super::settings;
+use super::super::super::sink_mute;
+use super::super::super::status_diff;
 use super::super::bt_connection_listener::BudsConnection;
+use super::media_keys;
 use super::sink;
 use super::utils;
 
 use async_std::sync::{Arc, Mutex};
-use galaxy_buds_rs::message::status_updated::StatusUpdate;
+use galaxy_buds_rs::message::{bud_property::Placement, status_updated::StatusUpdate};
 
 #[cfg(feature = "pulse-sink")]
 use pulsectl::controllers::SinkController;
@@ -44,21 +51,92 @@ pub async fn handle(
             handle_auto_music(&update, info, &config);
         }
 
+        // Emit media keys on wear changes
+        if let Some(ref keys) = config.media_keys {
+            media_keys::handle_wear(&update, info, keys);
+        }
+
+        // Lock touchpads while the buds are in the case
+        if config.touchpad_lock_in_case() {
+            handle_case_touchpad_lock(&update, info).await;
+        }
+
         // handle desktop notification
         if config.low_battery_notification() {
-            sink::handle_low_battery(&update, info);
+            sink::handle_low_battery(&update, info, &cfg);
+        }
+
+        // Remind to charge the case
+        if let Some(threshold) = config.case_battery_reminder() {
+            sink::handle_case_battery(&update, info, threshold, &cfg);
         }
 
         // Fallback to next available sink if buds
         // get placed into the case
         #[cfg(feature = "pulse-sink")]
         if config.smart_sink() {
-            sink::fallback_to_sink(info, &update);
+            sink::fallback_to_sink(info, &update, config.media_control_backend());
         }
     }
 
+    // Let hooks mute the mic of meetings
+    handle_removed_during_input(&update, info).await;
+
     // Update the local status of the buds
     update_status(&update, info);
+    charging::update(info);
+
+    let notify_charging = cfg
+        .get_device_config(&connection.addr)
+        .map(|i| i.charging_notification())
+        .unwrap_or(false);
+    if notify_charging {
+        sink::handle_charging(info, &cfg);
+    }
+}
+
+/// Lock the touchpads when the buds get placed into the case and unlock them once worn again
+async fn handle_case_touchpad_lock(update: &StatusUpdate, info: &mut BudsInfo) {
+    let was_in_case =
+        utils::is_in_case_state(info.inner.placement_left, info.inner.placement_right);
+    let is_in_case = utils::is_in_case_state(update.placement_left, update.placement_right);
+    let is_some_wearing =
+        utils::is_some_wearing_state(update.placement_left, update.placement_right);
+
+    let locked = settings::get(&info.inner, "lock_touchpad", None).map_or(false, |i| i.as_bool());
+
+    if !was_in_case && is_in_case && !locked {
+        if let Err(err) = settings::set(info, "lock_touchpad", "true", None, false).await {
+            eprintln!("Error locking touchpads: {}", err);
+            return;
+        }
+        info.touchpad_locked_by_case = true;
+    } else if is_some_wearing && info.touchpad_locked_by_case {
+        // Only unlock if we locked them earlier
+        if let Err(err) = settings::set(info, "lock_touchpad", "false", None, false).await {
+            eprintln!("Error unlocking touchpads: {}", err);
+            return;
+        }
+        info.touchpad_locked_by_case = false;
+    }
+}
+
+/// Publish an event if both buds get removed while an application records audio
+async fn handle_removed_during_input(update: &StatusUpdate, info: &mut BudsInfo) {
+    let was_wearing =
+        utils::is_some_wearing_state(info.inner.placement_left, info.inner.placement_right);
+    let is_wearing = utils::is_some_wearing_state(update.placement_left, update.placement_right);
+    if !was_wearing || is_wearing {
+        return;
+    }
+
+    if async_std::task::spawn_blocking(pactl::has_input_stream).await {
+        info.events.push(status_diff::named_event(
+            &info.inner.address,
+            "both_buds_removed_during_input_stream",
+            serde_json::Value::Null,
+        ));
+    }
 }
 
 /// Handle automatically pausing/playing music on earbuds wearing statu changes
@@ -68,17 +146,23 @@ fn handle_auto_music(update: &StatusUpdate, info: &mut BudsInfo, config: &BudsCo
     let was_wearing =
         utils::is_wearing_state(info.inner.placement_left, info.inner.placement_right);
 
-    let was_some_wearing =
-        utils::is_some_wearing_state(info.inner.placement_left, info.inner.placement_right);
-
-    let is_some_wearing_state =
-        utils::is_some_wearing_state(update.placement_left, update.placement_right);
+    // Whether the configured removal trigger is fulfilled
+    let was_removed = counts_as_removed(
+        info.inner.placement_left,
+        info.inner.placement_right,
+        config,
+    );
+    let is_removed = counts_as_removed(update.placement_left, update.placement_right, config);
 
     #[cfg(feature = "pulse-sink")]
     let handler = SinkController::create();
 
     // True if put buds on
     if !was_wearing && is_wearing {
+        // Unmute a sink muted on removal
+        sink_mute::unmute();
+        info.muted_sink = false;
+
         // Auto sink change
         #[cfg(feature = "pulse-sink")]
         if config.smart_sink() {
@@ -93,11 +177,14 @@ fn handle_auto_music(update: &StatusUpdate, info: &mut BudsInfo, config: &BudsCo
 
         // Auto resume
         if config.auto_play() && info.inner.paused_music_earlier {
-            utils::try_play();
+            if should_resume(info, config) {
+                media_control::resume(config.media_control_backend());
+            }
             info.inner.paused_music_earlier = false;
+            info.paused_player = None;
         }
-    } else if !is_some_wearing_state && was_some_wearing {
-        // True if take the buds off
+    } else if is_removed && !was_removed {
+        // True if the buds were taken off
 
         // Don't do music actions if buds aren't default device
         #[cfg(feature = "pulse-sink")]
@@ -107,9 +194,65 @@ fn handle_auto_music(update: &StatusUpdate, info: &mut BudsInfo, config: &BudsCo
 
         if config.auto_pause() {
             // Auto pause music
-            if utils::try_pause() {
-                info.inner.paused_music_earlier = true;
+            let backend = config.media_control_backend();
+            match media_control::pause(backend) {
+                Some(Paused::Player(player)) => {
+                    info.inner.paused_music_earlier = true;
+                    info.paused_player = Some((player, Instant::now()));
+                }
+                Some(Paused::Sink) => info.muted_sink = true,
+                None => (),
             }
+
+            // Cover apps which ignore the pause. The pulseaudio backend mutes until worn again
+            if let Some(duration) = config.mute_on_pause() {
+                if backend == MediaControlBackend::Mpris {
+                    sink_mute::mute_for(duration);
+                }
+            }
+        }
+    }
+}
+
+// Check the conditions for resuming the player paused on removal
+fn should_resume(info: &BudsInfo, config: &BudsConfig) -> bool {
+    let (player, paused_at) = match info.paused_player {
+        Some(ref v) => v,
+        None => return false,
+    };
+
+    if let Some(max) = config.resume_within() {
+        if paused_at.elapsed() > max {
+            return false;
+        }
+    }
+
+    let ignored = config.resume_ignore_players().iter().any(|i| {
+        let i = i.to_lowercase();
+        player.identity.to_lowercase().contains(&i) || player.bus_name.to_lowercase().contains(&i)
+    });
+    if ignored {
+        return false;
+    }
+
+    // Another player might have started in the meantime
+    config.media_control_backend() != MediaControlBackend::Mpris
+        || !config.resume_same_player()
+        || media_control::active_player().as_ref() == Some(&player.bus_name)
+}
+
+// Returns true if the buds count as removed for auto pausing
+fn counts_as_removed(left: Placement, right: Placement, config: &BudsConfig) -> bool {
+    match config.auto_pause_trigger() {
+        PauseTrigger::Either => !utils::is_wearing_state(left, right),
+        PauseTrigger::Both => !utils::is_some_wearing_state(left, right),
+        PauseTrigger::PrimaryOnly => {
+            let primary = if config.primary_is_left() {
+                left
+            } else {
+                right
+            };
+            primary != Placement::Ear
         }
     }
 }
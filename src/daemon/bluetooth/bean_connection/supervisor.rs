@@ -0,0 +1,150 @@
+use super::super::super::{
+    buds_config::Config, buds_info::ConnectionState, connection_history::DisconnectReason,
+    raw_message,
+};
+use super::super::{
+    bt_connection_listener::BudsConnection,
+    rfcomm_connector::{self, ConnHandler},
+};
+use super::{battery_only, listener};
+
+use async_std::sync::{Arc, Mutex};
+use futures_lite::FutureExt;
+use galaxy_buds_rs::model::Model;
+use log::{error, info};
+
+use std::{
+    panic::AssertUnwindSafe,
+    time::{Duration, Instant},
+};
+
+const MAX_RESTARTS: usize = 5;
+const RESTART_DELAY: Duration = Duration::from_secs(2);
+// A connection running that long without panicking counts as stable again
+const STABLE_UPTIME: Duration = Duration::from_secs(10 * 60);
+
+/// Runs the listener of a connection. If it panics (eg. because of a malformed
+/// frame), the panic gets logged together with the last received frame and the
+/// connection gets restarted
+pub async fn supervise(
+    connection: BudsConnection,
+    config: Arc<Mutex<Config>>,
+    ch: Arc<Mutex<ConnHandler>>,
+    model: Model,
+    battery_only: bool,
+) {
+    let address = connection.addr.clone();
+    let cd = Arc::clone(&ch.lock().await.connection_data);
+    let last_frame = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let mut connection = Some(connection);
+    let mut restarts = 0;
+
+    while let Some(conn) = connection.take() {
+        let started = Instant::now();
+        let res = if battery_only {
+            AssertUnwindSafe(battery_only::start_listen(
+                conn,
+                Arc::clone(&ch),
+                Arc::clone(&last_frame),
+            ))
+            .catch_unwind()
+            .await
+        } else {
+            AssertUnwindSafe(listener::start_listen(
+                conn,
+                Arc::clone(&config),
+                Arc::clone(&ch),
+                model,
+                Arc::clone(&last_frame),
+            ))
+            .catch_unwind()
+            .await
+        };
+
+        // Regular disconnect
+        if res.is_ok() {
+            return;
+        }
+
+        let frame = last_frame
+            .lock()
+            .map(|i| raw_message::to_hex(&i))
+            .unwrap_or_default();
+        error!(
+            "Connection handler of {} panicked. Last frame: [{}]",
+            address, frame
+        );
+
+        // Forget the (possibly broken) state of the device but keep it
+        // reserved, so no other connection gets opened while restarting
+        {
+            let mut ch = ch.lock().await;
+            ch.remove_device(&address, DisconnectReason::HandlerPanic)
+                .await;
+            ch.reserve_device(&address);
+        }
+
+        let last_error = "Connection handler crashed".to_owned();
+
+        // Only give up on connections crashing again and again
+        if started.elapsed() >= STABLE_UPTIME {
+            restarts = 0;
+        }
+
+        restarts += 1;
+        if restarts > MAX_RESTARTS {
+            error!("Too many restarts. Giving up on {}", address);
+            let state = ConnectionState {
+                last_error: Some(last_error),
+                ..ConnectionState::default()
+            };
+            cd.lock().await.set_connection_state(&address, state);
+            ch.lock().await.release_device(&address);
+            return;
+        }
+
+        let state = ConnectionState::backoff(RESTART_DELAY, last_error.clone());
+        cd.lock().await.set_connection_state(&address, state);
+        async_std::task::sleep(RESTART_DELAY).await;
+
+        let state = ConnectionState {
+            connecting: true,
+            backoff_until: None,
+            last_error: Some(last_error.clone()),
+        };
+        cd.lock().await.set_connection_state(&address, state);
+
+        connection = match reconnect(&address).await {
+            Ok(c) => {
+                info!("Restarted connection to {}", address);
+
+                // Keep the error which made the connection restart
+                let state = ConnectionState {
+                    last_error: Some(last_error),
+                    ..ConnectionState::default()
+                };
+                cd.lock().await.set_connection_state(&address, state);
+                Some(c)
+            }
+            Err(err) => {
+                error!("Couldn't restart connection to {}: {}", address, err);
+                ch.lock().await.release_device(&address);
+                let state = ConnectionState {
+                    last_error: Some(err),
+                    ..ConnectionState::default()
+                };
+                cd.lock().await.set_connection_state(&address, state);
+                None
+            }
+        };
+    }
+}
+
+async fn reconnect(address: &str) -> Result<BudsConnection, String> {
+    if address == rfcomm_connector::SIMULATOR_ADDRESS {
+        let path = std::env::var(rfcomm_connector::SIMULATOR_ENV).map_err(|e| e.to_string())?;
+        return rfcomm_connector::connect_simulator(path).await;
+    }
+
+    rfcomm_connector::connect_rfcomm(address)
+}
@@ -5,6 +5,7 @@ use std::time::SystemTime;
 use super::super::super::unix_socket::bluetooth_commands;
 use super::super::super::{buds_config::Config, buds_info::BudsInfo};
 use super::super::bt_connection_listener::BudsConnection;
+use super::media_keys;
 
 use async_std::sync::{Arc, Mutex};
 use galaxy_buds_rs::message::{
@@ -23,6 +24,16 @@ pub async fn handle(
 ) -> bool {
     let early_exit;
 
+    // Emit configured media keys
+    if let Some(keys) = config
+        .lock()
+        .await
+        .get_device_config(&connection.addr)
+        .and_then(|i| i.media_keys.clone())
+    {
+        media_keys::handle_touch(&tap_info, &keys);
+    }
+
     if info.inner.touchpads_blocked {
         // Separate config logic to keep cfg locked as short as possible
         early_exit = {
@@ -1,33 +1,21 @@
 #![allow(dead_code)]
 
+use crate::daemon::charging::{ChargeEstimate, Charging};
+use crate::daemon::notifications::Message;
+use crate::daemon::session::SessionSummary;
+
 use galaxy_buds_rs::message::bud_property::Placement;
-use mpris::{Player, PlayerFinder};
 use notify_rust::Notification;
 
-fn get_finder() -> Option<PlayerFinder> {
-    PlayerFinder::new().ok()
-}
-
-fn get_player(finder: &PlayerFinder) -> Option<Player> {
-    finder.find_active().ok()
-}
-
-pub fn try_pause() -> bool {
-    get_finder()
-        .and_then(|finder| get_player(&finder).and_then(|player| player.pause().ok()))
-        .is_some()
-}
-
-pub fn try_play() -> bool {
-    get_finder()
-        .and_then(|finder| get_player(&finder).and_then(|player| player.play().ok()))
-        .is_some()
-}
-
 pub fn is_placed_state(left: Placement, right: Placement) -> bool {
     left == Placement::InOpenCase && right == Placement::InOpenCase
 }
 
+pub fn is_in_case_state(left: Placement, right: Placement) -> bool {
+    let in_case = |p: Placement| p == Placement::InOpenCase || p == Placement::InCloseCase;
+    in_case(left) && in_case(right)
+}
+
 pub fn is_some_wearing_state(left: Placement, right: Placement) -> bool {
     left == Placement::Ear || right == Placement::Ear
 }
@@ -36,8 +24,12 @@ pub fn is_wearing_state(left: Placement, right: Placement) -> bool {
     left == Placement::Ear && right == Placement::Ear
 }
 
-pub fn get_desktop_notification(l_batt: i8, r_batt: i8) -> Notification {
-    Notification::new()
+// Thresholds of the battery notifications
+pub const LOW_BATTERY: i8 = 20;
+pub const CRITICAL_BATTERY: i8 = 5;
+
+pub fn get_desktop_notification(l_batt: i8, r_batt: i8) -> Message {
+    let notification = Notification::new()
         .summary("Buds Live battery low")
         .body(
             format!(
@@ -47,5 +39,121 @@ pub fn get_desktop_notification(l_batt: i8, r_batt: i8) -> Notification {
             .as_str(),
         )
         .icon("battery")
-        .to_owned()
+        .to_owned();
+
+    Message::new(notification)
+        .value("left", l_batt)
+        .value("right", r_batt)
+        .value("threshold", LOW_BATTERY)
+}
+
+pub fn get_critical_battery_notification(l_batt: i8, r_batt: i8) -> Message {
+    let notification = Notification::new()
+        .summary("Buds battery critical")
+        .body(
+            format!(
+                "Your buds are about to turn off: (L: {}%, R: {}%)",
+                l_batt, r_batt
+            )
+            .as_str(),
+        )
+        .icon("battery-caution")
+        .to_owned();
+
+    Message::new(notification)
+        .value("left", l_batt)
+        .value("right", r_batt)
+        .value("threshold", CRITICAL_BATTERY)
+}
+
+pub fn get_connect_notification(l_batt: i8, r_batt: i8, c_batt: i8) -> Message {
+    let notification = Notification::new()
+        .summary("Buds connected")
+        .body(
+            format!(
+                "Battery: (L: {}%, R: {}%, Case: {}%)",
+                l_batt, r_batt, c_batt
+            )
+            .as_str(),
+        )
+        .icon("audio-headphones")
+        .to_owned();
+
+    Message::new(notification)
+        .value("left", l_batt)
+        .value("right", r_batt)
+        .value("case", c_batt)
+}
+
+pub fn get_case_battery_notification(c_batt: i8, threshold: i8) -> Message {
+    let notification = Notification::new()
+        .summary("Buds case battery low")
+        .body(format!("Don't forget to charge your case: ({}%)", c_batt).as_str())
+        .icon("battery-low")
+        .to_owned();
+
+    Message::new(notification)
+        .value("case", c_batt)
+        .value("threshold", threshold)
+}
+
+pub fn get_charging_notification(charging: &Charging) -> Message {
+    let side = |name: &str, estimate: Option<ChargeEstimate>| {
+        estimate.map(|i| format!("{} full in {} min", name, i.minutes_to_full))
+    };
+    let sides: Vec<String> = vec![side("L", charging.left), side("R", charging.right)]
+        .into_iter()
+        .flatten()
+        .collect();
+
+    let notification = Notification::new()
+        .summary("Buds charging")
+        .body(sides.join(", ").as_str())
+        .icon("battery-good-charging")
+        .to_owned();
+
+    // Minutes until the buds are full
+    let minutes = |estimate: Option<ChargeEstimate>| {
+        estimate
+            .map(|i| i.minutes_to_full.to_string())
+            .unwrap_or_else(|| "?".to_owned())
+    };
+    Message::new(notification)
+        .value("left", minutes(charging.left))
+        .value("right", minutes(charging.right))
+}
+
+pub fn get_firmware_change_notification(previous: &str, current: &str) -> Message {
+    let notification = Notification::new()
+        .summary("Buds firmware updated")
+        .body(format!("The firmware changed from {} to {}", previous, current).as_str())
+        .icon("software-update-available")
+        .to_owned();
+
+    Message::new(notification)
+        .value("previous", previous)
+        .value("current", current)
+}
+
+pub fn get_session_summary_notification(summary: &SessionSummary) -> Message {
+    let notification = Notification::new()
+        .summary("Buds disconnected")
+        .body(
+            format!(
+                "Worn for {} min, used L: {}%, R: {}%, ANC on {}% of the time",
+                summary.worn_secs / 60,
+                summary.battery_used_left,
+                summary.battery_used_right,
+                summary.anc_percent
+            )
+            .as_str(),
+        )
+        .icon("audio-headphones")
+        .to_owned();
+
+    Message::new(notification)
+        .value("minutes", summary.worn_secs / 60)
+        .value("left", summary.battery_used_left)
+        .value("right", summary.battery_used_right)
+        .value("anc", summary.anc_percent)
 }
@@ -0,0 +1,128 @@
+/*
+ * The only place writing settings to the buds without a request: touch and
+ * hold actions saved in the config (eg. after a factory reset), the desired
+ * state and values changed by other apps. All of it only happens with
+ * `source_of_truth = "daemon"`, otherwise the buds are left as they are.
+ */
+
+use super::super::super::{
+    buds_config::{BudsConfig, Config},
+    buds_info::BudsInfo,
+    settings::{self, Setting, Value},
+    status_diff, utils,
+};
+use super::super::bt_connection_listener::BudsConnection;
+
+use async_std::sync::{Arc, Mutex};
+use galaxy_buds_rs::message::bud_property::TouchpadOption;
+use log::{error, info};
+use serde_json::{json, Map};
+
+use std::collections::BTreeMap;
+
+// Values to write by setting key and parameter
+type Wanted = BTreeMap<(&'static str, Option<&'static str>), (&'static dyn Setting, Value)>;
+
+/// Set every setting which differs from what the user wants. `external` are the
+/// settings other apps changed. Returns the names of the written settings
+pub async fn handle(
+    external: &[(&'static str, Option<&'static str>, Value)],
+    info: &mut BudsInfo,
+    config: &Arc<Mutex<Config>>,
+    connection: &BudsConnection,
+) -> Vec<String> {
+    let mut wanted = match config.lock().await.get_device_config(&connection.addr) {
+        Some(cfg) if cfg.reassert_settings() => from_config(cfg),
+        _ => return Vec::new(),
+    };
+
+    // Values the user has set through the daemon win over the config
+    for (key, param, _) in external {
+        if let (Some(setting), Some(value)) = (
+            settings::find(key),
+            info.desired_settings.get(&(*key, *param)),
+        ) {
+            wanted.insert((key, *param), (setting, *value));
+        }
+    }
+
+    let mut drift = Map::new();
+
+    for ((key, param), (setting, value)) in wanted {
+        // Skip settings the model doesn't support
+        let current = match settings::get(&info.inner, key, param) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        if current == value {
+            continue;
+        }
+
+        let name = match param {
+            Some(param) => format!("{}:{}", key, param),
+            None => key.to_string(),
+        };
+
+        match settings::set_value(info, setting, value, param).await {
+            Ok(_) => {
+                info!(
+                    "Restored {} of {}: {} -> {}",
+                    name, connection.addr, current, value
+                );
+                drift.insert(name, json!({ "from": current, "to": value }));
+            }
+            Err(err) => error!("Can't restore {} of {}: {}", name, connection.addr, err),
+        }
+    }
+
+    let written = drift.keys().cloned().collect();
+
+    if !drift.is_empty() {
+        info.events.push(status_diff::named_event(
+            &connection.addr,
+            "drift_corrected",
+            json!(drift),
+        ));
+    }
+
+    written
+}
+
+// Touch and hold actions and the desired state of the device config
+fn from_config(cfg: &BudsConfig) -> Wanted {
+    let mut wanted = Wanted::new();
+
+    if let Some(setting) = settings::find("touchpad_action") {
+        let touchpad = [
+            (Some("left"), &cfg.touchpad_option_left),
+            (Some("right"), &cfg.touchpad_option_right),
+        ];
+
+        for (param, option) in touchpad.iter() {
+            let option = match option.as_deref().map(utils::str_to_touchpad_option) {
+                Some(TouchpadOption::Undetected) | None => continue,
+                Some(option) => option,
+            };
+            wanted.insert(
+                (setting.key(), *param),
+                (setting, Value::Number(option.encode())),
+            );
+        }
+    }
+
+    for (key, param, value) in cfg.desired_state() {
+        let (setting, value) = match settings::parse(&key, param.as_deref(), &value) {
+            Ok(v) => v,
+            Err(err) => {
+                error!("Invalid desired state {} of {}: {}", key, cfg.address, err);
+                continue;
+            }
+        };
+
+        for param in setting.affected_params(param.as_deref()) {
+            wanted.insert((setting.key(), param), (setting, value));
+        }
+    }
+
+    wanted
+}
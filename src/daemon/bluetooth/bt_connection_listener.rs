@@ -3,30 +3,37 @@
  * forwards connection events to the connector
  */
 
+use async_std::os::unix::net::UnixStream;
 use bluetooth_serial_port_async::BtSocket;
 use blurz::{
     BluetoothAdapter, BluetoothDevice,
     BluetoothEvent::{self, Connected},
     BluetoothSession,
 };
-use galaxy_buds_rs::model::Model;
-use log::debug;
+use log::{debug, info};
 
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::SyncSender;
 use std::time::Duration;
 
+use super::super::{models, seat_lock::SeatLock};
 use super::rfcomm_connector::ConnectionEventData;
 
 /// An active connection to a pair of buds
 #[derive(Debug)]
 pub struct BudsConnection {
     pub addr: String,
-    pub socket: BtSocket,
+    // None for simulated buds
+    pub socket: Option<BtSocket>,
+    pub stream: UnixStream,
     pub fd: i32,
+    // Held as long as the connection is alive
+    pub seat_lock: Option<SeatLock>,
+    // Name of the device set in BlueZ
+    pub alias: Option<String>,
 }
 
 /// Listens for new Bluethooth connections
-pub fn run(sender: Sender<ConnectionEventData>) {
+pub fn run(sender: SyncSender<ConnectionEventData>) {
     let session = &BluetoothSession::create_session(None).unwrap();
     let mut printed_adapter_missing = false;
 
@@ -58,18 +65,8 @@ pub fn run(sender: Sender<ConnectionEventData>) {
 
         let adapter = adapter.unwrap();
 
-        // check if a pair of buds is already connected!
-        if let Ok(devices) = adapter.get_device_list() {
-            for device in devices {
-                let device = BluetoothDevice::new(&session, device);
-                let is_connected = device.is_connected();
-                if is_connected.is_err() || !is_connected.unwrap() {
-                    continue;
-                }
-
-                check_device(&sender, &session, device.get_id());
-            }
-        }
+        // Buds connected before the daemon started don't send a connect event
+        adopt_connected(&sender, &session, &adapter);
 
         // Handle all future connection events
         loop {
@@ -99,8 +96,56 @@ pub fn run(sender: Sender<ConnectionEventData>) {
     }
 }
 
-// We need this behaivor twice
-fn check_device(sender: &Sender<ConnectionEventData>, session: &BluetoothSession, device: String) {
+// Attach to already connected buds instead of waiting for the next connect event
+fn adopt_connected(
+    sender: &SyncSender<ConnectionEventData>,
+    session: &BluetoothSession,
+    adapter: &BluetoothAdapter,
+) {
+    let devices = match adapter.get_device_list() {
+        Ok(devices) => devices,
+        Err(_) => return,
+    };
+
+    for device in devices {
+        let device = BluetoothDevice::new(session, device);
+        if !device.is_connected().unwrap_or(false) || !supported_device(&device) {
+            continue;
+        }
+
+        // Only adopt devices recognized as buds or Samsung audio devices
+        let name = device.get_name().unwrap_or_default();
+        let (model, battery_only) = match models::find_by_name(&name) {
+            Some(model) => (model, false),
+            None if models::is_samsung_name(&name) => (models::UNKNOWN_MODEL_TEMPLATE, true),
+            None => {
+                debug!("Not adopting {name}");
+                continue;
+            }
+        };
+
+        let address = match device.get_address() {
+            Ok(address) => address,
+            Err(_) => continue,
+        };
+
+        info!("Adopting already connected {} ({})", name, address);
+        sender
+            .send(ConnectionEventData {
+                address,
+                model,
+                adopted: true,
+                battery_only,
+            })
+            .unwrap();
+    }
+}
+
+fn check_device(
+    sender: &SyncSender<ConnectionEventData>,
+    session: &BluetoothSession,
+    device: String,
+) {
     let device = BluetoothDevice::new(session, device);
 
     if !supported_device(&device) {
@@ -109,10 +154,23 @@ fn check_device(sender: &Sender<ConnectionEventData>, session: &BluetoothSession
         return;
     }
 
+    // Attach unknown Samsung devices in a battery-only mode
+    let name = device.get_name().unwrap();
+    let (model, battery_only) = match models::find_by_name(&name) {
+        Some(model) => (model, false),
+        None if models::is_samsung_name(&name) => {
+            info!("Unknown model {}, only reading its battery", name);
+            (models::UNKNOWN_MODEL_TEMPLATE, true)
+        }
+        None => (models::from_name(&name), false),
+    };
+
     sender
         .send(ConnectionEventData {
             address: device.get_address().unwrap(),
-            model: name_to_model(device.get_name().unwrap().as_str()),
+            model,
+            adopted: false,
+            battery_only,
         })
         .unwrap();
 }
@@ -121,26 +179,52 @@ fn check_device(sender: &Sender<ConnectionEventData>, session: &BluetoothSession
 pub fn supported_device(device: &BluetoothDevice) -> bool {
     device
         .get_uuids()
-        .unwrap()
-        .iter()
-        .any(|s| s.to_lowercase() == "00001101-0000-1000-8000-00805f9b34fb")
+        .map(|uuids| {
+            uuids
+                .iter()
+                .any(|s| s.to_lowercase() == "00001101-0000-1000-8000-00805f9b34fb")
+        })
+        .unwrap_or(false)
+}
+
+/// Returns the object path of the default adapter, eg. /org/bluez/hci0
+pub fn get_adapter_path() -> Result<String, String> {
+    let session = BluetoothSession::create_session(None).map_err(|e| e.to_string())?;
+    let adapter = BluetoothAdapter::init(&session).map_err(|e| e.to_string())?;
+    Ok(adapter.get_id())
+}
+
+/// Returns the name of a device set in BlueZ
+pub fn get_alias(address: &str) -> Option<String> {
+    let session = BluetoothSession::create_session(None).ok()?;
+    let adapter = BluetoothAdapter::init(&session).ok()?;
+
+    adapter
+        .get_device_list()
+        .ok()?
+        .into_iter()
+        .map(|i| BluetoothDevice::new(&session, i))
+        .find(|i| i.get_address().ok().as_deref() == Some(address))?
+        .get_alias()
+        .ok()
 }
 
-/// Gives devices model from its name
-fn name_to_model(device_name: &str) -> Model {
-    let device_name = device_name.to_lowercase();
-
-    if device_name.contains("buds live") {
-        Model::BudsLive
-    } else if device_name.contains("buds pro") {
-        Model::BudsPro
-    } else if device_name.contains("buds 2 pro") {
-        Model::BudsPro2
-    } else if device_name.contains("buds+") {
-        Model::BudsPlus
-    } else if device_name.contains("buds2") {
-        Model::Buds2
-    } else {
-        Model::Buds
+/// Mark a device as trusted in BlueZ. Returns false if it was already trusted
+pub fn trust(address: &str) -> Result<bool, String> {
+    let session = BluetoothSession::create_session(None).map_err(|e| e.to_string())?;
+    let adapter = BluetoothAdapter::init(&session).map_err(|e| e.to_string())?;
+
+    let device = adapter
+        .get_device_list()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|i| BluetoothDevice::new(&session, i))
+        .find(|i| i.get_address().ok().as_deref() == Some(address))
+        .ok_or_else(|| "device not found".to_owned())?;
+
+    if device.is_trusted().map_err(|e| e.to_string())? {
+        return Ok(false);
     }
+    device.set_trusted(true).map_err(|e| e.to_string())?;
+    Ok(true)
 }
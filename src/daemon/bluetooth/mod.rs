@@ -1,3 +1,5 @@
-mod bean_connection;
+#[cfg(feature = "dbus")]
+pub mod agent;
+pub mod bean_connection;
 pub mod bt_connection_listener;
 pub mod rfcomm_connector;
@@ -1,31 +1,59 @@
 use super::super::buds_config::{BudsConfig, Config};
-use super::super::buds_info::BudsInfo;
+use super::super::buds_info::{BudsInfo, ConnectionState};
+use super::super::connection_history::{self, DisconnectReason};
+use super::super::lost;
+use super::super::runtime_state;
+use super::super::seat_lock::SeatLock;
+use super::super::status_diff;
 use super::bean_connection;
-use super::bt_connection_listener::BudsConnection;
+use super::bt_connection_listener::{self, BudsConnection};
 
 use async_std::sync::Arc;
 use async_std::sync::Mutex;
 use bluetooth_serial_port_async::{BtAddr, BtProtocol, BtSocket};
 use galaxy_buds_rs::model::Model;
-use log::info;
+use log::{info, warn};
+use serde_json::json;
+
+use async_std::channel::{self, Receiver as EventReceiver, Sender, TrySendError};
+use async_std::os::unix::net::UnixStream;
 
 use std::collections::HashMap;
+use std::os::unix::io::AsRawFd;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+// Events a subscriber may lag behind. The oldest ones get dropped beyond that
+const SUBSCRIBER_CAPACITY: usize = 64;
+
+/// Address used for buds simulated by budsim
+pub const SIMULATOR_ADDRESS: &str = "00:00:00:00:00:00";
+
+// Connecting to buds adopted right after the daemon started might fail
+// until BlueZ has set up their profiles
+const ADOPT_ATTEMPTS: u32 = 5;
+const ADOPT_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Environment variable pointing to a budsim socket
+pub const SIMULATOR_ENV: &str = "EARBUDS_SIMULATOR";
 
 /// The connection handler keeps track of
 /// all connected devices and its status
 pub struct ConnHandler {
     connected_devices: Vec<String>,
     pub connection_data: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
 }
 
 impl ConnHandler {
     /// Create a new Connection handler
-    pub fn new(cd: Arc<Mutex<ConnectionData>>) -> Self {
+    pub fn new(cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) -> Self {
         ConnHandler {
             connected_devices: Vec::new(),
             connection_data: cd,
+            config,
         }
     }
 
@@ -39,9 +67,51 @@ impl ConnHandler {
         self.connected_devices.push(dev);
     }
 
-    /// Remove a device from the ConnHandler
-    pub async fn remove_device(&mut self, dev: &str) {
-        self.connection_data.lock().await.data.remove(dev);
+    /// Mark a device as connected before its connection is established, so no
+    /// second connection gets opened meanwhile. Returns false if it already is
+    pub fn reserve_device(&mut self, dev: &str) -> bool {
+        if self.has_device(dev) {
+            return false;
+        }
+
+        self.add_device(dev.to_owned());
+        true
+    }
+
+    /// Release a device reserved by `reserve_device` whose connection failed
+    pub fn release_device(&mut self, dev: &str) {
+        if let Some(pos) = self.get_item_pos(dev) {
+            self.connected_devices.remove(pos);
+        }
+    }
+
+    /// Remove a device from the ConnHandler and publish a `disconnected` event
+    pub async fn remove_device(&mut self, dev: &str, reason: DisconnectReason) {
+        let removed = {
+            let mut lock = self.connection_data.lock().await;
+            lock.data.remove(dev).map(|info| {
+                // A lost connection is expected after a disconnect request
+                let reason = if info.disconnect_requested {
+                    DisconnectReason::Requested
+                } else {
+                    reason
+                };
+                let event =
+                    status_diff::named_event(dev, "disconnected", json!({ "reason": reason }));
+                lock.publish(dev, &event);
+                (info, reason)
+            })
+        };
+
+        if let Some((info, reason)) = removed {
+            connection_history::record(&info, reason).await;
+            if reason == DisconnectReason::ConnectionLost {
+                lost::record(&info).await;
+            }
+            runtime_state::remember(&info);
+
+            bean_connection::session_end::handle(info, &self.connection_data, &self.config).await;
+        }
 
         let pos = self.get_item_pos(dev);
         if pos.is_none() {
@@ -65,13 +135,140 @@ impl ConnHandler {
 /// Shared data for informations about connected buds
 pub struct ConnectionData {
     pub data: HashMap<String, BudsInfo>,
+    pub subscribers: Vec<Subscriber>,
+    // Connection attempts by device address
+    pub connection_states: HashMap<String, ConnectionState>,
+    // Established connections by device address
+    pub connects: HashMap<String, u64>,
+}
+
+/// A client listening for status changes
+pub struct Subscriber {
+    // Only receive events of this device if set
+    pub device: Option<String>,
+    sender: Sender<String>,
+    // Used to drop the oldest event if the subscriber lags behind
+    dropper: EventReceiver<String>,
+    dropped: AtomicU64,
+    // Passive subscribers don't keep the daemon polling the buds frequently
+    pub passive: bool,
+}
+
+impl Subscriber {
+    /// Create a subscriber and the receiver of its events
+    pub fn new(device: Option<String>, passive: bool) -> (Self, EventReceiver<String>) {
+        let (sender, receiver) = channel::bounded(SUBSCRIBER_CAPACITY);
+        let subscriber = Self {
+            device,
+            sender,
+            dropper: receiver.clone(),
+            dropped: AtomicU64::new(0),
+            passive,
+        };
+        (subscriber, receiver)
+    }
+
+    /// Queue an event. Returns false if the subscriber is gone
+    pub fn send(&self, event: &str) -> bool {
+        if !self.is_alive() {
+            return false;
+        }
+
+        match self.sender.try_send(event.to_owned()) {
+            Ok(()) => true,
+            Err(TrySendError::Full(event)) => {
+                self.dropper.try_recv().ok();
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+                self.sender.try_send(event).is_ok()
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+
+    /// Returns the number of events waiting to be received
+    pub fn queued(&self) -> usize {
+        self.sender.len()
+    }
+
+    /// Returns the number of events dropped because the subscriber lagged behind
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    // The own receiver doesn't count
+    fn is_alive(&self) -> bool {
+        self.sender.receiver_count() > 1
+    }
 }
 
 impl ConnectionData {
     pub fn new() -> Self {
         ConnectionData {
             data: HashMap::new(),
+            subscribers: Vec::new(),
+            connection_states: HashMap::new(),
+            connects: HashMap::new(),
+        }
+    }
+
+    /// Add the data of a device on its first message and publish a `connected` event.
+    /// Returns the data of the device
+    pub fn add_device<F>(&mut self, device: &str, create: F) -> &mut BudsInfo
+    where
+        F: FnOnce() -> BudsInfo,
+    {
+        if !self.data.contains_key(device) {
+            *self.connects.entry(device.to_owned()).or_insert(0) += 1;
+            self.data.insert(device.to_owned(), create());
+
+            let event = status_diff::named_event(device, "connected", json!({}));
+            self.publish(device, &event);
         }
+
+        self.data.get_mut(device).unwrap()
+    }
+
+    /// Update the connection state of a device and notify its subscribers
+    pub fn set_connection_state(&mut self, device: &str, state: ConnectionState) {
+        let event = status_diff::named_event(device, "connection_state", json!(state));
+        self.connection_states.insert(device.to_owned(), state);
+        self.publish(device, &event);
+    }
+
+    /// Returns the connection state of a device
+    pub fn get_connection_state(&self, device: &str) -> ConnectionState {
+        self.connection_states
+            .get(device)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Returns a device the daemon currently tries to connect to. If no
+    /// address is set, any device gets returned
+    pub fn get_pending_connection(&self, addr: &str) -> Option<(String, ConnectionState)> {
+        self.connection_states
+            .iter()
+            .find(|(address, state)| (addr.is_empty() || *address == addr) && state.is_pending())
+            .map(|(address, state)| (address.clone(), state.clone()))
+    }
+
+    /// Send an event to all subscribers of a device. Closed
+    /// subscriptions get removed
+    pub fn publish(&mut self, device: &str, event: &str) {
+        self.subscribers.retain(|i| {
+            if i.device.as_ref().map(|d| d != device).unwrap_or(false) {
+                return true;
+            }
+
+            i.send(event)
+        });
+    }
+
+    /// Returns true if a client which isn't passive listens for events of a device
+    pub fn has_active_subscribers(&self, device: &str) -> bool {
+        self.subscribers
+            .iter()
+            .any(|i| !i.passive && i.is_alive() && i.device.as_ref().map_or(true, |d| d == device))
     }
 
     /// Returns a device by its address. If no address is set,
@@ -146,32 +343,51 @@ pub async fn run(
     cd: Arc<Mutex<ConnectionData>>,
     config: Arc<Mutex<Config>>,
 ) {
-    let connection_handler = ConnHandler::new(cd);
+    let connection_handler = ConnHandler::new(Arc::clone(&cd), Arc::clone(&config));
     let arc_ch = Arc::new(Mutex::new(connection_handler));
 
-    for i in rec {
-        let connection = {
-            let mut connection_handler = arc_ch.lock().await;
+    // Attach simulated buds if desired
+    if let Ok(path) = std::env::var(SIMULATOR_ENV) {
+        attach_simulator(path, &config, &arc_ch).await;
+    }
 
-            // Ignore already connected devices
-            if connection_handler.has_device(i.address.as_str()) {
+    for i in rec {
+        // Skip devices the user doesn't want us to manage
+        {
+            let mut cfg = config.lock().await;
+            if let Err(err) = cfg.load().await {
+                warn!("Can't reload config: {}", err);
+            }
+            if !cfg.is_device_allowed(&i.address) {
+                info!("Ignoring device {}", i.address);
                 continue;
             }
+        }
 
-            // Connect to the RFCOMM interface of the buds
-            let connection = connect_rfcomm(i.address.clone());
-            if let Err(err) = connection {
+        // Ignore already connected devices. Reserve the device while
+        // connecting, so a restarting connection doesn't open a second one
+        if !arc_ch.lock().await.reserve_device(i.address.as_str()) {
+            continue;
+        }
+
+        // Connect to the RFCOMM interface of the buds
+        let connection = match connect(&i, &cd).await {
+            Ok(connection) => connection,
+            Err(err) => {
                 eprintln!("Error connecting to rfcomm: {:?}", err);
+                arc_ch.lock().await.release_device(i.address.as_str());
                 continue;
             }
+        };
 
-            // Add device to the connection handler
-            connection_handler.add_device(i.address.to_owned());
-
+        if i.battery_only {
+            info!(
+                "Connected to {} of an unknown model, only reading the battery",
+                i.address
+            );
+        } else {
             info!("Connected successfully to {}", i.model);
-
-            connection
-        };
+        }
 
         // Set default config for (apparently) new device
         {
@@ -183,27 +399,134 @@ pub async fn run(
             }
         }
 
+        // Let the buds reconnect without confirmation next time
+        if config.lock().await.auto_trust() {
+            let addr = i.address.clone();
+            match async_std::task::spawn_blocking(move || bt_connection_listener::trust(&addr))
+                .await
+            {
+                Ok(true) => info!("Marked {} as trusted", i.address),
+                Ok(false) => (),
+                Err(err) => warn!("Can't trust {}: {}", i.address, err),
+            }
+        }
+
         // Create a new buds connection task
-        async_std::task::spawn(bean_connection::listener::start_listen(
-            connection.unwrap(),
+        async_std::task::spawn(bean_connection::supervisor::supervise(
+            connection,
             Arc::clone(&config),
             Arc::clone(&arc_ch),
             i.model,
+            i.battery_only,
         ));
     }
 }
 
+// Connect to a budsim socket and listen to it like to real buds
+async fn attach_simulator(path: String, config: &Arc<Mutex<Config>>, ch: &Arc<Mutex<ConnHandler>>) {
+    let connection = match connect_simulator(&path).await {
+        Ok(v) => v,
+        Err(err) => {
+            eprintln!("Error connecting to simulator {}: {}", path, err);
+            return;
+        }
+    };
+
+    ch.lock().await.add_device(SIMULATOR_ADDRESS.to_owned());
+    info!("Connected successfully to simulator {}", path);
+
+    async_std::task::spawn(bean_connection::supervisor::supervise(
+        connection,
+        Arc::clone(config),
+        Arc::clone(ch),
+        Model::BudsLive,
+        false,
+    ));
+}
+
+// Connect to the buds of an event, retrying for adopted ones
+async fn connect(
+    event: &ConnectionEventData,
+    cd: &Arc<Mutex<ConnectionData>>,
+) -> Result<BudsConnection, String> {
+    let address = event.address.as_str();
+    let mut last_error = cd.lock().await.get_connection_state(address).last_error;
+
+    let mut attempt = 1;
+    loop {
+        let state = ConnectionState {
+            connecting: true,
+            backoff_until: None,
+            last_error: last_error.clone(),
+        };
+        cd.lock().await.set_connection_state(address, state);
+
+        let err = match connect_rfcomm(address) {
+            Ok(connection) => {
+                let state = ConnectionState {
+                    last_error,
+                    ..ConnectionState::default()
+                };
+                cd.lock().await.set_connection_state(address, state);
+                return Ok(connection);
+            }
+            Err(err) => err,
+        };
+
+        if !event.adopted || attempt >= ADOPT_ATTEMPTS {
+            let state = ConnectionState {
+                last_error: Some(err.clone()),
+                ..ConnectionState::default()
+            };
+            cd.lock().await.set_connection_state(address, state);
+            return Err(err);
+        }
+
+        info!("Connecting to {} failed, retrying: {}", address, err);
+        let state = ConnectionState::backoff(ADOPT_RETRY_DELAY, err.clone());
+        cd.lock().await.set_connection_state(address, state);
+        last_error = Some(err);
+
+        attempt += 1;
+        async_std::task::sleep(ADOPT_RETRY_DELAY).await;
+    }
+}
+
 /// Connect to buds live via rfcomm proto
 pub fn connect_rfcomm<S: AsRef<str>>(addr: S) -> Result<BudsConnection, String> {
+    // Make sure no daemon of another seat is connected to the buds
+    let seat_lock = SeatLock::acquire(addr.as_ref())?;
+
     let mut socket = BtSocket::new(BtProtocol::RFCOMM).map_err(|e| e.to_string())?;
     let address = BtAddr::from_str(addr.as_ref()).unwrap();
     socket.connect(address).map_err(|e| e.to_string())?;
     let fd = socket.get_fd();
+    let stream = socket.get_stream();
 
     Ok(BudsConnection {
         addr: addr.as_ref().to_owned(),
-        socket,
+        socket: Some(socket),
+        stream,
+        fd,
+        seat_lock: Some(seat_lock),
+        alias: bt_connection_listener::get_alias(addr.as_ref()),
+    })
+}
+
+/// Connect to a running budsim instance instead of real buds
+pub async fn connect_simulator<S: AsRef<str>>(path: S) -> Result<BudsConnection, String> {
+    let stream = UnixStream::connect(path.as_ref())
+        .await
+        .map_err(|e| e.to_string())?;
+    let fd = stream.as_raw_fd();
+
+    Ok(BudsConnection {
+        addr: SIMULATOR_ADDRESS.to_owned(),
+        socket: None,
+        stream,
         fd,
+        seat_lock: None,
+        alias: Some("budsim".to_owned()),
     })
 }
 
@@ -211,4 +534,8 @@ pub fn connect_rfcomm<S: AsRef<str>>(addr: S) -> Result<BudsConnection, String>
 pub struct ConnectionEventData {
     pub address: String,
     pub model: Model,
+    // Connected before the daemon started
+    pub adopted: bool,
+    // Unknown Samsung device. Only its battery gets read
+    pub battery_only: bool,
 }
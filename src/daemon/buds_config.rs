@@ -1,13 +1,56 @@
 #![allow(dead_code)]
+use super::config_migration::{self, Migrated, CONFIG_VERSION};
+use super::media_keys::MediaKey;
+use super::parse;
+use super::settings;
+use super::touchpad_schedule;
+use super::utils;
+
+use galaxy_buds_rs::message::bud_property::TouchpadOption;
 use serde::{Deserialize, Serialize};
 
+use std::{collections::HashMap, time::Duration};
+
 use async_std::fs::{self, File};
 use async_std::io::prelude::*;
 use async_std::path::PathBuf;
+use log::info;
+
+// Applications treated as calls if no list is configured
+const DEFAULT_CALL_APPS: &[&str] = &["zoom", "teams", "skype", "discord", "slack", "webex"];
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 pub struct Config {
+    // Layout version, used to migrate old configs
+    pub version: Option<u32>,
+    // Only manage these devices if set
+    pub allowed_devices: Option<Vec<String>>,
+    // Never manage these devices
+    pub ignore_devices: Option<Vec<String>>,
+    // Applications which start a call when recording audio
+    pub call_apps: Option<Vec<String>>,
+    // Log all requests changing something to the history store
+    pub audit_requests: Option<bool>,
+    // Where to keep the history: 'jsonl' (default) or 'sqlite'
+    pub history_backend: Option<String>,
+    // Serve the HTTP endpoint (eg. for Home Assistant) on this address
+    pub http_address: Option<String>,
+    // Require a token of a paired client for HTTP requests
+    pub http_pairing: Option<bool>,
+    // Serve HTTPS with this PEM certificate chain and private key
+    pub http_tls_cert: Option<String>,
+    pub http_tls_key: Option<String>,
+    // Write a status snapshot to the runtime directory on every update
+    pub status_file: Option<bool>,
+    // Mark connecting buds as trusted in BlueZ so they can reconnect on their own
+    pub auto_trust: Option<bool>,
+    // Report the battery to BlueZ and thereby UPower
+    pub battery_provider: Option<bool>,
+    // Appearance of desktop notifications
+    pub notifications: Option<NotificationsConfig>,
     pub buds_settings: Vec<BudsConfig>,
+    // Commands to run on events
+    pub hooks: Option<Vec<HookConfig>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -17,9 +60,138 @@ pub struct BudsConfig {
     pub low_battery_notification: Option<bool>,
     pub auto_resume_music: Option<bool>,
     pub auto_pause_music: Option<bool>,
+    // Pause when "either" bud, "both" buds or the primary bud ("primary-only") got removed
+    pub auto_pause_trigger: Option<String>,
+    // The bud considered primary, "left" or "right"
+    pub primary_bud: Option<String>,
+    // Pause players through "mpris" (default) or mute the default sink with "pulseaudio"
+    pub media_control_backend: Option<String>,
+    // Mute the default sink for this many seconds when auto pausing
+    #[serde(default, deserialize_with = "parse::deserialize_seconds")]
+    pub mute_on_pause: Option<u64>,
+    // Only resume if the buds were removed for less than this many seconds
+    #[serde(default, deserialize_with = "parse::deserialize_seconds")]
+    pub resume_within: Option<u64>,
+    // Only resume if the paused player is still the active one
+    pub resume_same_player: Option<bool>,
+    // Never resume these players (identity or bus name, eg. "firefox")
+    pub resume_ignore_players: Option<Vec<String>>,
     pub smart_sink: Option<bool>,
     pub smart_touchpad: Option<bool>,
     pub hold_to_disconnect: Option<bool>,
+    pub touchpad_lock_in_case: Option<bool>,
+    pub connect_notification: Option<bool>,
+    // Remind to charge the case below this battery level
+    pub case_battery_reminder: Option<i8>,
+    pub session_summary_notification: Option<bool>,
+    // Notify how long the buds take to charge once it can be estimated
+    pub charging_notification: Option<bool>,
+    // Notify when the firmware changed since the last connection
+    pub firmware_change_notification: Option<bool>,
+    // Use the buds mic as default input once a headset profile is active
+    pub auto_mic: Option<bool>,
+    // Switch to the headset profile during calls
+    pub auto_profile: Option<bool>,
+    // "buds" accepts settings changed by other apps, "daemon" sets the own values again
+    pub source_of_truth: Option<String>,
+    // Touch and hold actions set by the user, restored after factory resets
+    pub touchpad_option_left: Option<String>,
+    pub touchpad_option_right: Option<String>,
+    // Media keys emitted through uinput
+    pub media_keys: Option<MediaKeysConfig>,
+    // Settings the buds get kept at, by settings key. Tables hold values per parameter
+    pub desired_state: Option<HashMap<String, toml::Value>>,
+    // Lock the touchpads while any of the rules matches
+    pub touchpad_lock_schedule: Option<Vec<LockRule>>,
+    // "pause", "ambient" or "both" when the session gets locked
+    pub lock_action: Option<String>,
+    // Chirp when buds reconnect after being lost while worn
+    pub chirp_when_lost: Option<bool>,
+    // Provide a socket which only controls this device, eg. for sandboxed apps
+    pub device_socket: Option<bool>,
+}
+
+/// Media keys (playpause, next or previous) to emit on touch and wear events
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct MediaKeysConfig {
+    pub put_on: Option<String>,
+    pub take_off: Option<String>,
+    pub hold_left: Option<String>,
+    pub hold_right: Option<String>,
+}
+
+/// Condition to lock the touchpads. All set fields have to match
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct LockRule {
+    // Local time range like "06:30-08:00", may wrap around midnight
+    pub hours: Option<String>,
+    // Weekdays like "mon" or "sat"
+    pub days: Option<Vec<String>>,
+    // Name of a process which has to run
+    pub app: Option<String>,
+}
+
+/// Command to run on an event
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct HookConfig {
+    // battery, wear, anc_changed, connect, disconnect, one of the shorthands connected,
+    // disconnected, placed_in_ear, removed_from_ear and low_battery or the name of an
+    // event (eg. case_battery_low)
+    pub event: String,
+    // Run through `sh -c`
+    pub command: String,
+    // Only run for this device
+    pub device: Option<String>,
+    // battery, low_battery: only run when the lower battery drops below this value
+    pub battery_below: Option<u8>,
+    // battery: only run if the battery changed this much since the last run
+    pub min_battery_delta: Option<u8>,
+    // wear: only run when the buds get put 'on' or taken 'off'
+    pub wear: Option<String>,
+    // Don't run again for the same device within this many seconds
+    #[serde(default, deserialize_with = "parse::deserialize_seconds")]
+    pub debounce: Option<u64>,
+}
+
+/// Wear transitions which pause music
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PauseTrigger {
+    Either,
+    Both,
+    PrimaryOnly,
+}
+
+/// How auto pause and resume control the media
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaControlBackend {
+    Mpris,
+    Pulseaudio,
+}
+
+/// Notification settings for each category
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct NotificationsConfig {
+    pub low_battery: Option<NotificationStyle>,
+    pub critical_battery: Option<NotificationStyle>,
+    pub connect: Option<NotificationStyle>,
+    pub case_battery: Option<NotificationStyle>,
+    pub session_summary: Option<NotificationStyle>,
+    pub firmware_changed: Option<NotificationStyle>,
+    pub charging: Option<NotificationStyle>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default, Clone)]
+pub struct NotificationStyle {
+    // One of "low", "normal" or "critical"
+    pub urgency: Option<String>,
+    // Timeout in milliseconds. 0 means the notification never expires
+    pub timeout: Option<u32>,
+    pub app_name: Option<String>,
+    pub icon: Option<String>,
+    // Templates replacing the default texts, eg. to translate them. Placeholders
+    // like {left} get replaced with the values of the notification
+    pub title: Option<String>,
+    pub body: Option<String>,
 }
 
 impl Config {
@@ -37,15 +209,49 @@ impl Config {
                 .unwrap_or(1)
                 == 0
         {
-            config = Self::default();
+            config = Self {
+                version: Some(CONFIG_VERSION),
+                ..Self::default()
+            };
             config.save().await?;
         } else {
             let conf_data = fs::read_to_string(&config_file)
                 .await
                 .map_err(|e| e.to_string())?;
 
-            config = toml::from_str(&conf_data).map_err(|e| e.to_string())?;
+            config = Self::parse(&conf_data).await?;
+        }
+
+        Ok(config)
+    }
+
+    // Parse a config, migrating and saving it if it has an old layout. The
+    // original gets kept as config.toml.bak
+    async fn parse(conf_data: &str) -> Result<Self, String> {
+        let mut value: toml::Value = toml::from_str(conf_data).map_err(|e| e.to_string())?;
+        let migrated = config_migration::migrate(&mut value)?;
+
+        let config: Self = value.try_into().map_err(|e| e.to_string())?;
+        if migrated == Migrated::UpToDate {
+            return Ok(config);
+        }
+
+        // Never write back a config which is invalid anyway
+        config.check_config()?;
+
+        let config_file = Self::get_config_file().await?;
+        fs::write(config_file.with_extension("toml.bak"), conf_data)
+            .await
+            .map_err(|e| format!("Can't back up the config: {}", e))?;
+
+        if migrated == Migrated::Layout {
+            config.save().await?;
+        } else {
+            fs::write(&config_file, config_migration::set_version(conf_data))
+                .await
+                .map_err(|e| e.to_string())?;
         }
+        info!("Migrated config to version {}", CONFIG_VERSION);
 
         Ok(config)
     }
@@ -72,7 +278,7 @@ impl Config {
         let conf_data = fs::read_to_string(&config_file)
             .await
             .map_err(|e| e.to_string())?;
-        *self = toml::from_str(&conf_data).map_err(|e| e.to_string())?;
+        *self = Self::parse(&conf_data).await?;
 
         self.check_config()
     }
@@ -84,6 +290,18 @@ impl Config {
             return Err("More than one device is set to default".to_string());
         }
 
+        // Check case battery reminder thresholds
+        if let Some(device) = self
+            .buds_settings
+            .iter()
+            .find(|i| !(1..=100).contains(&i.case_battery_reminder.unwrap_or(1)))
+        {
+            return Err(format!(
+                "Invalid case battery reminder for {}. Has to be between 1 and 100",
+                device.address
+            ));
+        }
+
         // Check if a device is configured more than once
         for device in self.buds_settings.iter() {
             if self
@@ -100,9 +318,218 @@ impl Config {
             }
         }
 
+        // Check the source of truth policies
+        for device in self.buds_settings.iter() {
+            if let Some(ref policy) = device.source_of_truth {
+                if policy != "buds" && policy != "daemon" {
+                    return Err(format!(
+                        "Invalid source_of_truth '{}' for {}. Use 'buds' or 'daemon'",
+                        policy, device.address
+                    ));
+                }
+            }
+
+            let options = device.touchpad_option_left.iter();
+            for option in options.chain(device.touchpad_option_right.iter()) {
+                if utils::str_to_touchpad_option(option) == TouchpadOption::Undetected {
+                    return Err(format!(
+                        "Invalid touchpad option '{}' for {}",
+                        option, device.address
+                    ));
+                }
+            }
+        }
+
+        // Check auto pause triggers
+        for device in self.buds_settings.iter() {
+            if let Some(ref trigger) = device.auto_pause_trigger {
+                if !["either", "both", "primary-only"].contains(&trigger.as_str()) {
+                    return Err(format!(
+                        "Invalid auto_pause_trigger '{}' for {}. Use 'either', 'both' or 'primary-only'",
+                        trigger, device.address
+                    ));
+                }
+            }
+
+            if let Some(ref backend) = device.media_control_backend {
+                if backend != "mpris" && backend != "pulseaudio" {
+                    return Err(format!(
+                        "Invalid media_control_backend '{}' for {}. Use 'mpris' or 'pulseaudio'",
+                        backend, device.address
+                    ));
+                }
+            }
+
+            if let Some(ref side) = device.primary_bud {
+                if side != "left" && side != "right" {
+                    return Err(format!(
+                        "Invalid primary_bud '{}' for {}. Use 'left' or 'right'",
+                        side, device.address
+                    ));
+                }
+            }
+        }
+
+        // Check desired states
+        for device in self.buds_settings.iter() {
+            for (key, param, value) in device.desired_state() {
+                settings::parse(&key, param.as_deref(), &value).map_err(|err| {
+                    format!("Invalid desired_state for {}: {}", device.address, err)
+                })?;
+            }
+        }
+
+        // Check lock actions
+        for device in self.buds_settings.iter() {
+            if let Some(ref action) = device.lock_action {
+                if !["pause", "ambient", "both"].contains(&action.as_str()) {
+                    return Err(format!(
+                        "Invalid lock_action '{}' for {}. Use 'pause', 'ambient' or 'both'",
+                        action, device.address
+                    ));
+                }
+            }
+        }
+
+        // Check touchpad lock schedules
+        for device in self.buds_settings.iter() {
+            for rule in device.touchpad_lock_schedule.iter().flatten() {
+                touchpad_schedule::check_rule(rule).map_err(|err| {
+                    format!(
+                        "Invalid touchpad_lock_schedule for {}: {}",
+                        device.address, err
+                    )
+                })?;
+            }
+        }
+
+        // Check the history backend
+        match self.history_backend.as_deref() {
+            None | Some("jsonl") => (),
+            Some("sqlite") if cfg!(feature = "sqlite") => (),
+            Some("sqlite") => {
+                return Err("earbuds was built without the sqlite feature".to_string())
+            }
+            Some(backend) => {
+                return Err(format!(
+                    "Invalid history_backend '{}'. Use 'jsonl' or 'sqlite'",
+                    backend
+                ))
+            }
+        }
+
+        // Check TLS files
+        if self.http_tls_cert.is_some() != self.http_tls_key.is_some() {
+            return Err("Set both http_tls_cert and http_tls_key to serve HTTPS".to_string());
+        }
+
+        // Check hooks
+        for hook in self.hooks.iter().flatten() {
+            if hook.event.is_empty() || hook.command.is_empty() {
+                return Err("Hooks need an event and a command".to_string());
+            }
+
+            if let Some(ref wear) = hook.wear {
+                if wear != "on" && wear != "off" {
+                    return Err(format!("Invalid wear filter '{}'. Use 'on' or 'off'", wear));
+                }
+            }
+
+            if hook.battery_below.map_or(false, |i| i > 100) {
+                return Err("battery_below has to be between 0 and 100".to_string());
+            }
+        }
+
+        // Check media keys
+        for device in self.buds_settings.iter() {
+            if let Some(ref keys) = device.media_keys {
+                let all = [
+                    &keys.put_on,
+                    &keys.take_off,
+                    &keys.hold_left,
+                    &keys.hold_right,
+                ];
+                for key in all.iter().copied().flatten() {
+                    if MediaKey::parse(key).is_none() {
+                        return Err(format!(
+                            "Invalid media key '{}' for {}. Use playpause, next or previous",
+                            key, device.address
+                        ));
+                    }
+                }
+            }
+        }
+
+        // Check notification urgency values
+        if let Some(ref notifications) = self.notifications {
+            let styles = [
+                &notifications.low_battery,
+                &notifications.critical_battery,
+                &notifications.connect,
+                &notifications.case_battery,
+                &notifications.session_summary,
+                &notifications.firmware_changed,
+                &notifications.charging,
+            ];
+
+            for style in styles.iter().copied().flatten() {
+                if let Some(ref urgency) = style.urgency {
+                    if !["low", "normal", "critical"].contains(&urgency.as_str()) {
+                        return Err(format!("Invalid notification urgency: {}", urgency));
+                    }
+                }
+            }
+        }
+
         Ok(())
     }
 
+    /// Returns true if the daemon is allowed to manage the given device
+    pub fn is_device_allowed(&self, address: &str) -> bool {
+        let contains = |list: &Option<Vec<String>>| {
+            list.as_ref()
+                .map(|l| l.iter().any(|i| i.eq_ignore_ascii_case(address)))
+        };
+
+        if contains(&self.ignore_devices).unwrap_or(false) {
+            return false;
+        }
+
+        contains(&self.allowed_devices).unwrap_or(true)
+    }
+
+    /// Applications which start a call when recording audio
+    pub fn call_apps(&self) -> Vec<String> {
+        self.call_apps
+            .clone()
+            .unwrap_or_else(|| DEFAULT_CALL_APPS.iter().map(|i| i.to_string()).collect())
+    }
+
+    /// Returns true if HTTP clients have to be paired
+    pub fn http_pairing(&self) -> bool {
+        self.http_pairing.unwrap_or(true)
+    }
+
+    /// Returns true if changing requests should be logged
+    pub fn audit_requests(&self) -> bool {
+        self.audit_requests.unwrap_or(false)
+    }
+
+    /// Returns true if the status snapshot file should be kept up to date
+    pub fn status_file(&self) -> bool {
+        self.status_file.unwrap_or(false)
+    }
+
+    /// Returns true if connecting buds should be trusted in BlueZ
+    pub fn auto_trust(&self) -> bool {
+        self.auto_trust.unwrap_or(false)
+    }
+
+    /// Returns true if the battery should be reported to UPower
+    pub fn battery_provider(&self) -> bool {
+        self.battery_provider.unwrap_or(true)
+    }
+
     /// Get configuration for a given device
     pub fn get_device_config(&self, address: &str) -> Option<&BudsConfig> {
         for i in &self.buds_settings {
@@ -197,18 +624,134 @@ impl BudsConfig {
         self.auto_pause_music.unwrap_or(false)
     }
 
+    /// Which removals trigger an auto pause
+    pub fn auto_pause_trigger(&self) -> PauseTrigger {
+        match self.auto_pause_trigger.as_deref() {
+            Some("either") => PauseTrigger::Either,
+            Some("primary-only") => PauseTrigger::PrimaryOnly,
+            _ => PauseTrigger::Both,
+        }
+    }
+
+    /// How to pause and resume media
+    pub fn media_control_backend(&self) -> MediaControlBackend {
+        match self.media_control_backend.as_deref() {
+            Some("pulseaudio") => MediaControlBackend::Pulseaudio,
+            _ => MediaControlBackend::Mpris,
+        }
+    }
+
+    /// Returns true if the left bud is the primary one
+    pub fn primary_is_left(&self) -> bool {
+        self.primary_bud.as_deref() == Some("left")
+    }
+
+    /// How long to mute the default sink after auto pausing, if enabled
+    pub fn mute_on_pause(&self) -> Option<Duration> {
+        self.mute_on_pause.map(Duration::from_secs)
+    }
+
     pub fn auto_play(&self) -> bool {
         self.auto_resume_music.unwrap_or(false)
     }
 
+    pub fn resume_within(&self) -> Option<Duration> {
+        self.resume_within.map(Duration::from_secs)
+    }
+
+    pub fn resume_same_player(&self) -> bool {
+        self.resume_same_player.unwrap_or(false)
+    }
+
+    pub fn resume_ignore_players(&self) -> &[String] {
+        self.resume_ignore_players.as_deref().unwrap_or_default()
+    }
+
     pub fn low_battery_notification(&self) -> bool {
         self.low_battery_notification.unwrap_or(false)
     }
 
+    pub fn connect_notification(&self) -> bool {
+        self.connect_notification.unwrap_or(false)
+    }
+
+    /// Returns the case battery level to remind at, if enabled
+    pub fn case_battery_reminder(&self) -> Option<i8> {
+        self.case_battery_reminder
+    }
+
+    pub fn session_summary_notification(&self) -> bool {
+        self.session_summary_notification.unwrap_or(false)
+    }
+
+    pub fn charging_notification(&self) -> bool {
+        self.charging_notification.unwrap_or(false)
+    }
+
+    pub fn firmware_change_notification(&self) -> bool {
+        self.firmware_change_notification.unwrap_or(false)
+    }
+
+    pub fn auto_mic(&self) -> bool {
+        self.auto_mic.unwrap_or(false)
+    }
+
+    pub fn auto_profile(&self) -> bool {
+        self.auto_profile.unwrap_or(false)
+    }
+
+    /// Returns the desired (key, parameter, value) of each setting
+    pub fn desired_state(&self) -> Vec<(String, Option<String>, String)> {
+        let to_string = |value: &toml::Value| match value {
+            toml::Value::String(s) => s.clone(),
+            value => value.to_string(),
+        };
+
+        let mut state = Vec::new();
+        for (key, value) in self.desired_state.iter().flatten() {
+            match value {
+                toml::Value::Table(params) => {
+                    for (param, value) in params {
+                        state.push((key.clone(), Some(param.clone()), to_string(value)));
+                    }
+                }
+                value => state.push((key.clone(), None, to_string(value))),
+            }
+        }
+        state
+    }
+
+    /// Returns true if settings changed by other apps should be overwritten
+    pub fn reassert_settings(&self) -> bool {
+        self.source_of_truth.as_deref() == Some("daemon")
+    }
+
     pub fn smart_touchpad(&self) -> bool {
         self.smart_touchpad.unwrap_or(false)
     }
 
+    pub fn chirp_when_lost(&self) -> bool {
+        self.chirp_when_lost.unwrap_or(false)
+    }
+
+    pub fn device_socket(&self) -> bool {
+        self.device_socket.unwrap_or(false)
+    }
+
+    /// Returns whether to pause playback and enable ambient sound on screen lock
+    pub fn lock_action(&self) -> (bool, bool) {
+        match self.lock_action.as_deref() {
+            Some("pause") => (true, false),
+            Some("ambient") => (false, true),
+            Some("both") => (true, true),
+            _ => (false, false),
+        }
+    }
+
+    pub fn touchpad_lock_in_case(&self) -> bool {
+        self.touchpad_lock_in_case.unwrap_or(false)
+    }
+
     pub fn smart_sink(&self) -> bool {
         self.smart_sink.unwrap_or(false)
     }
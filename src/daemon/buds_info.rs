@@ -1,5 +1,8 @@
-use std::time::SystemTime;
+use std::collections::{HashMap, VecDeque};
+use std::io::ErrorKind;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
+use async_std::channel::{self, Receiver, Sender};
 use async_std::io::prelude::*;
 use async_std::os::unix::net::UnixStream;
 use galaxy_buds_rs::{
@@ -15,6 +18,23 @@ use galaxy_buds_rs::{
 };
 use serde::{Deserialize, Serialize};
 
+use super::charging::{ChargeTracker, Charging};
+use super::firmware::FirmwareVersion;
+use super::labels::Labels;
+use super::media_control::PausedPlayer;
+use super::models;
+use super::notifications::Notifier;
+use super::raw_message::{self, RawMessage};
+use super::session::Session;
+use super::settings::Value;
+use super::stats::Stats;
+use super::unix_socket::fit_test::FitTestResult;
+
+const MAX_WRITE_RETRIES: u32 = 3;
+const WRITE_RETRY_DELAY: Duration = Duration::from_millis(50);
+// Debug requests without an answer after this long count as lost
+pub const DEBUG_ANSWER_TIMEOUT: Duration = Duration::from_secs(2);
+
 /// Informations about a connected pair
 /// of Galaxy Buds live
 pub struct BudsInfo {
@@ -24,6 +44,39 @@ pub struct BudsInfo {
     pub left_tp_hold_count: u8,
     pub right_tp_hold_count: u8,
     pub last_tp_update: SystemTime,
+    pub touchpad_locked_by_case: bool,
+    pub did_critical_battery_notify: bool,
+    pub did_case_battery_notify: bool,
+    // True while the buds provide an input source
+    pub mic_source_active: bool,
+    // Events which get published to subscribers after handling a message
+    pub events: Vec<String>,
+    // Messages get recorded here instead of being sent while in dry run mode
+    pub dry_run: Option<Vec<String>>,
+    // Send times of unanswered debug requests, oldest first
+    pub pending_debug: VecDeque<Instant>,
+    // Send time of the debug request measuring the round trip time
+    pub rtt_probe: Option<(Instant, Sender<Duration>)>,
+    pub notifier: Notifier,
+    pub session: Session,
+    // Values of settings the daemon has set, by key and parameter of `Setting::params`
+    pub desired_settings: HashMap<(&'static str, Option<&'static str>), Value>,
+    pub stats: Stats,
+    // Answer to a running fit test
+    pub fit_test: Option<FitTestResult>,
+    // Receive time of the last extended status update
+    pub last_extended_status: Option<Instant>,
+    // Requests waiting for the next extended status update
+    pub extended_status_waiters: Vec<Sender<()>>,
+    // Set if a client asked to disconnect the device
+    pub disconnect_requested: bool,
+    // Last event published to subscribers
+    pub last_event: Option<String>,
+    pub charge: ChargeTracker,
+    // Player paused on removal and when it happened
+    pub paused_player: Option<(PausedPlayer, Instant)>,
+    // Set if the default sink got muted on removal
+    pub muted_sink: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -51,6 +104,9 @@ pub struct BudsInfoInner {
     pub equalizer_type: EqualizerType,
     pub touchpads_blocked: bool,
     pub noise_reduction: bool,
+    // ANC intensity, low (0) or high (1). Unknown until the daemon has set it
+    #[serde(default)]
+    pub noise_reduction_level: Option<u8>,
     pub did_battery_notify: bool,
     #[serde(with = "touchpad_option_dser")]
     pub touchpad_option_left: TouchpadOption,
@@ -60,14 +116,95 @@ pub struct BudsInfoInner {
     pub debug: DebugInfo,
     #[serde(with = "DefModel")]
     pub model: Model,
+    pub model_name: String,
+    // Unknown until the buds answered the version request
+    pub firmware: Option<FirmwareVersion>,
+    // Name of the device set in BlueZ
+    pub alias: Option<String>,
     pub ambient_sound_enabled: bool,
     pub ambient_sound_volume: u8,
     pub extra_high_ambient_volume: bool,
     pub tab_lock_status: ExtTapLockStatus,
+    // Not part of the status of the buds, so only known once the daemon has set it
+    #[serde(default)]
+    pub ambient_during_calls: Option<bool>,
+    // Audio of the buds goes to another host, eg. the phone
+    #[serde(default)]
+    pub other_host_active: bool,
+    // Unknown model, only the battery gets read
+    #[serde(default)]
+    pub battery_only: bool,
+    // Estimates while buds charge in the case
+    #[serde(default)]
+    pub charging: Option<Charging>,
+    // Keys of the supported settings. Only set in status responses
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    // Only set in status responses
+    #[serde(default)]
+    pub labels: Option<Labels>,
+    #[serde(flatten)]
+    pub connection: ConnectionState,
+}
+
+/// Progress of (re)connecting to a device
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ConnectionState {
+    // An attempt to connect is running
+    pub connecting: bool,
+    // Unix time in seconds of the next attempt
+    pub backoff_until: Option<u64>,
+    pub last_error: Option<String>,
+}
+
+impl ConnectionState {
+    /// Wait for the given time before the next attempt
+    pub fn backoff(delay: Duration, error: String) -> Self {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+
+        Self {
+            connecting: false,
+            backoff_until: Some((now + delay).as_secs()),
+            last_error: Some(error),
+        }
+    }
+
+    /// Returns true while the daemon tries to connect
+    pub fn is_pending(&self) -> bool {
+        self.connecting || self.backoff_until.is_some()
+    }
+
+    /// Describe the state, eg. "Reconnecting in 8s"
+    pub fn describe(&self) -> String {
+        if self.connecting {
+            return "Connecting".to_owned();
+        }
+
+        if let Some(until) = self.backoff_until {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            return format!("Reconnecting in {}s", until.saturating_sub(now));
+        }
+
+        match self.last_error {
+            Some(ref err) => format!("Not connected: {}", err),
+            None => "Not connected".to_owned(),
+        }
+    }
 }
 
 impl BudsInfo {
-    pub fn new<S: AsRef<str>>(stream: UnixStream, address: S, model: Model) -> Self {
+    pub fn new<S: AsRef<str>>(
+        stream: UnixStream,
+        address: S,
+        model: Model,
+        alias: Option<String>,
+    ) -> Self {
         Self {
             stream,
             inner: BudsInfoInner {
@@ -81,21 +218,56 @@ impl BudsInfo {
                 equalizer_type: EqualizerType::Undetected,
                 touchpads_blocked: false,
                 noise_reduction: false,
+                // Buds Pro start with high
+                noise_reduction_level: None,
                 did_battery_notify: false,
                 touchpad_option_left: TouchpadOption::Undetected,
                 touchpad_option_right: TouchpadOption::Undetected,
                 paused_music_earlier: false,
                 debug: DebugInfo::default(),
                 model,
+                model_name: models::get(model).name().to_owned(),
+                firmware: None,
+                alias,
                 ambient_sound_enabled: false,
                 ambient_sound_volume: 0,
                 extra_high_ambient_volume: false,
                 tab_lock_status: ExtTapLockStatus::default(),
+                ambient_during_calls: None,
+                other_host_active: false,
+                battery_only: false,
+                charging: None,
+                capabilities: Vec::new(),
+                labels: None,
+                connection: ConnectionState::default(),
             },
             last_debug: SystemTime::now(),
             left_tp_hold_count: 0,
             right_tp_hold_count: 0,
             last_tp_update: SystemTime::now(),
+            touchpad_locked_by_case: false,
+            did_critical_battery_notify: false,
+            did_case_battery_notify: false,
+            mic_source_active: false,
+            events: Vec::new(),
+            dry_run: None,
+            pending_debug: VecDeque::new(),
+            rtt_probe: None,
+            notifier: Notifier::new(address.as_ref()),
+            session: Session::new(),
+            desired_settings: HashMap::new(),
+            stats: Stats {
+                update_mode: models::get(model).update_mode(),
+                ..Stats::default()
+            },
+            fit_test: None,
+            last_extended_status: None,
+            extended_status_waiters: Vec::new(),
+            disconnect_requested: false,
+            last_event: None,
+            charge: ChargeTracker::default(),
+            paused_player: None,
+            muted_sink: false,
         }
     }
 
@@ -110,52 +282,127 @@ impl BudsInfo {
         self.right_tp_hold_count = 0;
     }
 
-    /// Returns the max ambient volume level for the given device
-    pub fn get_max_ambientsound_volume_level(&self) -> u8 {
-        match self.inner.model {
-            Model::BudsPro => {
-                if self.has_feature(Feature::ExtraHighAmbientVolume) {
-                    4
-                } else {
-                    3
-                }
-            }
-            Model::BudsLive => 0,
-            Model::BudsPlus => {
-                if self.has_feature(Feature::ExtraHighAmbientVolume) {
-                    4
-                } else {
-                    3
-                }
-            }
-            Model::Buds => 3,
-            Model::Buds2 => 3,
-            Model::BudsPro2 => {
-                if self.has_feature(Feature::ExtraHighAmbientVolume) {
-                    4
-                } else {
-                    3
-                }
-            }
-        }
-    }
-
     // Send a message to the earbuds
-    pub async fn send<T>(&self, msg: T) -> Result<(), String>
+    pub async fn send<T>(&mut self, msg: T) -> Result<(), String>
     where
         T: message::Payload,
     {
+        self.write_frame(&msg.to_byte_array()).await
+    }
+
+    // Send a message which isn't provided by galaxy_buds_rs
+    pub async fn send_raw(&mut self, msg: RawMessage) -> Result<(), String> {
+        let frame = msg.to_byte_array(self.inner.model);
+        self.write_frame(&frame).await
+    }
+
+    // Write a message frame to the buds or record it in dry run mode
+    pub async fn write_frame(&mut self, frame: &[u8]) -> Result<(), String> {
+        if let Some(ref mut recorded) = self.dry_run {
+            recorded.push(raw_message::describe_frame(frame));
+            return Ok(());
+        }
+
         let mut stream = &self.stream;
-        if let Err(err) = stream.write(&msg.to_byte_array()).await {
-            return Err(err.to_string());
+        let mut retries = 0;
+
+        loop {
+            let err = match stream.write(frame).await {
+                Ok(_) => {
+                    self.stats.frames_sent += 1;
+                    return Ok(());
+                }
+                Err(err) => err,
+            };
+
+            // Give the bluetooth stack some time on transient errors
+            let transient = matches!(
+                err.kind(),
+                ErrorKind::WouldBlock | ErrorKind::Interrupted | ErrorKind::TimedOut
+            );
+            if !transient || retries >= MAX_WRITE_RETRIES {
+                self.stats.write_failures += 1;
+                return Err(err.to_string());
+            }
+
+            retries += 1;
+            self.stats.write_retries += 1;
+            async_std::task::sleep(WRITE_RETRY_DELAY * retries).await;
         }
+    }
 
-        Ok(())
+    /// Ask the buds for their current settings. They answer with an extended status update
+    pub async fn request_extended_status(&mut self) -> Result<(), String> {
+        self.send_raw(RawMessage::new(
+            raw_message::ids::EXTENDED_STATUS_UPDATED,
+            Vec::new(),
+        ))
+        .await
+    }
+
+    /// Returns a receiver which gets notified once the next extended status update got handled
+    pub fn wait_extended_status(&mut self) -> Receiver<()> {
+        let (sender, receiver) = channel::bounded(1);
+        self.extended_status_waiters.push(sender);
+        receiver
+    }
+
+    /// Notify everyone waiting for an extended status update
+    pub fn handled_extended_status(&mut self) {
+        self.last_extended_status = Some(Instant::now());
+        for waiter in self.extended_status_waiters.drain(..) {
+            waiter.try_send(()).ok();
+        }
     }
 
     pub async fn request_debug_data(&mut self) -> Result<(), String> {
+        self.send_debug_request().await.map(|_| ())
+    }
+
+    /// Request debug data and report the round trip time of exactly this
+    /// request to the returned receiver
+    pub async fn probe_debug_data(&mut self) -> Result<Receiver<Duration>, String> {
+        let sent = self.send_debug_request().await?;
+        let (sender, receiver) = channel::bounded(1);
+        self.rtt_probe = Some((sent, sender));
+        Ok(receiver)
+    }
+
+    /// Match a debug data answer to the oldest unanswered request. The buds
+    /// answer in order, requests without answer get dropped after a while
+    pub fn handled_debug_data(&mut self) {
+        self.drop_unanswered_debug();
+        let answered = match self.pending_debug.pop_front() {
+            Some(sent) => sent,
+            None => return,
+        };
+
+        if matches!(self.rtt_probe, Some((probe, _)) if probe == answered) {
+            if let Some((_, sender)) = self.rtt_probe.take() {
+                sender.try_send(answered.elapsed()).ok();
+            }
+        }
+    }
+
+    async fn send_debug_request(&mut self) -> Result<Instant, String> {
         self.last_debug = SystemTime::now();
-        self.send(debug::new(debug::DebugVariant::GetAllData)).await
+        self.stats.debug_requests += 1;
+        let sent = Instant::now();
+        self.send(debug::new(debug::DebugVariant::GetAllData))
+            .await?;
+
+        self.drop_unanswered_debug();
+        self.pending_debug.push_back(sent);
+        Ok(sent)
+    }
+
+    fn drop_unanswered_debug(&mut self) {
+        while let Some(sent) = self.pending_debug.front() {
+            if sent.elapsed() < DEBUG_ANSWER_TIMEOUT {
+                break;
+            }
+            self.pending_debug.pop_front();
+        }
     }
 }
 
@@ -164,6 +411,17 @@ impl BudsInfoInner {
     pub fn has_feature(&self, feature: Feature) -> bool {
         self.model.has_feature(feature)
     }
+
+    /// Returns the max ambient volume level for the given device
+    pub fn get_max_ambientsound_volume_level(&self) -> u8 {
+        models::get(self.model)
+            .max_ambient_volume(self.has_feature(Feature::ExtraHighAmbientVolume))
+    }
+
+    /// Returns true if the model can pass through ambient sound during calls
+    pub fn supports_ambient_during_calls(&self) -> bool {
+        models::get(self.model).supports_ambient_during_calls()
+    }
 }
 
 // Serialize/Deserialize Placement
@@ -0,0 +1,95 @@
+/*
+ * Estimates the charging rate of buds in the case from successive battery
+ * samples and the time until they are full. Charging slows down near 100%,
+ * so estimates are rather optimistic for the last few percent.
+ */
+
+use super::buds_info::BudsInfo;
+
+use galaxy_buds_rs::message::bud_property::Placement;
+use serde::{Deserialize, Serialize};
+
+use std::time::Instant;
+
+// Samples of the recent charging rate. Older ones get dropped
+const MAX_SAMPLES: usize = 10;
+// Estimates need a change of at least this many percent
+const MIN_DELTA: i8 = 2;
+
+/// Estimated charging of a bud
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ChargeEstimate {
+    pub percent_per_hour: u16,
+    pub minutes_to_full: u32,
+}
+
+/// Charging estimates of buds in the case
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Charging {
+    pub left: Option<ChargeEstimate>,
+    pub right: Option<ChargeEstimate>,
+}
+
+/// Battery levels of the buds since they got placed into the case
+#[derive(Debug, Default)]
+pub struct ChargeTracker {
+    left: Vec<(Instant, i8)>,
+    right: Vec<(Instant, i8)>,
+    // Whether the estimate of this charging got notified already
+    pub notified: bool,
+}
+
+/// Record the current battery levels and update the estimates of the status
+pub fn update(info: &mut BudsInfo) {
+    let inner = &info.inner;
+    let tracker = &mut info.charge;
+
+    let left = sample(&mut tracker.left, inner.placement_left, inner.batt_left);
+    let right = sample(&mut tracker.right, inner.placement_right, inner.batt_right);
+    if tracker.left.is_empty() && tracker.right.is_empty() {
+        tracker.notified = false;
+    }
+
+    info.inner.charging = if left.is_some() || right.is_some() {
+        Some(Charging { left, right })
+    } else {
+        None
+    };
+}
+
+// Add a sample of a bud and estimate its charging. Samples get reset once the bud left the case
+fn sample(
+    samples: &mut Vec<(Instant, i8)>,
+    placement: Placement,
+    level: i8,
+) -> Option<ChargeEstimate> {
+    if placement != Placement::InOpenCase && placement != Placement::InCloseCase {
+        samples.clear();
+        return None;
+    }
+
+    // Only changes tell something about the rate
+    if samples.last().map(|i| i.1) != Some(level) {
+        samples.push((Instant::now(), level));
+        if samples.len() > MAX_SAMPLES {
+            samples.remove(0);
+        }
+    }
+
+    let (first_time, first) = *samples.first()?;
+    let (last_time, last) = *samples.last()?;
+    if last - first < MIN_DELTA || level >= 100 {
+        return None;
+    }
+
+    let hours = last_time.duration_since(first_time).as_secs_f64() / 3600.0;
+    if hours <= 0.0 {
+        return None;
+    }
+
+    let rate = (last - first) as f64 / hours;
+    Some(ChargeEstimate {
+        percent_per_hour: rate.round() as u16,
+        minutes_to_full: ((100 - level) as f64 / rate * 60.0).ceil() as u32,
+    })
+}
@@ -0,0 +1,150 @@
+/*
+ * Upgrades configs written by older versions. Each migration converts the
+ * raw toml of one version into the next one, so changing the layout of the
+ * config only needs a new entry in `MIGRATIONS`. Configs only get rewritten
+ * entirely if a migration changed the layout, since that loses comments.
+ */
+
+use toml::value::Table;
+
+use std::convert::TryFrom;
+
+/// Version of the current config layout
+pub const CONFIG_VERSION: u32 = 1;
+
+// Migrations by the version they upgrade from. They return true if they changed the layout
+static MIGRATIONS: &[fn(&mut Table) -> bool] = &[from_unversioned];
+
+/// What a migration changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Migrated {
+    UpToDate,
+    // Only the version has to be set
+    Version,
+    Layout,
+}
+
+/// Upgrade a config to the current version
+pub fn migrate(value: &mut toml::Value) -> Result<Migrated, String> {
+    let table = value.as_table_mut().ok_or("Invalid config")?;
+
+    let version = match table.get("version") {
+        Some(v) => {
+            let version = v.as_integer().ok_or("Invalid config version")?;
+            u32::try_from(version).map_err(|_| format!("Invalid config version {}", version))?
+        }
+        None => 0,
+    };
+
+    if version > CONFIG_VERSION {
+        return Err(format!(
+            "The config has version {} but only {} is supported. Please upgrade earbuds",
+            version, CONFIG_VERSION
+        ));
+    }
+
+    let mut layout_changed = false;
+    for migration in &MIGRATIONS[version as usize..] {
+        layout_changed |= migration(table);
+    }
+
+    table.insert(
+        "version".to_owned(),
+        toml::Value::Integer(CONFIG_VERSION as i64),
+    );
+
+    Ok(if layout_changed {
+        Migrated::Layout
+    } else if version < CONFIG_VERSION {
+        Migrated::Version
+    } else {
+        Migrated::UpToDate
+    })
+}
+
+/// Set the version in the text of a config, keeping comments and formatting
+pub fn set_version(config: &str) -> String {
+    let version = format!("version = {}", CONFIG_VERSION);
+    let mut lines: Vec<String> = config.lines().map(|i| i.to_owned()).collect();
+
+    // Top level keys come before the first table
+    let existing = lines
+        .iter()
+        .take_while(|i| !i.trim_start().starts_with('['))
+        .position(|i| {
+            let key = i.split('=').next().unwrap_or_default().trim();
+            i.contains('=') && key == "version"
+        });
+
+    match existing {
+        Some(pos) => lines[pos] = version,
+        None => lines.insert(0, version),
+    }
+
+    let mut config = lines.join("\n");
+    config.push('\n');
+    config
+}
+
+// Configs before versioning only lack the version field
+fn from_unversioned(_table: &mut Table) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn migrate_str(config: &str) -> Result<(Migrated, toml::Value), String> {
+        let mut value: toml::Value = toml::from_str(config).unwrap();
+        migrate(&mut value).map(|migrated| (migrated, value))
+    }
+
+    #[test]
+    fn test_migrate_unversioned() {
+        let (migrated, value) = migrate_str("allowed_devices = []").unwrap();
+        assert_eq!(migrated, Migrated::Version);
+        assert_eq!(value["version"].as_integer(), Some(CONFIG_VERSION as i64));
+        assert!(value.get("allowed_devices").is_some());
+    }
+
+    #[test]
+    fn test_migrate_current() {
+        let config = format!("version = {}", CONFIG_VERSION);
+        let (migrated, _) = migrate_str(&config).unwrap();
+        assert_eq!(migrated, Migrated::UpToDate);
+    }
+
+    #[test]
+    fn test_migrate_invalid_versions() {
+        let newer = format!("version = {}", CONFIG_VERSION + 1);
+        assert!(migrate_str(&newer).is_err());
+        assert!(migrate_str("version = -1").is_err());
+        assert!(migrate_str("version = 4294967296").is_err());
+        assert!(migrate_str("version = '1'").is_err());
+    }
+
+    #[test]
+    fn test_set_version() {
+        let config = "# Layout version\nversion = 0\n# Devices\n[[buds_settings]]\nversion = 5\n";
+        assert_eq!(
+            set_version(config),
+            format!(
+                "# Layout version\nversion = {}\n# Devices\n[[buds_settings]]\nversion = 5\n",
+                CONFIG_VERSION
+            )
+        );
+    }
+
+    #[test]
+    fn test_set_version_missing() {
+        let config = "# Comment\nversions = 3\n[notifications.low_battery]\nversion = 3";
+        assert_eq!(
+            set_version(config),
+            format!(
+                "version = {}\n# Comment\nversions = 3\n[notifications.low_battery]\nversion = 3\n",
+                CONFIG_VERSION
+            )
+        );
+    }
+}
@@ -0,0 +1,111 @@
+/*
+ * Records every disconnect into the history store to find out how stable the
+ * connection to the buds is over time.
+ */
+
+use super::{buds_info::BudsInfo, history};
+
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Why a connection ended
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisconnectReason {
+    // Reading from the buds failed, eg. out of range or turned off
+    ConnectionLost,
+    // A client or a touchpad action disconnected the buds
+    Requested,
+    // The connection handler panicked
+    HandlerPanic,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct DisconnectEntry {
+    device: String,
+    connected_at: u64,
+    disconnected_at: u64,
+    duration_secs: u64,
+    reason: DisconnectReason,
+}
+
+/// Disconnects of a single day (UTC)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DaySummary {
+    pub day: String,
+    pub disconnects: u32,
+    // Disconnects which weren't requested
+    pub drops: u32,
+    pub avg_duration_secs: u64,
+}
+
+/// Write the disconnect of a device into the history
+pub async fn record(info: &BudsInfo, reason: DisconnectReason) {
+    let connected_at = to_secs(info.session.started());
+    let disconnected_at = to_secs(SystemTime::now());
+
+    let entry = DisconnectEntry {
+        device: info.inner.address.clone(),
+        connected_at,
+        disconnected_at,
+        duration_secs: disconnected_at.saturating_sub(connected_at),
+        reason,
+    };
+
+    if let Err(err) = history::append("disconnect", &entry).await {
+        eprintln!("Can't write disconnect to history: {}", err);
+    }
+}
+
+/// Summarize the recorded disconnects per day, oldest first
+pub async fn summarize() -> Result<Vec<DaySummary>, String> {
+    let mut days: BTreeMap<u64, (DaySummary, u64)> = BTreeMap::new();
+
+    for entry in history::read::<DisconnectEntry>("disconnect").await? {
+        let day = entry.disconnected_at / SECS_PER_DAY;
+        let (summary, total_duration) = days.entry(day).or_default();
+
+        summary.disconnects += 1;
+        if entry.reason != DisconnectReason::Requested {
+            summary.drops += 1;
+        }
+        *total_duration += entry.duration_secs;
+    }
+
+    Ok(days
+        .into_iter()
+        .map(|(day, (mut summary, total_duration))| {
+            summary.day = format_day(day);
+            summary.avg_duration_secs = total_duration / summary.disconnects as u64;
+            summary
+        })
+        .collect())
+}
+
+fn to_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+// Format days since the epoch as YYYY-MM-DD
+fn format_day(days: u64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days as i64 + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
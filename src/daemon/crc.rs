@@ -0,0 +1,20 @@
+/*
+ * Checksum of the message frames. Has no dependencies, since the budsim
+ * binary includes this file as well.
+ */
+
+/// CRC16 CCITT (XMODEM) as used by the buds
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0;
+    for byte in data {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            if crc & 0x8000 != 0 {
+                crc = (crc << 1) ^ 0x1021;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
@@ -0,0 +1,439 @@
+/*
+ * Session bus interface for desktop applets (eg. a Plasma data engine).
+ * `org.livebuds.Daemon1` at /org/livebuds/Daemon lists the connected
+ * devices, each exposing `org.livebuds.Device1` properties and methods
+ * mirroring the get_status, get_value, set_value and toggle_value requests of
+ * the unix socket. Changes are announced through the standard
+ * PropertiesChanged signal. The stable part is described in dbus_api.rs.
+ */
+
+use super::bluetooth::rfcomm_connector::{ConnectionData, Subscriber};
+use super::buds_config::Config;
+use super::buds_info::{BudsInfo, BudsInfoInner};
+use super::dbus_api::{API_VERSION, BUS_NAME, ROOT_PATH};
+use super::settings::{self, Value};
+use super::unix_socket::{audit, Request};
+
+use async_std::sync::{Arc, Mutex};
+use galaxy_buds_rs::message::bud_property::Placement;
+use log::error;
+use zbus::{dbus_interface, fdo, zvariant::OwnedObjectPath, Connection, ConnectionBuilder};
+
+use std::{collections::HashMap, convert::TryFrom};
+
+struct Daemon {
+    devices: Vec<OwnedObjectPath>,
+}
+
+#[dbus_interface(name = "org.livebuds.Daemon1")]
+impl Daemon {
+    #[dbus_interface(property)]
+    fn version(&self) -> u32 {
+        API_VERSION
+    }
+
+    /// Object paths of all connected devices
+    #[dbus_interface(property)]
+    fn devices(&self) -> Vec<OwnedObjectPath> {
+        self.devices.clone()
+    }
+}
+
+/// Values of a device exposed as properties
+#[derive(Debug, Clone, PartialEq)]
+struct DeviceState {
+    address: String,
+    model: String,
+    battery_left: i32,
+    battery_right: i32,
+    battery_case: i32,
+    noise_reduction: bool,
+    ambient_sound: bool,
+    wearing_left: bool,
+    wearing_right: bool,
+}
+
+impl DeviceState {
+    fn new(info: &BudsInfoInner) -> Self {
+        Self {
+            address: info.address.clone(),
+            model: format!("{:?}", info.model),
+            battery_left: info.batt_left as i32,
+            battery_right: info.batt_right as i32,
+            battery_case: info.batt_case as i32,
+            noise_reduction: info.noise_reduction,
+            ambient_sound: info.ambient_sound_enabled,
+            wearing_left: info.placement_left == Placement::Ear,
+            wearing_right: info.placement_right == Placement::Ear,
+        }
+    }
+}
+
+struct Device {
+    state: DeviceState,
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+}
+
+impl Device {
+    // Run `f` on the connected device
+    async fn with_device<F, T>(&self, f: F) -> fdo::Result<T>
+    where
+        F: FnOnce(&mut BudsInfo) -> T,
+    {
+        let mut connection_data = self.cd.lock().await;
+        match connection_data.get_device_mut(&self.state.address) {
+            Some(info) if info.inner.ready => Ok(f(info)),
+            _ => Err(not_connected()),
+        }
+    }
+
+    // Record a change in the audit log like the requests of the unix socket
+    async fn audit<T>(&self, cmd: &str, params: [Option<&str>; 3], result: &Result<T, String>) {
+        let mut request = Request::new(cmd.to_owned(), Some(self.state.address.clone()));
+        request.opt_param1 = params[0].map(|i| i.to_owned());
+        request.opt_param2 = params[1].map(|i| i.to_owned());
+        request.opt_param3 = params[2].map(|i| i.to_owned());
+
+        let result = result.as_ref().map(|_| ()).map_err(|e| e.as_str());
+        audit::audit_result(&self.config, &request, result).await;
+    }
+}
+
+fn not_connected() -> fdo::Error {
+    fdo::Error::Failed("Device not connected".to_owned())
+}
+
+// Empty strings select no side
+fn param(side: &str) -> Option<&str> {
+    Some(side).filter(|i| !i.is_empty())
+}
+
+#[dbus_interface(name = "org.livebuds.Device1")]
+impl Device {
+    /// The status as json, same as the payload of get_status
+    async fn get_status(&self) -> fdo::Result<String> {
+        self.with_device(|info| {
+            let mut status = info.inner.clone();
+            status.capabilities = settings::supported(&status)
+                .into_iter()
+                .map(|i| i.to_owned())
+                .collect();
+            serde_json::to_string(&status).unwrap()
+        })
+        .await
+    }
+
+    /// The value of a setting, eg. "noise_reduction". `side` is "left",
+    /// "right" or empty
+    async fn get_value(&self, key: String, side: String) -> fdo::Result<String> {
+        self.with_device(|info| settings::get(&info.inner, &key, param(&side)))
+            .await?
+            .map(|i| i.to_string())
+            .map_err(fdo::Error::Failed)
+    }
+
+    /// Set a setting. Returns false if the buds already had the value
+    async fn set_value(&self, key: String, value: String, side: String) -> fdo::Result<bool> {
+        let res = {
+            let mut connection_data = self.cd.lock().await;
+            match connection_data.get_device_mut(&self.state.address) {
+                Some(info) if info.inner.ready => {
+                    settings::set(info, &key, &value, param(&side), false).await
+                }
+                _ => return Err(not_connected()),
+            }
+        };
+
+        let params = [Some(key.as_str()), Some(value.as_str()), param(&side)];
+        self.audit("set_value", params, &res).await;
+        res.map_err(fdo::Error::Failed)
+    }
+
+    /// Invert a boolean setting. Returns the new value
+    async fn toggle_value(&self, key: String, side: String) -> fdo::Result<String> {
+        let res: Result<Value, String> = {
+            let mut connection_data = self.cd.lock().await;
+            match connection_data.get_device_mut(&self.state.address) {
+                Some(info) if info.inner.ready => settings::toggle(info, &key, param(&side)).await,
+                _ => return Err(not_connected()),
+            }
+        };
+
+        self.audit(
+            "toggle_value",
+            [Some(key.as_str()), None, param(&side)],
+            &res,
+        )
+        .await;
+        res.map(|i| i.to_string()).map_err(fdo::Error::Failed)
+    }
+
+    #[dbus_interface(property)]
+    fn address(&self) -> String {
+        self.state.address.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn model(&self) -> String {
+        self.state.model.clone()
+    }
+
+    #[dbus_interface(property)]
+    fn battery_left(&self) -> i32 {
+        self.state.battery_left
+    }
+
+    #[dbus_interface(property)]
+    fn battery_right(&self) -> i32 {
+        self.state.battery_right
+    }
+
+    #[dbus_interface(property)]
+    fn battery_case(&self) -> i32 {
+        self.state.battery_case
+    }
+
+    #[dbus_interface(property)]
+    fn noise_reduction(&self) -> bool {
+        self.state.noise_reduction
+    }
+
+    #[dbus_interface(property)]
+    fn ambient_sound(&self) -> bool {
+        self.state.ambient_sound
+    }
+
+    #[dbus_interface(property)]
+    fn wearing_left(&self) -> bool {
+        self.state.wearing_left
+    }
+
+    #[dbus_interface(property)]
+    fn wearing_right(&self) -> bool {
+        self.state.wearing_right
+    }
+
+    /// True if at least one bud is worn
+    #[dbus_interface(property)]
+    fn wearing(&self) -> bool {
+        self.state.wearing_left || self.state.wearing_right
+    }
+}
+
+/// Serve the interface until the daemon exits
+pub async fn run(cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) {
+    let connection = match connect().await {
+        Ok(c) => c,
+        Err(err) => {
+            error!("Can't provide the D-Bus interface: {}", err);
+            return;
+        }
+    };
+
+    // Get notified about every change. The exported values get pushed by the buds
+    let (subscriber, receiver) = Subscriber::new(None, true);
+    cd.lock().await.subscribers.push(subscriber);
+
+    let mut exported: HashMap<String, DeviceState> = HashMap::new();
+
+    loop {
+        let states: HashMap<String, DeviceState> = cd
+            .lock()
+            .await
+            .data
+            .iter()
+            .map(|(addr, info)| (addr.clone(), DeviceState::new(&info.inner)))
+            .collect();
+
+        if let Err(err) = sync(&connection, &cd, &config, &mut exported, states).await {
+            error!("Can't update D-Bus properties: {}", err);
+        }
+
+        if receiver.recv().await.is_err() {
+            return;
+        }
+    }
+}
+
+async fn connect() -> zbus::Result<Connection> {
+    ConnectionBuilder::session()?
+        .name(BUS_NAME)?
+        .serve_at(ROOT_PATH, Daemon { devices: vec![] })?
+        .build()
+        .await
+}
+
+// Export new devices, remove disconnected ones and update changed properties
+async fn sync(
+    connection: &Connection,
+    cd: &Arc<Mutex<ConnectionData>>,
+    config: &Arc<Mutex<Config>>,
+    exported: &mut HashMap<String, DeviceState>,
+    states: HashMap<String, DeviceState>,
+) -> zbus::Result<()> {
+    let server = connection.object_server();
+    let mut devices_changed = false;
+
+    let removed: Vec<String> = exported
+        .keys()
+        .filter(|i| !states.contains_key(*i))
+        .cloned()
+        .collect();
+    for address in removed {
+        exported.remove(&address);
+        server.remove::<Device, _>(device_path(&address)).await?;
+        devices_changed = true;
+    }
+
+    for (address, state) in states {
+        let path = device_path(&address);
+
+        let old = match exported.insert(address, state.clone()) {
+            Some(old) => old,
+            None => {
+                let device = Device {
+                    state,
+                    cd: Arc::clone(cd),
+                    config: Arc::clone(config),
+                };
+                server.at(path, device).await?;
+                devices_changed = true;
+                continue;
+            }
+        };
+
+        if old == state {
+            continue;
+        }
+
+        let iface_ref = server.interface::<_, Device>(path).await?;
+        let mut iface = iface_ref.get_mut().await;
+        iface.state = state.clone();
+        let ctxt = iface_ref.signal_context();
+
+        if old.battery_left != state.battery_left {
+            iface.battery_left_changed(ctxt).await?;
+        }
+        if old.battery_right != state.battery_right {
+            iface.battery_right_changed(ctxt).await?;
+        }
+        if old.battery_case != state.battery_case {
+            iface.battery_case_changed(ctxt).await?;
+        }
+        if old.noise_reduction != state.noise_reduction {
+            iface.noise_reduction_changed(ctxt).await?;
+        }
+        if old.ambient_sound != state.ambient_sound {
+            iface.ambient_sound_changed(ctxt).await?;
+        }
+        if old.wearing_left != state.wearing_left {
+            iface.wearing_left_changed(ctxt).await?;
+        }
+        if old.wearing_right != state.wearing_right {
+            iface.wearing_right_changed(ctxt).await?;
+        }
+        if (old.wearing_left || old.wearing_right) != (state.wearing_left || state.wearing_right) {
+            iface.wearing_changed(ctxt).await?;
+        }
+    }
+
+    if devices_changed {
+        let iface_ref = server.interface::<_, Daemon>(ROOT_PATH).await?;
+        let mut iface = iface_ref.get_mut().await;
+        iface.devices = exported.keys().map(|i| device_path(i)).collect();
+        iface.devices_changed(iface_ref.signal_context()).await?;
+    }
+
+    Ok(())
+}
+
+// Object path of a device, eg. /org/livebuds/Daemon/devices/AA_BB_CC_DD_EE_FF
+fn device_path(address: &str) -> OwnedObjectPath {
+    let path = format!("{}/devices/{}", ROOT_PATH, address.replace(':', "_"));
+    OwnedObjectPath::try_from(path).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::dbus_api::INTERFACES;
+    use super::*;
+    use zbus::{xml::Node, Interface};
+
+    // Introspect the implementation like clients see it
+    fn implementation() -> Node {
+        let device = Device {
+            state: DeviceState {
+                address: String::new(),
+                model: String::new(),
+                battery_left: 0,
+                battery_right: 0,
+                battery_case: 0,
+                noise_reduction: false,
+                ambient_sound: false,
+                wearing_left: false,
+                wearing_right: false,
+            },
+            cd: Arc::new(Mutex::new(ConnectionData::new())),
+            config: Arc::new(Mutex::new(Config::default())),
+        };
+
+        let mut xml = String::from("<node>");
+        Daemon { devices: vec![] }.introspect_to_writer(&mut xml, 0);
+        device.introspect_to_writer(&mut xml, 0);
+        xml.push_str("</node>");
+
+        Node::from_reader(xml.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_stable_api_is_implemented() {
+        let node = implementation();
+        assert!(!INTERFACES.is_empty());
+
+        for stable in INTERFACES {
+            let interfaces = node.interfaces();
+            let interface = interfaces
+                .iter()
+                .find(|i| i.name() == stable.name)
+                .unwrap_or_else(|| panic!("{} is missing in the implementation", stable.name));
+
+            for property in stable.properties {
+                let properties = interface.properties();
+                let implemented = properties
+                    .iter()
+                    .find(|i| i.name() == property.name)
+                    .unwrap_or_else(|| panic!("{}.{} is missing", stable.name, property.name));
+                assert_eq!(implemented.ty(), property.signature, "{}", property.name);
+                assert_eq!(implemented.access(), "read", "{}", property.name);
+            }
+
+            for method in stable.methods {
+                let methods = interface.methods();
+                let implemented = methods
+                    .iter()
+                    .find(|i| i.name() == method.name)
+                    .unwrap_or_else(|| panic!("{}.{} is missing", stable.name, method.name));
+
+                // zbus doesn't name output arguments
+                let args: Vec<_> = implemented
+                    .args()
+                    .iter()
+                    .map(|i| {
+                        let name = i.name().filter(|_| i.direction() == Some("in"));
+                        (name, i.ty(), i.direction())
+                    })
+                    .collect();
+                let expected: Vec<_> = method
+                    .args
+                    .iter()
+                    .map(|i| {
+                        let name = Some(i.name).filter(|_| i.direction == "in");
+                        (name, i.signature, Some(i.direction))
+                    })
+                    .collect();
+                assert_eq!(args, expected, "{}.{}", stable.name, method.name);
+            }
+        }
+    }
+}
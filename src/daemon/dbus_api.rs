@@ -0,0 +1,194 @@
+/*
+ * The stable subset of the D-Bus interface. Clients (eg. GNOME Shell
+ * extensions) can rely on everything listed here for a given API_VERSION.
+ * build.rs includes this file to generate the introspection XML, so it
+ * must not use anything besides core types.
+ */
+
+/// Increased on incompatible changes of the members below
+pub const API_VERSION: u32 = 1;
+
+pub const BUS_NAME: &str = "org.livebuds.Daemon";
+pub const ROOT_PATH: &str = "/org/livebuds/Daemon";
+
+/// A read only property
+pub struct Property {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub doc: &'static str,
+}
+
+/// An argument of a method, `direction` is "in" or "out"
+pub struct Arg {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub direction: &'static str,
+}
+
+pub struct Method {
+    pub name: &'static str,
+    pub args: &'static [Arg],
+    pub doc: &'static str,
+}
+
+pub struct Interface {
+    pub name: &'static str,
+    pub doc: &'static str,
+    pub properties: &'static [Property],
+    pub methods: &'static [Method],
+}
+
+pub const INTERFACES: &[Interface] = &[
+    Interface {
+        name: "org.livebuds.Daemon1",
+        doc: "The daemon at /org/livebuds/Daemon",
+        properties: &[
+            Property {
+                name: "Version",
+                signature: "u",
+                doc: "Version of this interface",
+            },
+            Property {
+                name: "Devices",
+                signature: "ao",
+                doc: "Object paths of the connected devices",
+            },
+        ],
+        methods: &[],
+    },
+    Interface {
+        name: "org.livebuds.Device1",
+        doc: "A connected device at /org/livebuds/Daemon/devices/<address with underscores>",
+        properties: &[
+            Property {
+                name: "Address",
+                signature: "s",
+                doc: "Bluetooth address",
+            },
+            Property {
+                name: "Model",
+                signature: "s",
+                doc: "Model of the buds",
+            },
+            Property {
+                name: "BatteryLeft",
+                signature: "i",
+                doc: "Battery of the left bud in percent",
+            },
+            Property {
+                name: "BatteryRight",
+                signature: "i",
+                doc: "Battery of the right bud in percent",
+            },
+            Property {
+                name: "BatteryCase",
+                signature: "i",
+                doc: "Battery of the case in percent",
+            },
+            Property {
+                name: "NoiseReduction",
+                signature: "b",
+                doc: "Active noise cancelling",
+            },
+            Property {
+                name: "AmbientSound",
+                signature: "b",
+                doc: "Ambient sound",
+            },
+            Property {
+                name: "WearingLeft",
+                signature: "b",
+                doc: "The left bud is in the ear",
+            },
+            Property {
+                name: "WearingRight",
+                signature: "b",
+                doc: "The right bud is in the ear",
+            },
+            Property {
+                name: "Wearing",
+                signature: "b",
+                doc: "At least one bud is in the ear",
+            },
+        ],
+        methods: &[
+            Method {
+                name: "GetStatus",
+                args: &[Arg {
+                    name: "status",
+                    signature: "s",
+                    direction: "out",
+                }],
+                doc: "The status as json, like the payload of `earbuds status -o json`",
+            },
+            Method {
+                name: "GetValue",
+                args: &[
+                    Arg {
+                        name: "key",
+                        signature: "s",
+                        direction: "in",
+                    },
+                    Arg {
+                        name: "side",
+                        signature: "s",
+                        direction: "in",
+                    },
+                    Arg {
+                        name: "value",
+                        signature: "s",
+                        direction: "out",
+                    },
+                ],
+                doc: "Value of a setting (eg. noise_reduction). Side is left, right or empty",
+            },
+            Method {
+                name: "SetValue",
+                args: &[
+                    Arg {
+                        name: "key",
+                        signature: "s",
+                        direction: "in",
+                    },
+                    Arg {
+                        name: "value",
+                        signature: "s",
+                        direction: "in",
+                    },
+                    Arg {
+                        name: "side",
+                        signature: "s",
+                        direction: "in",
+                    },
+                    Arg {
+                        name: "changed",
+                        signature: "b",
+                        direction: "out",
+                    },
+                ],
+                doc: "Set a setting. Returns false if the buds already had the value",
+            },
+            Method {
+                name: "ToggleValue",
+                args: &[
+                    Arg {
+                        name: "key",
+                        signature: "s",
+                        direction: "in",
+                    },
+                    Arg {
+                        name: "side",
+                        signature: "s",
+                        direction: "in",
+                    },
+                    Arg {
+                        name: "value",
+                        signature: "s",
+                        direction: "out",
+                    },
+                ],
+                doc: "Invert a boolean setting and return the new value",
+            },
+        ],
+    },
+];
@@ -0,0 +1,274 @@
+/*
+ * Keeps recent log lines and raw frames in memory. Log lines can be requested
+ * and frames followed by clients, and everything gets written, together with
+ * the config, into a diagnostics bundle if the daemon panics.
+ */
+
+use super::{buds_config::get_home_dir, history, raw_message};
+
+use async_std::channel::{self, Receiver, Sender, TrySendError};
+use log::{LevelFilter, Log, Metadata, Record};
+use serde_json::json;
+
+use std::{
+    collections::VecDeque,
+    fs, panic,
+    path::PathBuf,
+    sync::Mutex,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const LOG_CAPACITY: usize = 500;
+// Lines a follower may lag behind. The oldest ones get dropped for it
+const FOLLOWER_CAPACITY: usize = 256;
+const FRAME_CAPACITY: usize = 32;
+const REDACTED_ADDRESS: &str = "XX:XX:XX:XX:XX:XX";
+
+static LOG_LINES: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+static LOG_FOLLOWERS: Mutex<Vec<Follower<String>>> = Mutex::new(Vec::new());
+static FRAMES: Mutex<VecDeque<serde_json::Value>> = Mutex::new(VecDeque::new());
+static FRAME_FOLLOWERS: Mutex<Vec<Follower<(String, Vec<u8>)>>> = Mutex::new(Vec::new());
+
+/// A client following log lines or frames
+struct Follower<T> {
+    sender: Sender<T>,
+    // Used to drop the oldest item if the follower lags behind
+    dropper: Receiver<T>,
+}
+
+impl<T> Follower<T> {
+    fn new() -> (Self, Receiver<T>) {
+        let (sender, receiver) = channel::bounded(FOLLOWER_CAPACITY);
+        let follower = Self {
+            sender,
+            dropper: receiver.clone(),
+        };
+        (follower, receiver)
+    }
+
+    // Queue an item. Returns false if the follower is gone
+    fn send(&self, item: T) -> bool {
+        if self.sender.receiver_count() <= 1 {
+            return false;
+        }
+
+        match self.sender.try_send(item) {
+            Ok(()) => true,
+            Err(TrySendError::Full(item)) => {
+                self.dropper.try_recv().ok();
+                self.sender.try_send(item).is_ok()
+            }
+            Err(TrySendError::Closed(_)) => false,
+        }
+    }
+}
+
+/// Logger writing to the wrapped logger and the in-memory log
+struct BufferedLogger<L> {
+    inner: L,
+}
+
+impl<L: Log> Log for BufferedLogger<L> {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.inner.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format!(
+            "{} {} {}: {}",
+            now(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+
+        // Followers get registered while the lines are locked, so they get
+        // every line either from the backlog or sent
+        if let Ok(mut lines) = LOG_LINES.lock() {
+            // Forget followers which are gone
+            if let Ok(mut followers) = LOG_FOLLOWERS.lock() {
+                followers.retain(|i| i.send(line.clone()));
+            }
+
+            if lines.len() >= LOG_CAPACITY {
+                lines.pop_front();
+            }
+            lines.push_back(line);
+        }
+
+        self.inner.log(record);
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Use the given logger and keep the last log lines in memory
+pub fn init_logger<L: Log + 'static>(logger: L, level: LevelFilter) {
+    if log::set_boxed_logger(Box::new(BufferedLogger { inner: logger })).is_ok() {
+        log::set_max_level(level);
+    }
+}
+
+/// Returns the last log lines
+pub fn recent_logs() -> Vec<String> {
+    LOG_LINES
+        .lock()
+        .map(|i| i.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Returns the last log lines and a receiver for all following ones, without
+/// missing or repeating a line in between
+pub fn follow_logs() -> (Vec<String>, Receiver<String>) {
+    let (follower, receiver) = Follower::new();
+
+    let lines = match LOG_LINES.lock() {
+        Ok(lines) => {
+            if let Ok(mut followers) = LOG_FOLLOWERS.lock() {
+                followers.push(follower);
+            }
+            lines.iter().cloned().collect()
+        }
+        Err(_) => Vec::new(),
+    };
+
+    (lines, receiver)
+}
+
+/// Returns a receiver for all following frames with the address of their device
+pub fn follow_frames() -> Receiver<(String, Vec<u8>)> {
+    let (follower, receiver) = Follower::new();
+    if let Ok(mut followers) = FRAME_FOLLOWERS.lock() {
+        followers.push(follower);
+    }
+    receiver
+}
+
+/// Remember a frame received from a device
+pub fn record_frame(address: &str, frame: &[u8]) {
+    if let Ok(mut followers) = FRAME_FOLLOWERS.lock() {
+        followers.retain(|i| i.send((address.to_owned(), frame.to_vec())));
+    }
+
+    let entry = json!({
+        "device": redact(address),
+        "time": now(),
+        "frame": raw_message::to_hex(frame),
+    });
+    push(&FRAMES, entry, FRAME_CAPACITY);
+}
+
+/// Write a diagnostics bundle on panics before running the previous hook
+pub fn install_panic_hook() {
+    let previous = panic::take_hook();
+    panic::set_hook(Box::new(move |panic_info| {
+        let message = panic_info
+            .payload()
+            .downcast_ref::<&str>()
+            .map(|i| i.to_string())
+            .or_else(|| panic_info.payload().downcast_ref::<String>().cloned())
+            .unwrap_or_default();
+        let location = panic_info.location().map(|i| i.to_string());
+
+        match write_bundle(message, location) {
+            Ok(path) => eprintln!(
+                "Wrote diagnostics to {}. Please attach it to your bug report",
+                path.display()
+            ),
+            Err(err) => eprintln!("Can't write diagnostics: {}", err),
+        }
+        previous(panic_info);
+    }));
+}
+
+fn write_bundle(message: String, location: Option<String>) -> Result<PathBuf, String> {
+    // The hook might run while the buffers are locked
+    let logs: Vec<String> = LOG_LINES
+        .try_lock()
+        .map(|i| i.iter().cloned().collect())
+        .unwrap_or_default();
+    let frames: Vec<serde_json::Value> = FRAMES
+        .try_lock()
+        .map(|i| i.iter().cloned().collect())
+        .unwrap_or_default();
+
+    let bundle = json!({
+        "time": now(),
+        "version": env!("CARGO_PKG_VERSION"),
+        "thread": std::thread::current().name(),
+        "message": message,
+        "location": location,
+        "logs": logs,
+        "frames": frames,
+        "config": read_config(),
+    });
+
+    let dir: PathBuf = history::get_state_dir()
+        .ok_or("Can't find state directory")?
+        .into();
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+    let file = dir.join(format!("crash-{}.json", now()));
+    let content = serde_json::to_string_pretty(&bundle).map_err(|e| e.to_string())?;
+    fs::write(&file, content).map_err(|e| e.to_string())?;
+
+    Ok(file)
+}
+
+// Read the config with all device addresses replaced
+fn read_config() -> Option<String> {
+    let file = get_home_dir()?
+        .join(".config")
+        .join("livebuds")
+        .join("config.toml");
+    let content = fs::read_to_string(file).ok()?;
+
+    let mut value: toml::Value = toml::from_str(&content).ok()?;
+    redact_value(&mut value);
+    toml::to_string_pretty(&value).ok()
+}
+
+fn redact_value(value: &mut toml::Value) {
+    match value {
+        toml::Value::String(s) => *s = redact(s),
+        toml::Value::Array(arr) => arr.iter_mut().for_each(redact_value),
+        toml::Value::Table(table) => table.values_mut().for_each(redact_value),
+        _ => (),
+    }
+}
+
+// Replace bluetooth addresses
+fn redact(s: &str) -> String {
+    let is_address = s.len() == 17
+        && s.split(':').count() == 6
+        && s.split(':')
+            .all(|i| i.len() == 2 && i.chars().all(|c| c.is_ascii_hexdigit()));
+
+    if is_address {
+        REDACTED_ADDRESS.to_owned()
+    } else {
+        s.to_owned()
+    }
+}
+
+fn push<T>(buffer: &Mutex<VecDeque<T>>, item: T, capacity: usize) {
+    if let Ok(mut buffer) = buffer.lock() {
+        if buffer.len() >= capacity {
+            buffer.pop_front();
+        }
+        buffer.push_back(item);
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,66 @@
+/*
+ * Firmware version of the buds. Only the date and release part of the version
+ * string (eg. "UK1" of "R177XXU0AUK1") is kept since it's enough to compare
+ * versions of the same model.
+ */
+
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+// Year letters start at 2015
+const FIRST_YEAR: u8 = b'O';
+const RELEASE_CHARS: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct FirmwareVersion {
+    year: u8,
+    month: u8,
+    release: u8,
+}
+
+impl FirmwareVersion {
+    /// Decode the software version of the left bud from a VERSION_INFO payload
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        // Hardware versions come first, then variant, date and release of each bud
+        let date = *payload.get(3)?;
+        let release = *payload.get(4)?;
+
+        Some(Self {
+            year: date >> 4,
+            month: date & 0x0F,
+            release,
+        })
+    }
+
+    /// Parse the date and release part of a version string, eg. "UK1"
+    pub fn parse(s: &str) -> Option<Self> {
+        let s = s.as_bytes();
+        if s.len() != 3 {
+            return None;
+        }
+
+        Some(Self {
+            year: s[0].checked_sub(FIRST_YEAR)?,
+            month: s[1].checked_sub(b'A').filter(|i| *i < 12)?,
+            release: RELEASE_CHARS.iter().position(|i| *i == s[2])? as u8,
+        })
+    }
+}
+
+impl fmt::Display for FirmwareVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let release = RELEASE_CHARS
+            .get(self.release as usize)
+            .copied()
+            .unwrap_or(b'?');
+
+        write!(
+            f,
+            "{}{}{}",
+            (FIRST_YEAR + self.year) as char,
+            (b'A' + self.month) as char,
+            release as char
+        )
+    }
+}
@@ -0,0 +1,55 @@
+use super::Store;
+
+use serde_json::{json, Value};
+
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::PathBuf,
+};
+
+/// Keeps the history as json lines in history.jsonl
+pub struct JsonlStore {
+    dir: PathBuf,
+}
+
+impl JsonlStore {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    fn file(&self) -> PathBuf {
+        self.dir.join("history.jsonl")
+    }
+}
+
+impl Store for JsonlStore {
+    fn append(&self, time: u64, kind: &str, entry: &Value) -> Result<(), String> {
+        fs::create_dir_all(&self.dir).map_err(|e| e.to_string())?;
+
+        let line = json!({ "time": time, "kind": kind, "entry": entry }).to_string() + "\n";
+
+        let mut f = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.file())
+            .map_err(|e| e.to_string())?;
+        f.write_all(line.as_bytes()).map_err(|e| e.to_string())
+    }
+
+    fn read(&self, kind: &str) -> Result<Vec<Value>, String> {
+        let file = self.file();
+        if !file.exists() {
+            return Ok(Vec::new());
+        }
+
+        let content = fs::read_to_string(&file).map_err(|e| e.to_string())?;
+
+        Ok(content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+            .filter(|i| i["kind"] == kind)
+            .map(|mut i| i["entry"].take())
+            .collect())
+    }
+}
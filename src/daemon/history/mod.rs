@@ -0,0 +1,92 @@
+/*
+ * History store for sessions, disconnects and audited requests. Entries are
+ * kept as json lines by default. Builds with the `sqlite` feature can keep
+ * them in a SQLite database instead (`history_backend = 'sqlite'`).
+ */
+
+mod jsonl;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+use super::buds_config::get_home_dir;
+
+use async_std::{path::PathBuf, task};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+
+use std::{
+    sync::{Arc, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Storage backend of the history
+pub trait Store: Send + Sync {
+    /// Store an entry of the given kind
+    fn append(&self, time: u64, kind: &str, entry: &Value) -> Result<(), String>;
+
+    /// Returns all entries of the given kind, oldest first
+    fn read(&self, kind: &str) -> Result<Vec<Value>, String>;
+}
+
+static STORE: Mutex<Option<Arc<dyn Store>>> = Mutex::new(None);
+
+/// Select the backend (jsonl or sqlite). Without calling this, jsonl gets used
+pub fn init(backend: Option<&str>) -> Result<(), String> {
+    let dir: std::path::PathBuf = get_state_dir().ok_or("Can't find state directory")?.into();
+
+    let store: Arc<dyn Store> = match backend.unwrap_or("jsonl") {
+        "jsonl" => Arc::new(jsonl::JsonlStore::new(dir)),
+        #[cfg(feature = "sqlite")]
+        "sqlite" => Arc::new(sqlite::SqliteStore::open(dir)?),
+        backend => return Err(format!("Unsupported history backend '{}'", backend)),
+    };
+
+    if let Ok(mut current) = STORE.lock() {
+        *current = Some(store);
+    }
+
+    Ok(())
+}
+
+/// Append an entry of the given kind to the history store
+pub async fn append<T: Serialize>(kind: &str, entry: &T) -> Result<(), String> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let entry = serde_json::to_value(entry).map_err(|e| e.to_string())?;
+
+    let store = get_store()?;
+    let kind = kind.to_owned();
+    task::spawn_blocking(move || store.append(time, &kind, &entry)).await
+}
+
+/// Read all entries of the given kind, skipping ones which can't be parsed
+pub async fn read<T: DeserializeOwned>(kind: &str) -> Result<Vec<T>, String> {
+    let store = get_store()?;
+    let kind = kind.to_owned();
+    let entries = task::spawn_blocking(move || store.read(&kind)).await?;
+
+    Ok(entries
+        .into_iter()
+        .filter_map(|i| serde_json::from_value(i).ok())
+        .collect())
+}
+
+fn get_store() -> Result<Arc<dyn Store>, String> {
+    if let Some(store) = STORE.lock().ok().and_then(|i| i.clone()) {
+        return Ok(store);
+    }
+
+    let dir = get_state_dir().ok_or("Can't find state directory")?;
+    Ok(Arc::new(jsonl::JsonlStore::new(dir.into())))
+}
+
+/// Directory for data written by the daemon
+pub fn get_state_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_STATE_HOME")
+        .filter(|i| !i.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| get_home_dir().map(|i| i.join(".local").join("state")))
+        .map(|i| i.join("livebuds"))
+}
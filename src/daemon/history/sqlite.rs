@@ -0,0 +1,63 @@
+use super::Store;
+
+use rusqlite::{params, Connection};
+use serde_json::Value;
+
+use std::{fs, path::PathBuf, sync::Mutex};
+
+/// Keeps the history in history.sqlite. Entries are stored as json text,
+/// so they can be queried with json_extract
+pub struct SqliteStore {
+    connection: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    pub fn open(dir: PathBuf) -> Result<Self, String> {
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        let connection = Connection::open(dir.join("history.sqlite")).map_err(|e| e.to_string())?;
+        connection
+            .execute_batch(
+                "CREATE TABLE IF NOT EXISTS history (
+                    time INTEGER NOT NULL,
+                    kind TEXT NOT NULL,
+                    entry TEXT NOT NULL
+                );
+                CREATE INDEX IF NOT EXISTS history_kind ON history (kind, time);",
+            )
+            .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl Store for SqliteStore {
+    fn append(&self, time: u64, kind: &str, entry: &Value) -> Result<(), String> {
+        let connection = self.connection.lock().map_err(|e| e.to_string())?;
+        connection
+            .execute(
+                "INSERT INTO history (time, kind, entry) VALUES (?1, ?2, ?3)",
+                params![time as i64, kind, entry.to_string()],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn read(&self, kind: &str) -> Result<Vec<Value>, String> {
+        let connection = self.connection.lock().map_err(|e| e.to_string())?;
+        let mut statement = connection
+            .prepare("SELECT entry FROM history WHERE kind = ?1 ORDER BY time")
+            .map_err(|e| e.to_string())?;
+
+        let rows = statement
+            .query_map([kind], |row| row.get::<_, String>(0))
+            .map_err(|e| e.to_string())?;
+
+        Ok(rows
+            .filter_map(|i| i.ok())
+            .filter_map(|i| serde_json::from_str(&i).ok())
+            .collect())
+    }
+}
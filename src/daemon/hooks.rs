@@ -0,0 +1,289 @@
+/*
+ * Runs the commands configured as [[hooks]] on battery, wear and noise control
+ * changes, connects, disconnects and named events (eg. case_battery_low).
+ * Filters decide which changes count, a debounce window limits how often a
+ * hook runs. The environment of a command describes the device and the event.
+ */
+
+use super::bluetooth::rfcomm_connector::{ConnectionData, Subscriber};
+use super::buds_config::{Config, HookConfig};
+use super::buds_info::BudsInfoInner;
+use super::labels;
+
+use async_std::sync::{Arc, Mutex};
+use galaxy_buds_rs::message::bud_property::Placement;
+use log::{error, info};
+use serde_json::{json, Value};
+
+use std::{
+    collections::HashMap,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+// Threshold of low_battery hooks without battery_below
+const LOW_BATTERY: u8 = 20;
+
+/// A change a hook can react to
+#[derive(Debug, Clone, PartialEq)]
+enum Trigger {
+    // Lower battery level of both buds
+    Battery { old: u8, new: u8 },
+    // True if at least one bud is worn
+    Wear(bool),
+    // Key of the new noise control mode, eg. 'anc'
+    NoiseControl(String),
+    Connect,
+    Disconnect,
+    // Name and data of a published event
+    Event(String, Value),
+}
+
+impl Trigger {
+    fn name(&self) -> &str {
+        match self {
+            Trigger::Battery { .. } => "battery",
+            Trigger::Wear(_) => "wear",
+            Trigger::NoiseControl(_) => "anc_changed",
+            Trigger::Connect => "connect",
+            Trigger::Disconnect => "disconnect",
+            Trigger::Event(name, _) => name,
+        }
+    }
+
+    // Returns true if a hook for `event` reacts to this trigger
+    fn is(&self, event: &str) -> bool {
+        match (event, self) {
+            ("connected", Trigger::Connect) | ("disconnected", Trigger::Disconnect) => true,
+            ("placed_in_ear", Trigger::Wear(worn)) => *worn,
+            ("removed_from_ear", Trigger::Wear(worn)) => !*worn,
+            ("low_battery", Trigger::Battery { .. }) => true,
+            _ => event == self.name(),
+        }
+    }
+
+    // Data of the trigger, like the data of published events
+    fn payload(&self) -> Value {
+        match self {
+            Trigger::Battery { old, new } => json!({ "old": old, "new": new }),
+            Trigger::Wear(worn) => json!({ "worn": worn }),
+            Trigger::NoiseControl(mode) => json!({ "mode": mode }),
+            Trigger::Connect | Trigger::Disconnect => json!({}),
+            Trigger::Event(_, data) => data.clone(),
+        }
+    }
+}
+
+/// Run hooks until the daemon exits
+pub async fn run(cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) {
+    let (subscriber, receiver) = Subscriber::new(None, true);
+    cd.lock().await.subscribers.push(subscriber);
+
+    let mut known: HashMap<String, BudsInfoInner> = HashMap::new();
+    // Time and battery of the last run by hook index and device
+    let mut last_runs: HashMap<(usize, String), Instant> = HashMap::new();
+    let mut battery_at_run: HashMap<(usize, String), u8> = HashMap::new();
+
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(_) => return,
+        };
+
+        let states: HashMap<String, BudsInfoInner> = cd
+            .lock()
+            .await
+            .data
+            .iter()
+            .map(|(addr, info)| (addr.clone(), info.inner.clone()))
+            .collect();
+
+        let mut triggers = changes(&known, &states);
+        if let Some(trigger) = named_event(&event) {
+            triggers.push(trigger);
+        }
+        // Disconnected devices are only described by their last state
+        let previous = std::mem::replace(&mut known, states);
+
+        let hooks = config.lock().await.hooks.clone().unwrap_or_default();
+
+        for (device, trigger) in triggers {
+            for (i, hook) in hooks.iter().enumerate() {
+                let key = (i, device.clone());
+                if !matches(hook, &device, &trigger, battery_at_run.get(&key).copied()) {
+                    continue;
+                }
+
+                // Debounce per device
+                let debounce = Duration::from_secs(hook.debounce.unwrap_or(0));
+                if last_runs
+                    .get(&key)
+                    .map_or(false, |i| i.elapsed() < debounce)
+                {
+                    continue;
+                }
+
+                last_runs.insert(key.clone(), Instant::now());
+                if let Trigger::Battery { new, .. } = trigger {
+                    battery_at_run.insert(key, new);
+                }
+
+                let info = known.get(&device).or_else(|| previous.get(&device));
+                execute(hook, &device, &trigger, info);
+            }
+        }
+    }
+}
+
+// Compare the known states of all devices with the current ones. Connects and
+// disconnects are published as events
+fn changes(
+    known: &HashMap<String, BudsInfoInner>,
+    states: &HashMap<String, BudsInfoInner>,
+) -> Vec<(String, Trigger)> {
+    let mut triggers = Vec::new();
+
+    for (address, new) in states {
+        let old = match known.get(address) {
+            Some(old) => old,
+            None => continue,
+        };
+
+        // Values are only known after the first extended status update
+        if !old.ready || !new.ready {
+            continue;
+        }
+
+        let (old_battery, new_battery) = (battery(old), battery(new));
+        if old_battery != new_battery {
+            triggers.push((
+                address.clone(),
+                Trigger::Battery {
+                    old: old_battery,
+                    new: new_battery,
+                },
+            ));
+        }
+
+        if is_worn(old) != is_worn(new) {
+            triggers.push((address.clone(), Trigger::Wear(is_worn(new))));
+        }
+
+        let (old_mode, new_mode) = (noise_control(old), noise_control(new));
+        if old_mode != new_mode {
+            triggers.push((address.clone(), Trigger::NoiseControl(new_mode)));
+        }
+    }
+
+    triggers
+}
+
+// Get the name of an event published by the daemon
+fn named_event(event: &str) -> Option<(String, Trigger)> {
+    let value: serde_json::Value = serde_json::from_str(event).ok()?;
+    let device = value.get("device")?.as_str()?.to_owned();
+    let name = value.get("event")?.as_str()?.to_owned();
+    let data = value.get("data").cloned().unwrap_or_else(|| json!({}));
+
+    let trigger = match name.as_str() {
+        "connected" => Trigger::Connect,
+        "disconnected" => Trigger::Disconnect,
+        _ => Trigger::Event(name, data),
+    };
+    Some((device, trigger))
+}
+
+// Check the filters of a hook
+fn matches(hook: &HookConfig, device: &str, trigger: &Trigger, battery_at_run: Option<u8>) -> bool {
+    if !trigger.is(&hook.event) {
+        return false;
+    }
+
+    if hook.device.as_ref().map_or(false, |i| i != device) {
+        return false;
+    }
+
+    match *trigger {
+        Trigger::Battery { old, new } => {
+            let below = match hook.battery_below {
+                None if hook.event == "low_battery" => Some(LOW_BATTERY),
+                below => below,
+            };
+
+            // Only run when crossing the threshold
+            if let Some(below) = below {
+                if old < below || new >= below {
+                    return false;
+                }
+            }
+
+            if let Some(delta) = hook.min_battery_delta {
+                let base = battery_at_run.unwrap_or(old);
+                if (base as i16 - new as i16).abs() < delta as i16 {
+                    return false;
+                }
+            }
+        }
+
+        Trigger::Wear(worn) => {
+            if let Some(ref wear) = hook.wear {
+                if (wear == "on") != worn {
+                    return false;
+                }
+            }
+        }
+
+        _ => (),
+    }
+
+    true
+}
+
+// Run the command of a hook without blocking other hooks
+fn execute(hook: &HookConfig, device: &str, trigger: &Trigger, info: Option<&BudsInfoInner>) {
+    info!("Running hook for {} of {}", hook.event, device);
+
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(&hook.command)
+        .env("EARBUDS_DEVICE", device)
+        .env("EARBUDS_EVENT", &hook.event)
+        .env("EARBUDS_PAYLOAD", trigger.payload().to_string());
+    if let Trigger::Battery { new, .. } = trigger {
+        command.env("EARBUDS_BATTERY", new.to_string());
+    }
+
+    // Values are only known after the first extended status update
+    if let Some(info) = info.filter(|i| i.ready) {
+        command
+            .env("EARBUDS_MODEL", &info.model_name)
+            .env(
+                "EARBUDS_NAME",
+                info.alias.as_deref().unwrap_or(&info.model_name),
+            )
+            .env("EARBUDS_BATTERY_LEFT", info.batt_left.to_string())
+            .env("EARBUDS_BATTERY_RIGHT", info.batt_right.to_string())
+            .env("EARBUDS_BATTERY_CASE", info.batt_case.to_string())
+            .env("EARBUDS_NOISE_CONTROL", noise_control(info))
+            .env("EARBUDS_WORN", (is_worn(info) as u8).to_string());
+    }
+
+    async_std::task::spawn_blocking(move || match command.status() {
+        Ok(status) if !status.success() => error!("Hook exited with {}", status),
+        Err(err) => error!("Can't run hook: {}", err),
+        _ => (),
+    });
+}
+
+fn battery(info: &BudsInfoInner) -> u8 {
+    info.batt_left.min(info.batt_right).max(0) as u8
+}
+
+fn noise_control(info: &BudsInfoInner) -> String {
+    labels::get(info, Some("en")).noise_control.key
+}
+
+fn is_worn(info: &BudsInfoInner) -> bool {
+    info.placement_left == Placement::Ear || info.placement_right == Placement::Ear
+}
@@ -0,0 +1,539 @@
+/*
+ * Minimal HTTP endpoint shaped for Home Assistant's RESTful sensor and switch
+ * integrations. Disabled unless `http_address` is configured, which has to be
+ * a loopback address unless pairing and TLS are enabled.
+ *
+ *   GET  /api/devices                   all connected devices
+ *   GET  /api/devices/<address>         a single device
+ *   GET  /api/devices/<address>/<key>   {"state": "on"|"off"} of a boolean setting
+ *   POST /api/devices/<address>/<key>   body "on" or "off"
+ *   GET  /api/events?timeout=<secs>     waits for the next event (204 on timeout)
+ *   POST /api/pair                      body: one-time code, returns {"token": ".."}
+ *
+ * Requests need an `Authorization: Bearer <token>` header unless `http_pairing`
+ * is disabled. Requests of web pages (with an `Origin` header) are refused, so a
+ * page can't control the buds through a "simple" cross-origin POST. Set `http_tls_cert` and `http_tls_key` to serve HTTPS.
+ */
+
+use super::bluetooth::rfcomm_connector::{ConnectionData, Subscriber};
+use super::buds_config::Config;
+use super::buds_info::BudsInfoInner;
+use super::pairing;
+use super::parse;
+use super::settings::{self, Value, ValueType};
+use super::unix_socket::{audit, Request};
+
+use async_std::{
+    io::{prelude::*, BufReader, Read, Write},
+    net::TcpListener,
+    sync::{Arc, Mutex},
+    task,
+};
+use galaxy_buds_rs::message::bud_property::Placement;
+use log::{error, info};
+use serde::Serialize;
+use serde_json::json;
+
+use std::{net::IpAddr, time::Duration};
+
+const DEFAULT_EVENT_TIMEOUT: u64 = 30;
+const MAX_EVENT_TIMEOUT: u64 = 300;
+const MAX_BODY_SIZE: usize = 1024;
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+// Time a client gets for the TLS handshake and to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// State of a device as returned by the endpoint
+#[derive(Debug, Serialize)]
+struct DeviceState {
+    address: String,
+    model: String,
+    ready: bool,
+    battery_left: i8,
+    battery_right: i8,
+    battery_case: i8,
+    wearing_left: bool,
+    wearing_right: bool,
+    noise_reduction: bool,
+    ambient_sound: bool,
+    equalizer: String,
+}
+
+impl DeviceState {
+    fn new(info: &BudsInfoInner) -> Self {
+        Self {
+            address: info.address.clone(),
+            model: format!("{:?}", info.model),
+            ready: info.ready,
+            battery_left: info.batt_left,
+            battery_right: info.batt_right,
+            battery_case: info.batt_case,
+            wearing_left: info.placement_left == Placement::Ear,
+            wearing_right: info.placement_right == Placement::Ear,
+            noise_reduction: info.noise_reduction,
+            ambient_sound: info.ambient_sound_enabled,
+            equalizer: format!("{:?}", info.equalizer_type),
+        }
+    }
+}
+
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: String,
+    body: String,
+    // Token of the Authorization header
+    token: Option<String>,
+    // Sent by browsers
+    origin: bool,
+    host: Option<String>,
+}
+
+/// Serve requests on the given address
+pub async fn run(address: String, cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) {
+    let (require_token, tls_files) = {
+        let config = config.lock().await;
+        let tls_files = config
+            .http_tls_cert
+            .clone()
+            .zip(config.http_tls_key.clone());
+        (config.http_pairing(), tls_files)
+    };
+
+    #[cfg(feature = "tls")]
+    let tls = match tls_files {
+        Some((cert, key)) => match tls_acceptor(&cert, &key) {
+            Ok(acceptor) => Some(acceptor),
+            Err(err) => {
+                error!("Can't set up TLS: {}", err);
+                return;
+            }
+        },
+        None => None,
+    };
+
+    #[cfg(not(feature = "tls"))]
+    if tls_files.is_some() {
+        error!("Can't serve HTTPS, earbuds was built without the tls feature");
+        return;
+    }
+
+    let listener = match TcpListener::bind(&address).await {
+        Ok(l) => l,
+        Err(err) => {
+            error!("Can't listen on {}: {}", address, err);
+            return;
+        }
+    };
+
+    // Other machines may only connect with a token and encryption
+    let remote_allowed = require_token && tls_files.is_some();
+    match listener.local_addr() {
+        Ok(local) if local.ip().is_loopback() || remote_allowed => (),
+        _ => {
+            error!(
+                "Refusing to serve HTTP on {}. Use a loopback address or enable http_pairing and TLS",
+                address
+            );
+            return;
+        }
+    }
+    info!("HTTP endpoint listening on {}", address);
+
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                error!("HTTP accept failed: {}", err);
+                continue;
+            }
+        };
+
+        #[cfg(feature = "tls")]
+        if let Some(ref acceptor) = tls {
+            let acceptor = acceptor.clone();
+            let cd = Arc::clone(&cd);
+            let config = Arc::clone(&config);
+            task::spawn(async move {
+                match async_std::future::timeout(READ_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => handle_client(stream, cd, config, require_token).await,
+                    Ok(Err(err)) => error!("TLS handshake failed: {}", err),
+                    Err(_) => error!("TLS handshake timed out"),
+                }
+            });
+            continue;
+        }
+
+        task::spawn(handle_client(
+            stream,
+            Arc::clone(&cd),
+            Arc::clone(&config),
+            require_token,
+        ));
+    }
+}
+
+// Load the certificate chain and the private key
+#[cfg(feature = "tls")]
+fn tls_acceptor(cert: &str, key: &str) -> Result<futures_rustls::TlsAcceptor, String> {
+    use futures_rustls::rustls::{Certificate, PrivateKey, ServerConfig};
+    use rustls_pemfile::Item;
+
+    let open = |path: &str| {
+        std::fs::File::open(path)
+            .map(std::io::BufReader::new)
+            .map_err(|e| format!("Can't open {}: {}", path, e))
+    };
+
+    let certs = rustls_pemfile::certs(&mut open(cert)?)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(Certificate)
+        .collect();
+
+    let key = rustls_pemfile::read_all(&mut open(key)?)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find_map(|i| match i {
+            Item::RSAKey(k) | Item::PKCS8Key(k) | Item::ECKey(k) => Some(PrivateKey(k)),
+            _ => None,
+        })
+        .ok_or("No private key found")?;
+
+    let config = ServerConfig::builder()
+        .with_safe_defaults()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| e.to_string())?;
+
+    Ok(Arc::new(config).into())
+}
+
+async fn handle_client<S>(
+    stream: S,
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+    require_token: bool,
+) where
+    S: Read + Write + Unpin,
+{
+    let mut reader = BufReader::new(stream);
+
+    let request = async_std::future::timeout(READ_TIMEOUT, read_request(&mut reader))
+        .await
+        .unwrap_or_else(|_| Err("Timed out reading the request".to_owned()));
+
+    let (status, body) = match request {
+        Ok(request) => {
+            let pairs = request.method == "POST" && request.path.trim_matches('/') == "api/pair";
+            if let Some(err) = refuse(&request, require_token) {
+                (403, json!({ "error": err }))
+            } else if require_token
+                && !pairs
+                && !pairing::is_authorized(request.token.as_deref()).await
+            {
+                (
+                    401,
+                    json!({ "error": "Unauthorized. Pair with `earbuds remote pair` first" }),
+                )
+            } else {
+                route(request, cd, config).await
+            }
+        }
+        Err(err) => (400, json!({ "error": err })),
+    };
+
+    let reason = match status {
+        200 => "OK",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Error",
+    };
+    let body = if status == 204 {
+        String::new()
+    } else {
+        body.to_string()
+    };
+
+    let response = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason,
+        body.len(),
+        body
+    );
+
+    let stream = reader.get_mut();
+    stream.write_all(response.as_bytes()).await.ok();
+    stream.flush().await.ok();
+}
+
+// Parse the request line, headers and body
+async fn read_request<S: Read + Unpin>(reader: &mut BufReader<S>) -> Result<HttpRequest, String> {
+    let line = read_line(reader).await?;
+    let mut parts = line.split_whitespace();
+    let method = parts.next().ok_or("Invalid request")?.to_owned();
+    let target = parts.next().ok_or("Invalid request")?;
+    let (path, query) = match target.split_once('?') {
+        Some((p, q)) => (p.to_owned(), q.to_owned()),
+        None => (target.to_owned(), String::new()),
+    };
+
+    let mut content_length = 0;
+    let mut token = None;
+    let mut origin = false;
+    let mut host = None;
+    for i in 0.. {
+        if i >= MAX_HEADERS {
+            return Err("Too many headers".to_owned());
+        }
+
+        let header = read_line(reader).await?;
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().map_err(|_| "Invalid length")?;
+            }
+
+            if name.trim().eq_ignore_ascii_case("origin") {
+                origin = true;
+            }
+
+            if name.trim().eq_ignore_ascii_case("host") {
+                host = Some(value.trim().to_owned());
+            }
+
+            if name.trim().eq_ignore_ascii_case("authorization") {
+                token = value
+                    .trim()
+                    .strip_prefix("Bearer ")
+                    .map(|i| i.trim().to_owned());
+            }
+        }
+    }
+
+    if content_length > MAX_BODY_SIZE {
+        return Err("Body too large".to_owned());
+    }
+
+    let mut body = vec![0u8; content_length];
+    reader
+        .read_exact(&mut body)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(HttpRequest {
+        method,
+        path,
+        query,
+        body: String::from_utf8_lossy(&body).into_owned(),
+        token,
+        origin,
+        host,
+    })
+}
+
+// Reason to refuse a request of a web page
+fn refuse(request: &HttpRequest, require_token: bool) -> Option<&'static str> {
+    if request.origin {
+        return Some("Requests of web pages are refused");
+    }
+
+    // Pages could reach a loopback address through a rebound domain
+    let loopback = request.host.as_deref().map_or(false, is_loopback_host);
+    if !require_token && !loopback {
+        return Some("Unknown host");
+    }
+
+    None
+}
+
+// Whether the Host header names this machine, eg. "localhost:8787" or "[::1]:8787"
+fn is_loopback_host(host: &str) -> bool {
+    let name = match host.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => host.split(':').next().unwrap_or_default(),
+    };
+
+    name.eq_ignore_ascii_case("localhost")
+        || name
+            .parse::<IpAddr>()
+            .map(|i| i.is_loopback())
+            .unwrap_or(false)
+}
+
+// Read a line of at most MAX_LINE_LENGTH bytes. Empty at the end of the stream
+async fn read_line<S: Read + Unpin>(reader: &mut BufReader<S>) -> Result<String, String> {
+    let mut line = String::new();
+    let len = reader
+        .take(MAX_LINE_LENGTH)
+        .read_line(&mut line)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if len as u64 == MAX_LINE_LENGTH && !line.ends_with('\n') {
+        return Err("Line too long".to_owned());
+    }
+
+    Ok(line)
+}
+
+async fn route(
+    request: HttpRequest,
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+) -> (u16, serde_json::Value) {
+    let segments: Vec<&str> = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .filter(|i| !i.is_empty())
+        .collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["api", "devices"]) => {
+            let cd = cd.lock().await;
+            let devices: Vec<DeviceState> = cd
+                .data
+                .values()
+                .map(|i| DeviceState::new(&i.inner))
+                .collect();
+            (200, json!(devices))
+        }
+
+        ("GET", ["api", "devices", address]) => match cd.lock().await.data.get(*address) {
+            Some(info) => (200, json!(DeviceState::new(&info.inner))),
+            None => not_found(),
+        },
+
+        ("GET", ["api", "devices", address, key]) => {
+            let cd = cd.lock().await;
+            let info = match cd.data.get(*address) {
+                Some(info) => info,
+                None => return not_found(),
+            };
+
+            match get_switch(&info.inner, key) {
+                Ok(state) => (200, json!({ "state": on_off(state) })),
+                Err(err) => (400, json!({ "error": err })),
+            }
+        }
+
+        ("POST", ["api", "devices", address, key]) => {
+            let value = match parse_switch_body(&request.body) {
+                Some(v) => v,
+                None => return (400, json!({ "error": "Body has to be 'on' or 'off'" })),
+            };
+
+            let res = {
+                let mut cd = cd.lock().await;
+                let info = match cd.data.get_mut(*address) {
+                    Some(info) => info,
+                    None => return not_found(),
+                };
+
+                if let Err(err) = get_switch(&info.inner, key) {
+                    return (400, json!({ "error": err }));
+                }
+
+                settings::set(info, key, &value.to_string(), None, false).await
+            };
+
+            let mut audited = Request::new("set_value".to_owned(), Some(address.to_string()));
+            audited.opt_param1 = Some(key.to_string());
+            audited.opt_param2 = Some(value.to_string());
+            let result = res.as_ref().map(|_| ()).map_err(|e| e.as_str());
+            audit::audit_result(&config, &audited, result).await;
+
+            match res {
+                Ok(_) => (200, json!({ "state": on_off(value) })),
+                Err(err) => (400, json!({ "error": err })),
+            }
+        }
+
+        ("GET", ["api", "events"]) => wait_for_event(&request.query, cd).await,
+
+        ("POST", ["api", "pair"]) => {
+            // Accept the plain code or {"code": ".."}
+            let body = request.body.trim();
+            let code = serde_json::from_str::<serde_json::Value>(body)
+                .ok()
+                .and_then(|i| i.get("code").and_then(|c| c.as_str().map(|c| c.to_owned())))
+                .unwrap_or_else(|| body.to_owned());
+
+            match pairing::pair(&code).await {
+                Ok(token) => (200, json!({ "token": token })),
+                Err(err) => (401, json!({ "error": err })),
+            }
+        }
+
+        (_, ["api", ..]) => (405, json!({ "error": "Method not allowed" })),
+        _ => not_found(),
+    }
+}
+
+// Long-poll the next event of any device
+async fn wait_for_event(query: &str, cd: Arc<Mutex<ConnectionData>>) -> (u16, serde_json::Value) {
+    let timeout = query
+        .split('&')
+        .find_map(|i| i.strip_prefix("timeout="))
+        .and_then(|i| i.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_EVENT_TIMEOUT)
+        .min(MAX_EVENT_TIMEOUT);
+
+    let (subscriber, receiver) = Subscriber::new(None, false);
+    cd.lock().await.subscribers.push(subscriber);
+
+    match async_std::future::timeout(Duration::from_secs(timeout), receiver.recv()).await {
+        Ok(Ok(event)) => (200, serde_json::from_str(&event).unwrap_or_default()),
+        _ => (204, serde_json::Value::Null),
+    }
+}
+
+// Current state of a boolean setting
+fn get_switch(info: &BudsInfoInner, key: &str) -> Result<bool, String> {
+    let setting = settings::find(key).ok_or("Unknown setting")?;
+    if setting.value_type() != ValueType::Bool {
+        return Err("Not a boolean setting".to_owned());
+    }
+
+    match settings::get(info, key, None)? {
+        Value::Bool(v) => Ok(v),
+        Value::Number(v) => Ok(v != 0),
+    }
+}
+
+// Accepts on/off, true/false etc. and the same as json {"state": ..}
+fn parse_switch_body(body: &str) -> Option<bool> {
+    let body = body.trim();
+    let state = serde_json::from_str::<serde_json::Value>(body)
+        .ok()
+        .and_then(|i| {
+            i.get("state")
+                .and_then(|s| s.as_str().map(|s| s.to_owned()))
+        })
+        .unwrap_or_else(|| body.to_owned());
+
+    parse::bool(&state).ok()
+}
+
+fn on_off(state: bool) -> &'static str {
+    if state {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn not_found() -> (u16, serde_json::Value) {
+    (404, json!({ "error": "Not found" }))
+}
@@ -0,0 +1,131 @@
+use super::pactl;
+
+use log::{info, warn};
+use mpris::PlayerFinder;
+use serde::{Deserialize, Serialize};
+
+use std::{env, path::Path};
+
+/// State of an optional desktop integration the daemon relies on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Integration {
+    pub name: String,
+    pub available: bool,
+    // Version, player names or why it's unavailable
+    pub detail: String,
+    // What doesn't work without it
+    pub affects: String,
+}
+
+/// Probe the optional integrations. Blocks while talking to D-Bus and pactl
+pub fn check() -> Vec<Integration> {
+    let session_bus = has_session_bus();
+
+    vec![
+        entry(
+            "dbus_session",
+            if session_bus {
+                Ok("reachable".to_owned())
+            } else {
+                Err("neither DBUS_SESSION_BUS_ADDRESS nor $XDG_RUNTIME_DIR/bus found".to_owned())
+            },
+            "notifications, media control, screen lock actions and the D-Bus API",
+        ),
+        entry(
+            "notifications",
+            notification_server(session_bus),
+            "battery and connection notifications",
+        ),
+        entry(
+            "mpris",
+            media_players(session_bus),
+            "pausing and resuming music when taking out the buds",
+        ),
+        entry(
+            "audio_server",
+            audio_server(),
+            "smart sink, muting on removal and the input stream event",
+        ),
+    ]
+}
+
+/// Log which features are degraded, so a missing integration doesn't go unnoticed
+pub fn log_degraded() {
+    for integration in check() {
+        if integration.available {
+            info!("{}: {}", integration.name, integration.detail);
+        } else {
+            warn!(
+                "{} unavailable ({}). Degraded: {}",
+                integration.name, integration.detail, integration.affects
+            );
+        }
+    }
+}
+
+fn entry(name: &str, state: Result<String, String>, affects: &str) -> Integration {
+    let (available, detail) = match state {
+        Ok(detail) => (true, detail),
+        Err(detail) => (false, detail),
+    };
+
+    Integration {
+        name: name.to_owned(),
+        available,
+        detail,
+        affects: affects.to_owned(),
+    }
+}
+
+// The systemd user session provides the bus at a default path
+fn has_session_bus() -> bool {
+    if env::var_os("DBUS_SESSION_BUS_ADDRESS").map_or(false, |i| !i.is_empty()) {
+        return true;
+    }
+
+    env::var_os("XDG_RUNTIME_DIR")
+        .map(|dir| Path::new(&dir).join("bus").exists())
+        .unwrap_or(false)
+}
+
+fn notification_server(session_bus: bool) -> Result<String, String> {
+    if !session_bus {
+        return Err("no D-Bus session".to_owned());
+    }
+
+    notify_rust::get_server_information()
+        .map(|i| format!("{} {}", i.name, i.version))
+        .map_err(|_| "no notification daemon running".to_owned())
+}
+
+// No running player isn't a problem, they get looked up on each use
+fn media_players(session_bus: bool) -> Result<String, String> {
+    if !session_bus {
+        return Err("no D-Bus session".to_owned());
+    }
+
+    let finder = PlayerFinder::new().map_err(|e| e.to_string())?;
+    let players: Vec<String> = finder
+        .find_all()
+        .unwrap_or_default()
+        .iter()
+        .map(|i| i.identity().to_owned())
+        .collect();
+
+    if players.is_empty() {
+        Ok("no player running".to_owned())
+    } else {
+        Ok(players.join(", "))
+    }
+}
+
+// pactl works with pulseaudio and pipewire-pulse
+fn audio_server() -> Result<String, String> {
+    let output = pactl::pactl(&["info"]).map_err(|e| format!("pactl failed: {}", e))?;
+
+    Ok(output
+        .lines()
+        .find_map(|i| i.strip_prefix("Server Name:"))
+        .map(|i| i.trim().to_owned())
+        .unwrap_or_else(|| "unknown server".to_owned()))
+}
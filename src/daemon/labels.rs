@@ -0,0 +1,139 @@
+/*
+ * Stable keys and translated display names of enum values in the status, so
+ * clients don't need their own mapping. The language is taken from the request
+ * or the locale of the daemon.
+ */
+
+use super::buds_info::BudsInfoInner;
+
+use galaxy_buds_rs::message::bud_property::EqualizerType;
+use serde::{Deserialize, Serialize};
+
+const LANGUAGES: [&str; 4] = ["en", "de", "fr", "es"];
+
+/// A value as machine readable key and display string
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Label {
+    pub key: String,
+    pub display: String,
+}
+
+/// Labels of the status values
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Labels {
+    pub language: String,
+    pub equalizer: Label,
+    pub noise_control: Label,
+}
+
+/// Get the labels of a status in the given language, or the language of the daemon
+pub fn get(info: &BudsInfoInner, language: Option<&str>) -> Labels {
+    let language = language
+        .and_then(find_language)
+        .or_else(system_language)
+        .unwrap_or("en");
+
+    let equalizer = equalizer_key(info.equalizer_type);
+    let noise_control = if info.noise_reduction {
+        "anc"
+    } else if info.ambient_sound_enabled {
+        "ambient"
+    } else {
+        "off"
+    };
+
+    Labels {
+        language: language.to_owned(),
+        equalizer: label(equalizer, language),
+        noise_control: label(noise_control, language),
+    }
+}
+
+fn label(key: &str, language: &str) -> Label {
+    Label {
+        key: key.to_owned(),
+        display: translate(key, language).to_owned(),
+    }
+}
+
+/// Returns the key of an equalizer preset, eg. 'bass_boost'
+pub fn equalizer_key(equalizer: EqualizerType) -> &'static str {
+    match equalizer {
+        EqualizerType::Normal => "normal",
+        EqualizerType::BassBoost => "bass_boost",
+        EqualizerType::Soft => "soft",
+        EqualizerType::Dynamic => "dynamic",
+        EqualizerType::Clear => "clear",
+        EqualizerType::TrebleBoost => "treble_boost",
+        _ => "unknown",
+    }
+}
+
+// Match a locale like de_DE.UTF-8 or fr-CA to a supported language
+fn find_language(locale: &str) -> Option<&'static str> {
+    let language = locale
+        .split(|c| c == '_' || c == '-' || c == '.')
+        .next()?
+        .to_lowercase();
+    LANGUAGES.iter().copied().find(|i| *i == language)
+}
+
+// Same order as gettext
+fn system_language() -> Option<&'static str> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|i| std::env::var(i).ok())
+        .find(|i| !i.is_empty())
+        .and_then(|i| find_language(&i))
+}
+
+fn translate(key: &str, language: &str) -> &'static str {
+    match (key, language) {
+        ("normal", _) => "Normal",
+
+        ("bass_boost", "de") => "Bassverstärkung",
+        ("bass_boost", "fr") => "Renforcement des basses",
+        ("bass_boost", "es") => "Refuerzo de graves",
+        ("bass_boost", _) => "Bass boost",
+
+        ("soft", "de") => "Weich",
+        ("soft", "fr") => "Doux",
+        ("soft", "es") => "Suave",
+        ("soft", _) => "Soft",
+
+        ("dynamic", "de") => "Dynamisch",
+        ("dynamic", "fr") => "Dynamique",
+        ("dynamic", "es") => "Dinámico",
+        ("dynamic", _) => "Dynamic",
+
+        ("clear", "de") => "Klar",
+        ("clear", "fr") => "Clair",
+        ("clear", "es") => "Claro",
+        ("clear", _) => "Clear",
+
+        ("treble_boost", "de") => "Höhenverstärkung",
+        ("treble_boost", "fr") => "Renforcement des aigus",
+        ("treble_boost", "es") => "Refuerzo de agudos",
+        ("treble_boost", _) => "Treble boost",
+
+        ("anc", "de") => "Aktive Geräuschunterdrückung",
+        ("anc", "fr") => "Réduction active du bruit",
+        ("anc", "es") => "Cancelación activa de ruido",
+        ("anc", _) => "Active noise cancelling",
+
+        ("ambient", "de") => "Umgebungsgeräusche",
+        ("ambient", "fr") => "Son ambiant",
+        ("ambient", "es") => "Sonido ambiente",
+        ("ambient", _) => "Ambient sound",
+
+        ("off", "de") => "Aus",
+        ("off", "fr") => "Désactivé",
+        ("off", "es") => "Desactivado",
+        ("off", _) => "Off",
+
+        (_, "de") => "Unbekannt",
+        (_, "fr") => "Inconnu",
+        (_, "es") => "Desconocido",
+        _ => "Unknown",
+    }
+}
@@ -0,0 +1,154 @@
+/*
+ * Buds which lose the connection while being worn are rather out of range
+ * than docked. Such disconnects get recorded as "possibly lost" together with
+ * the last known event. Devices with `chirp_when_lost` start chirping once
+ * they reconnect, to find them quickly.
+ */
+
+use super::bluetooth::rfcomm_connector::ConnectionData;
+use super::buds_config::Config;
+use super::buds_info::BudsInfo;
+use super::raw_message::{self, RawMessage};
+use super::{history, status_diff};
+
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use galaxy_buds_rs::message::bud_property::Placement;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// Stop chirping after this time unless the buds disconnect before
+const CHIRP_DURATION: Duration = Duration::from_secs(20);
+
+/// A device which lost the connection while being worn
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LostEntry {
+    pub device: String,
+    pub time: u64,
+    pub battery_left: i8,
+    pub battery_right: i8,
+    // Last event published for the device
+    pub last_event: Option<serde_json::Value>,
+    // Time the device reconnected afterwards
+    #[serde(default)]
+    pub found_at: Option<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct FoundEntry {
+    device: String,
+    time: u64,
+}
+
+/// Record a lost connection if the device was worn
+pub async fn record(info: &BudsInfo) {
+    if info.inner.placement_left != Placement::Ear && info.inner.placement_right != Placement::Ear {
+        return;
+    }
+
+    let entry = LostEntry {
+        device: info.inner.address.clone(),
+        time: now(),
+        battery_left: info.inner.batt_left,
+        battery_right: info.inner.batt_right,
+        last_event: info
+            .last_event
+            .as_deref()
+            .and_then(|i| serde_json::from_str(i).ok()),
+        found_at: None,
+    };
+
+    if let Err(err) = history::append("lost", &entry).await {
+        eprintln!("Can't write lost device to history: {}", err);
+    }
+}
+
+/// Returns the recorded lost devices, newest first
+pub async fn list() -> Result<Vec<LostEntry>, String> {
+    let found = history::read::<FoundEntry>("found").await?;
+    let mut entries = history::read::<LostEntry>("lost").await?;
+
+    for entry in entries.iter_mut() {
+        entry.found_at = found
+            .iter()
+            .filter(|i| i.device == entry.device && i.time >= entry.time)
+            .map(|i| i.time)
+            .min();
+    }
+
+    entries.reverse();
+    Ok(entries)
+}
+
+/// Mark a lost device as found once it's ready again. Armed devices start chirping
+pub async fn handle_reconnect(
+    info: &mut BudsInfo,
+    cd: &Arc<Mutex<ConnectionData>>,
+    config: &Arc<Mutex<Config>>,
+) {
+    let lost = match list().await {
+        Ok(entries) => entries
+            .iter()
+            .any(|i| i.device == info.inner.address && i.found_at.is_none()),
+        Err(err) => {
+            eprintln!("Can't read lost devices: {}", err);
+            return;
+        }
+    };
+    if !lost {
+        return;
+    }
+
+    let entry = FoundEntry {
+        device: info.inner.address.clone(),
+        time: now(),
+    };
+    if let Err(err) = history::append("found", &entry).await {
+        eprintln!("Can't write found device to history: {}", err);
+    }
+
+    let armed = config
+        .lock()
+        .await
+        .get_device_config(&info.inner.address)
+        .map(|i| i.chirp_when_lost())
+        .unwrap_or(false);
+
+    info.events.push(status_diff::named_event(
+        &info.inner.address,
+        "lost_device_found",
+        json!({ "chirping": armed }),
+    ));
+
+    if !armed {
+        return;
+    }
+
+    let start = RawMessage::new(raw_message::ids::FIND_MY_EARBUDS_START, Vec::new());
+    if let Err(err) = info.send_raw(start).await {
+        eprintln!("Can't start chirping: {}", err);
+        return;
+    }
+
+    let cd = Arc::clone(cd);
+    let address = info.inner.address.clone();
+    task::spawn(async move {
+        task::sleep(CHIRP_DURATION).await;
+
+        let stop = RawMessage::new(raw_message::ids::FIND_MY_EARBUDS_STOP, Vec::new());
+        if let Some(info) = cd.lock().await.get_device_mut(&address) {
+            info.send_raw(stop).await.ok();
+        }
+    });
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,84 @@
+/*
+ * Pauses and resumes media when taking out and putting on the buds. The mpris
+ * backend controls the active player (eg. Spotify, Firefox or mpv) through
+ * org.mpris.MediaPlayer2, the pulseaudio backend mutes the default sink for
+ * setups without MPRIS players.
+ */
+
+use super::buds_config::MediaControlBackend;
+use super::sink_mute;
+
+use mpris::{Player, PlayerFinder};
+use serde::{Deserialize, Serialize};
+
+/// A player paused when removing the buds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PausedPlayer {
+    pub bus_name: String,
+    pub identity: String,
+}
+
+fn get_finder() -> Option<PlayerFinder> {
+    PlayerFinder::new().ok()
+}
+
+fn get_player(finder: &PlayerFinder) -> Option<Player> {
+    finder.find_active().ok()
+}
+
+pub fn try_pause() -> bool {
+    get_finder()
+        .and_then(|finder| get_player(&finder).and_then(|player| player.pause().ok()))
+        .is_some()
+}
+
+pub fn try_play() -> bool {
+    get_finder()
+        .and_then(|finder| get_player(&finder).and_then(|player| player.play().ok()))
+        .is_some()
+}
+
+/// Pause the active player. Returns it if it got paused
+pub fn pause_active_player() -> Option<PausedPlayer> {
+    let finder = get_finder()?;
+    let player = get_player(&finder)?;
+    player.pause().ok()?;
+
+    Some(PausedPlayer {
+        bus_name: player.bus_name().to_owned(),
+        identity: player.identity().to_owned(),
+    })
+}
+
+/// Bus name of the active player
+pub fn active_player() -> Option<String> {
+    let finder = get_finder()?;
+    get_player(&finder).map(|player| player.bus_name().to_owned())
+}
+
+/// What got paused by `pause`
+pub enum Paused {
+    Player(PausedPlayer),
+    Sink,
+}
+
+/// Pause media with the given backend. Returns what got paused
+pub fn pause(backend: MediaControlBackend) -> Option<Paused> {
+    match backend {
+        MediaControlBackend::Mpris => pause_active_player().map(Paused::Player),
+        MediaControlBackend::Pulseaudio => {
+            sink_mute::mute();
+            Some(Paused::Sink)
+        }
+    }
+}
+
+/// Resume media paused by `pause`
+pub fn resume(backend: MediaControlBackend) {
+    match backend {
+        MediaControlBackend::Mpris => {
+            try_play();
+        }
+        MediaControlBackend::Pulseaudio => sink_mute::unmute(),
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ * Emits media key events through a virtual uinput keyboard, for applications
+ * which react to hardware keys but not to MPRIS. Requires write access to
+ * /dev/uinput (eg. by adding the user to the 'input' group).
+ */
+
+use nix::libc;
+
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    mem,
+    os::unix::io::AsRawFd,
+    sync::Mutex,
+};
+
+const EV_SYN: u16 = 0x00;
+const EV_KEY: u16 = 0x01;
+const SYN_REPORT: u16 = 0;
+const BUS_VIRTUAL: u16 = 0x06;
+
+const DEVICE_NAME: &[u8] = b"earbuds media keys";
+
+nix::ioctl_none!(ui_dev_create, b'U', 1);
+nix::ioctl_write_int!(ui_set_evbit, b'U', 100);
+nix::ioctl_write_int!(ui_set_keybit, b'U', 101);
+
+// Created on first use and kept until the daemon exits
+static DEVICE: Mutex<Option<File>> = Mutex::new(None);
+
+/// A media key which can be emitted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaKey {
+    PlayPause,
+    Next,
+    Previous,
+}
+
+impl MediaKey {
+    pub fn parse(s: &str) -> Option<MediaKey> {
+        Some(match s.to_lowercase().as_str() {
+            "playpause" | "play-pause" => MediaKey::PlayPause,
+            "next" => MediaKey::Next,
+            "previous" | "prev" => MediaKey::Previous,
+            _ => return None,
+        })
+    }
+
+    // Linux input event code
+    fn code(&self) -> u16 {
+        match self {
+            MediaKey::Next => 163,
+            MediaKey::PlayPause => 164,
+            MediaKey::Previous => 165,
+        }
+    }
+
+    fn all() -> [MediaKey; 3] {
+        [MediaKey::PlayPause, MediaKey::Next, MediaKey::Previous]
+    }
+}
+
+/// Press and release a media key
+pub fn press(key: MediaKey) -> Result<(), String> {
+    let mut device = DEVICE.lock().map_err(|e| e.to_string())?;
+
+    if device.is_none() {
+        *device = Some(create_device()?);
+    }
+    let file = device.as_mut().unwrap();
+
+    for value in [1, 0] {
+        write_event(file, EV_KEY, key.code(), value)?;
+        write_event(file, EV_SYN, SYN_REPORT, 0)?;
+    }
+
+    Ok(())
+}
+
+// Create the virtual keyboard
+fn create_device() -> Result<File, String> {
+    let file = OpenOptions::new()
+        .write(true)
+        .open("/dev/uinput")
+        .map_err(|e| format!("Can't open /dev/uinput: {}", e))?;
+    let fd = file.as_raw_fd();
+
+    // Safe since the ioctls only get plain integers
+    unsafe {
+        ui_set_evbit(fd, EV_KEY as libc::c_ulong).map_err(|e| e.to_string())?;
+        for key in MediaKey::all() {
+            ui_set_keybit(fd, key.code() as libc::c_ulong).map_err(|e| e.to_string())?;
+        }
+    }
+
+    // Describe the device using the legacy setup which works with all kernels
+    let mut setup: libc::uinput_user_dev = unsafe { mem::zeroed() };
+    for (dst, src) in setup.name.iter_mut().zip(DEVICE_NAME) {
+        *dst = *src as libc::c_char;
+    }
+    setup.id.bustype = BUS_VIRTUAL;
+    setup.id.version = 1;
+    (&file)
+        .write_all(as_bytes(&setup))
+        .map_err(|e| e.to_string())?;
+
+    unsafe { ui_dev_create(fd) }.map_err(|e| e.to_string())?;
+    Ok(file)
+}
+
+fn write_event(file: &mut File, type_: u16, code: u16, value: i32) -> Result<(), String> {
+    let event = libc::input_event {
+        time: libc::timeval {
+            tv_sec: 0,
+            tv_usec: 0,
+        },
+        type_,
+        code,
+        value,
+    };
+
+    file.write_all(as_bytes(&event)).map_err(|e| e.to_string())
+}
+
+// Raw bytes of a C struct
+fn as_bytes<T>(value: &T) -> &[u8] {
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, mem::size_of::<T>()) }
+}
@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "pulse-sink")]
+use pulsectl::controllers::{types::DeviceInfo, DeviceControl, SourceController};
+
+/// An audio input source provided by the buds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MicSource {
+    pub name: String,
+    pub description: String,
+    pub is_default: bool,
+}
+
+/// List all input sources belonging to the given device
+#[cfg(feature = "pulse-sink")]
+pub fn list_sources(address: &str) -> Result<Vec<MicSource>, String> {
+    let mut handler = SourceController::create().map_err(|e| format!("{:?}", e))?;
+
+    let default_name = handler.get_default_device().ok().and_then(|i| i.name);
+
+    Ok(get_bt_sources(&mut handler, address)?
+        .into_iter()
+        .filter_map(|i| {
+            let name = i.name?;
+            Some(MicSource {
+                is_default: Some(&name) == default_name.as_ref(),
+                description: i.description.unwrap_or_else(|| name.clone()),
+                name,
+            })
+        })
+        .collect())
+}
+
+/// Make the mic of the given device the default input source
+#[cfg(feature = "pulse-sink")]
+pub fn set_default(address: &str) -> Result<MicSource, String> {
+    let mut handler = SourceController::create().map_err(|e| format!("{:?}", e))?;
+
+    let source = get_bt_sources(&mut handler, address)?
+        .into_iter()
+        .find_map(|i| i.name.map(|name| (name, i.description)))
+        .ok_or("No input source found. Is a headset profile active?")?;
+
+    handler
+        .set_default_device(&source.0)
+        .map_err(|e| format!("{:?}", e))?;
+
+    Ok(MicSource {
+        description: source.1.unwrap_or_else(|| source.0.clone()),
+        name: source.0,
+        is_default: true,
+    })
+}
+
+// Returns the sources of a bluetooth device, leaving out monitors of its sinks
+#[cfg(feature = "pulse-sink")]
+fn get_bt_sources(
+    handler: &mut SourceController,
+    address: &str,
+) -> Result<Vec<DeviceInfo>, String> {
+    let devices = handler.list_devices().map_err(|e| format!("{:?}", e))?;
+
+    Ok(devices
+        .into_iter()
+        .filter(|i| {
+            // Pulseaudio and pipewire name the address differently
+            let matches_address = ["device.string", "api.bluez5.address"].iter().any(|key| {
+                i.proplist
+                    .get_str(key)
+                    .map(|a| a.eq_ignore_ascii_case(address))
+                    .unwrap_or(false)
+            });
+
+            let is_monitor = i
+                .name
+                .as_ref()
+                .map(|n| n.ends_with(".monitor"))
+                .unwrap_or(true);
+
+            matches_address && !is_monitor
+        })
+        .collect())
+}
+
+#[cfg(not(feature = "pulse-sink"))]
+pub fn list_sources(_address: &str) -> Result<Vec<MicSource>, String> {
+    Err("Compiled without the pulse-sink feature".to_owned())
+}
+
+#[cfg(not(feature = "pulse-sink"))]
+pub fn set_default(_address: &str) -> Result<MicSource, String> {
+    Err("Compiled without the pulse-sink feature".to_owned())
+}
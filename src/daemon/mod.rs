@@ -1,27 +1,105 @@
+#[cfg(feature = "dbus")]
+mod battery_provider;
 mod bluetooth;
 mod buds_config;
 pub mod buds_info;
+pub mod charging;
+mod config_migration;
+pub mod connection_history;
+mod crc;
+#[cfg(feature = "dbus")]
+mod dbus;
+// Partly only used by build.rs
+#[allow(dead_code)]
+pub mod dbus_api;
+pub mod diagnostics;
+pub mod firmware;
+pub mod history;
+mod hooks;
+mod http;
+pub mod integrations;
+pub mod labels;
+pub mod lost;
+mod media_control;
+pub mod media_keys;
+pub mod mic;
+pub mod models;
+mod multi_host;
+pub mod notifications;
+mod pactl;
+mod pairing;
+pub mod parse;
+mod polling;
+mod profile;
+pub mod raw_message;
+mod runtime_state;
+#[cfg(feature = "dbus")]
+mod screen_lock;
+mod seat_lock;
+pub mod session;
+pub mod settings;
+mod sink_mute;
+pub mod stats;
+pub mod status_diff;
+pub mod status_file;
+mod touchpad_schedule;
 pub mod unix_socket;
 pub mod utils;
 
-use async_std::sync::Mutex;
+use async_std::{
+    channel::{self, Receiver},
+    sync::Mutex,
+};
 use bluetooth::rfcomm_connector::ConnectionData;
+use log::{error, info};
+use nix::sys::signal::{SigSet, Signal};
 
 use std::{
     sync::{mpsc, Arc},
     thread,
+    time::{Duration, Instant},
 };
 
 use self::bluetooth::rfcomm_connector::ConnectionEventData;
 
-/// Starts the complete daemon
-pub async fn run_daemon(p: String) {
-    // Exchange connection events between bluetooth and connection handler
-    let (conn_tx, conn_rx) = mpsc::channel::<ConnectionEventData>();
+const CONNECTION_EVENT_CAPACITY: usize = 16;
+// Give up saving the runtime state if the connection data stays locked this long
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Block the termination signals for the calling thread and all threads spawned by it.
+/// Has to run before the async runtime starts its threads, so the signals only reach
+/// the thread waiting for them
+pub fn block_signals() -> Option<SigSet> {
+    let mut signals = SigSet::empty();
+    signals.add(Signal::SIGTERM);
+    signals.add(Signal::SIGINT);
+    signals.add(Signal::SIGHUP);
+    signals.add(Signal::SIGUSR1);
+
+    match signals.thread_block() {
+        Ok(()) => Some(signals),
+        Err(err) => {
+            eprintln!("Can't block signals: {:?}", err);
+            None
+        }
+    }
+}
 
+/// Starts the complete daemon. `signals` are the signals blocked by `block_signals`
+pub async fn run_daemon(p: String, signals: Option<SigSet>) {
     // Exchanging Buds data between unix socket and the buds listener
     let connection_data = Arc::new(Mutex::new(ConnectionData::new()));
 
+    let status_rewrite = handle_signals(signals, p.clone(), Arc::clone(&connection_data));
+    diagnostics::install_panic_hook();
+
+    // Pick up where the previous daemon left off
+    runtime_state::load();
+
+    // Exchange connection events between bluetooth and connection handler. The
+    // bluetooth listener waits while the connection handler is busy
+    let (conn_tx, conn_rx) = mpsc::sync_channel::<ConnectionEventData>(CONNECTION_EVENT_CAPACITY);
+
     // Config setup
     let config = Arc::new(Mutex::new(
         buds_config::Config::new()
@@ -29,6 +107,15 @@ pub async fn run_daemon(p: String) {
             .expect("Couldn't read config"),
     ));
 
+    // Select the history store
+    let history_backend = config.lock().await.history_backend.clone();
+    if let Err(err) = history::init(history_backend.as_deref()) {
+        error!("Can't open the history store: {}", err);
+    }
+
+    // Tell which desktop integrations are missing
+    async_std::task::spawn_blocking(integrations::log_degraded);
+
     // Run Unix socket listener
     async_std::task::spawn(unix_socket::socket::run(
         p,
@@ -36,6 +123,68 @@ pub async fn run_daemon(p: String) {
         Arc::clone(&config),
     ));
 
+    // Run the sockets of single devices
+    async_std::task::spawn(unix_socket::socket::run_device_sockets(
+        Arc::clone(&connection_data),
+        Arc::clone(&config),
+    ));
+
+    // Switch profiles during calls
+    async_std::task::spawn(profile::run(
+        Arc::clone(&connection_data),
+        Arc::clone(&config),
+    ));
+
+    // Lock touchpads as scheduled
+    async_std::task::spawn(touchpad_schedule::run(
+        Arc::clone(&connection_data),
+        Arc::clone(&config),
+    ));
+
+    // Keep the status snapshot file up to date
+    async_std::task::spawn(status_file::run(
+        Arc::clone(&connection_data),
+        Arc::clone(&config),
+        status_rewrite,
+    ));
+
+    // Notice when another host plays on the buds
+    async_std::task::spawn(multi_host::run(Arc::clone(&connection_data)));
+
+    // Run commands on events
+    async_std::task::spawn(hooks::run(
+        Arc::clone(&connection_data),
+        Arc::clone(&config),
+    ));
+
+    // Provide the D-Bus interface
+    #[cfg(feature = "dbus")]
+    async_std::task::spawn(dbus::run(Arc::clone(&connection_data), Arc::clone(&config)));
+
+    // Report the battery to UPower
+    #[cfg(feature = "dbus")]
+    async_std::task::spawn(battery_provider::run(
+        Arc::clone(&connection_data),
+        Arc::clone(&config),
+    ));
+
+    // Pause or switch to ambient sound while the session is locked
+    #[cfg(feature = "dbus")]
+    async_std::task::spawn(screen_lock::run(
+        Arc::clone(&connection_data),
+        Arc::clone(&config),
+    ));
+
+    // Serve the HTTP endpoint if configured
+    let http_address = config.lock().await.http_address.clone();
+    if let Some(address) = http_address {
+        async_std::task::spawn(http::run(
+            address,
+            Arc::clone(&connection_data),
+            Arc::clone(&config),
+        ));
+    }
+
     // Run connection handler
     async_std::task::spawn(bluetooth::rfcomm_connector::run(
         conn_rx,
@@ -53,3 +202,56 @@ pub async fn run_daemon(p: String) {
         .join()
         .expect("Thread spawning failed");
 }
+
+// Remove the socket file, save the runtime state and exit gracefully on termination
+// signals. SIGUSR1 gets forwarded to the returned receiver to rewrite the status file
+fn handle_signals(
+    signals: Option<SigSet>,
+    socket_path: String,
+    cd: Arc<Mutex<ConnectionData>>,
+) -> Receiver<()> {
+    let (rewrite_tx, rewrite_rx) = channel::bounded(1);
+
+    // Waiting only works for blocked signals
+    let signals = match signals {
+        Some(signals) => signals,
+        None => return rewrite_rx,
+    };
+
+    thread::spawn(move || {
+        while let Ok(signal) = signals.wait() {
+            // A pending rewrite covers this one as well
+            if signal == Signal::SIGUSR1 {
+                rewrite_tx.try_send(()).ok();
+                continue;
+            }
+
+            info!("Received {:?}. Shutting down", signal);
+            save_runtime_state(&cd);
+            std::fs::remove_file(&socket_path).ok();
+            unix_socket::socket::remove_device_sockets();
+            std::fs::remove_file(status_file::get_path()).ok();
+            std::process::exit(0);
+        }
+    });
+
+    rewrite_rx
+}
+
+// A task holding the lock (eg. while running pactl) must not keep the daemon from exiting
+fn save_runtime_state(cd: &Mutex<ConnectionData>) {
+    let start = Instant::now();
+
+    loop {
+        if let Some(lock) = cd.try_lock() {
+            runtime_state::save(lock.data.values());
+            return;
+        }
+
+        if start.elapsed() > SHUTDOWN_TIMEOUT {
+            error!("Connection data stayed locked, the runtime state isn't saved");
+            return;
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
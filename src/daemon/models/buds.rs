@@ -0,0 +1,24 @@
+use super::BudsModel;
+use galaxy_buds_rs::model::Model;
+
+/// The original Galaxy Buds
+pub struct Buds;
+
+impl BudsModel for Buds {
+    fn model(&self) -> Model {
+        Model::Buds
+    }
+
+    // Eg. "Galaxy Buds (A1B2)". Newer models contain "buds" as well
+    fn matches_name(&self, name: &str) -> bool {
+        name == "galaxy buds" || name.starts_with("galaxy buds (")
+    }
+
+    fn name(&self) -> &'static str {
+        "Galaxy Buds"
+    }
+
+    fn frame_markers(&self) -> (u8, u8) {
+        (0xFE, 0xEE)
+    }
+}
@@ -0,0 +1,43 @@
+use super::{BudsModel, UpdateMode};
+use galaxy_buds_rs::model::Model;
+
+/// Galaxy Buds 2
+pub struct Buds2;
+
+impl BudsModel for Buds2 {
+    fn model(&self) -> Model {
+        Model::Buds2
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        name.contains("buds2")
+    }
+
+    fn name(&self) -> &'static str {
+        "Galaxy Buds2"
+    }
+
+    fn update_mode(&self) -> UpdateMode {
+        UpdateMode::Push
+    }
+
+    fn supports_ambient_during_calls(&self) -> bool {
+        true
+    }
+
+    fn min_firmware(&self, setting: &str) -> Option<&'static str> {
+        match setting {
+            // Added with the november 2021 update
+            "ambient_during_calls" => Some("UK1"),
+            _ => None,
+        }
+    }
+
+    fn supports_noise_controls(&self) -> bool {
+        true
+    }
+
+    fn supports_fit_test(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,28 @@
+use super::{BudsModel, UpdateMode};
+use galaxy_buds_rs::model::Model;
+
+/// Galaxy Buds Live
+pub struct BudsLive;
+
+impl BudsModel for BudsLive {
+    fn model(&self) -> Model {
+        Model::BudsLive
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        name.contains("buds live")
+    }
+
+    fn name(&self) -> &'static str {
+        "Galaxy Buds Live"
+    }
+
+    fn update_mode(&self) -> UpdateMode {
+        UpdateMode::Push
+    }
+
+    // Ambient sound has no volume levels on the buds live
+    fn max_ambient_volume(&self, _extra_high: bool) -> u8 {
+        0
+    }
+}
@@ -0,0 +1,31 @@
+use super::BudsModel;
+use galaxy_buds_rs::model::Model;
+
+/// Galaxy Buds+
+pub struct BudsPlus;
+
+impl BudsModel for BudsPlus {
+    fn model(&self) -> Model {
+        Model::BudsPlus
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        name.contains("buds+")
+    }
+
+    fn name(&self) -> &'static str {
+        "Galaxy Buds+"
+    }
+
+    fn max_ambient_volume(&self, extra_high: bool) -> u8 {
+        if extra_high {
+            4
+        } else {
+            3
+        }
+    }
+
+    fn ambient_in_extended_status(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,47 @@
+use super::{BudsModel, UpdateMode};
+use galaxy_buds_rs::model::Model;
+
+/// Galaxy Buds Pro
+pub struct BudsPro;
+
+impl BudsModel for BudsPro {
+    fn model(&self) -> Model {
+        Model::BudsPro
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        name.contains("buds pro")
+    }
+
+    fn name(&self) -> &'static str {
+        "Galaxy Buds Pro"
+    }
+
+    fn update_mode(&self) -> UpdateMode {
+        UpdateMode::Push
+    }
+
+    fn max_ambient_volume(&self, extra_high: bool) -> u8 {
+        if extra_high {
+            4
+        } else {
+            3
+        }
+    }
+
+    fn supports_ambient_during_calls(&self) -> bool {
+        true
+    }
+
+    fn supports_noise_controls(&self) -> bool {
+        true
+    }
+
+    fn supports_anc_level(&self) -> bool {
+        true
+    }
+
+    fn supports_fit_test(&self) -> bool {
+        true
+    }
+}
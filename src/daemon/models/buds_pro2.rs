@@ -0,0 +1,43 @@
+use super::{BudsModel, UpdateMode};
+use galaxy_buds_rs::model::Model;
+
+/// Galaxy Buds 2 Pro
+pub struct BudsPro2;
+
+impl BudsModel for BudsPro2 {
+    fn model(&self) -> Model {
+        Model::BudsPro2
+    }
+
+    fn matches_name(&self, name: &str) -> bool {
+        name.contains("buds 2 pro")
+    }
+
+    fn name(&self) -> &'static str {
+        "Galaxy Buds2 Pro"
+    }
+
+    fn update_mode(&self) -> UpdateMode {
+        UpdateMode::Push
+    }
+
+    fn max_ambient_volume(&self, extra_high: bool) -> u8 {
+        if extra_high {
+            4
+        } else {
+            3
+        }
+    }
+
+    fn supports_ambient_during_calls(&self) -> bool {
+        true
+    }
+
+    fn supports_noise_controls(&self) -> bool {
+        true
+    }
+
+    fn supports_fit_test(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,130 @@
+/*
+ * Model specific behavior. Adding support for a new device means
+ * implementing `BudsModel` in its own module and listing it in `MODELS`.
+ */
+
+mod buds;
+mod buds2;
+mod buds_live;
+mod buds_plus;
+mod buds_pro;
+mod buds_pro2;
+
+use galaxy_buds_rs::model::Model;
+use serde::{Deserialize, Serialize};
+
+/// How a model keeps the daemon up to date
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateMode {
+    /// Sends a status update on every change by itself
+    Push,
+    /// Gets asked for its data periodically
+    Poll,
+}
+
+impl Default for UpdateMode {
+    fn default() -> Self {
+        UpdateMode::Poll
+    }
+}
+
+/// Describes the differences between the supported devices
+pub trait BudsModel: Sync {
+    /// The galaxy_buds_rs model this implementation describes
+    fn model(&self) -> Model;
+
+    /// Returns true if a bluetooth device name belongs to this model
+    fn matches_name(&self, name: &str) -> bool;
+
+    /// Product name shown to users, eg. "Galaxy Buds Live"
+    fn name(&self) -> &'static str;
+
+    /// Highest ambient sound volume level
+    fn max_ambient_volume(&self, _extra_high: bool) -> u8 {
+        3
+    }
+
+    /// Whether ambient sound settings are part of the extended status update
+    fn ambient_in_extended_status(&self) -> bool {
+        false
+    }
+
+    /// Whether ambient sound can be passed through during calls
+    fn supports_ambient_during_calls(&self) -> bool {
+        false
+    }
+
+    /// Whether off, ambient sound and ANC are switched with a single message
+    fn supports_noise_controls(&self) -> bool {
+        false
+    }
+
+    /// Whether the ANC intensity can be set to low or high
+    fn supports_anc_level(&self) -> bool {
+        false
+    }
+
+    /// Oldest firmware (eg. "UK1") supporting a setting, if it came with an update
+    fn min_firmware(&self, _setting: &str) -> Option<&'static str> {
+        None
+    }
+
+    /// Whether the buds can check how well they seal the ear canal
+    fn supports_fit_test(&self) -> bool {
+        false
+    }
+
+    /// Whether the buds push their status or have to be polled
+    fn update_mode(&self) -> UpdateMode {
+        UpdateMode::Poll
+    }
+
+    /// Start and end markers of a message frame
+    fn frame_markers(&self) -> (u8, u8) {
+        (0xFD, 0xDD)
+    }
+}
+
+/// Model whose status frame layout is used for unknown Samsung devices
+pub const UNKNOWN_MODEL_TEMPLATE: Model = Model::BudsPro2;
+
+// Ordered by name matching priority. The original buds are the fallback
+static MODELS: &[&dyn BudsModel] = &[
+    &buds_live::BudsLive,
+    &buds_pro::BudsPro,
+    &buds_pro2::BudsPro2,
+    &buds_plus::BudsPlus,
+    &buds2::Buds2,
+    &buds::Buds,
+];
+
+/// Returns the implementation for a given model
+pub fn get(model: Model) -> &'static dyn BudsModel {
+    MODELS
+        .iter()
+        .find(|i| i.model() == model)
+        .copied()
+        .unwrap_or(&buds::Buds)
+}
+
+/// Gives devices model from its name
+pub fn from_name(device_name: &str) -> Model {
+    find_by_name(device_name).unwrap_or(Model::Buds)
+}
+
+/// Returns true if a device of an unknown model is likely a Samsung audio device
+pub fn is_samsung_name(device_name: &str) -> bool {
+    let device_name = device_name.to_lowercase();
+    device_name.contains("galaxy") || device_name.contains("samsung")
+}
+
+/// Returns the model of a device if its name is one of known buds
+pub fn find_by_name(device_name: &str) -> Option<Model> {
+    let device_name = device_name.to_lowercase();
+
+    MODELS
+        .iter()
+        .find(|i| i.matches_name(&device_name))
+        .map(|i| i.model())
+}
@@ -0,0 +1,74 @@
+/*
+ * Detects buds whose audio is played by another host (eg. the phone) while
+ * they're connected to this machine. The buds don't report their active host,
+ * but the bluetooth card of this machine goes away or gets turned off then.
+ */
+
+use super::bluetooth::rfcomm_connector::ConnectionData;
+use super::pactl::pactl;
+use super::{profile, status_diff};
+
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use log::info;
+use serde_json::json;
+
+use std::time::Duration;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Keep `other_host_active` of the connected buds up to date and publish
+/// a `host_changed` event if it changes
+pub async fn run(cd: Arc<Mutex<ConnectionData>>) {
+    loop {
+        task::sleep(POLL_INTERVAL).await;
+
+        let devices: Vec<String> = cd
+            .lock()
+            .await
+            .data
+            .values()
+            .filter(|i| i.inner.ready && !i.inner.battery_only)
+            .map(|i| i.inner.address.clone())
+            .collect();
+
+        for addr in devices {
+            let check_addr = addr.clone();
+            let active = match task::spawn_blocking(move || other_host_active(&check_addr)).await {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let mut connection_data = cd.lock().await;
+            let changed = match connection_data.get_device_mut(&addr) {
+                Some(info) if info.inner.other_host_active != active => {
+                    info.inner.other_host_active = active;
+                    true
+                }
+                _ => false,
+            };
+
+            if changed {
+                info!(
+                    "Audio of {} is {}",
+                    addr,
+                    if active { "on another host" } else { "back" }
+                );
+                let event = status_diff::named_event(
+                    &addr,
+                    "host_changed",
+                    json!({ "other_host_active": active }),
+                );
+                connection_data.publish(&addr, &event);
+            }
+        }
+    }
+}
+
+// Returns None if there's no audio server to ask
+fn other_host_active(addr: &str) -> Option<bool> {
+    pactl(&["info"]).ok()?;
+    Some(profile::get_card(addr).map_or(true, |card| card.active_profile == "off"))
+}
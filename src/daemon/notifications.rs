@@ -0,0 +1,286 @@
+use super::buds_config::{Config, NotificationStyle};
+
+use notify_rust::{Notification, Timeout, Urgency};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+// Identical notifications within this time are dropped
+const DEDUP_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+// Devices with muted notifications, until the given time or until unmuted if None.
+// Kept outside of the device state to survive reconnects
+static MUTED: Mutex<Vec<(String, Option<SystemTime>)>> = Mutex::new(Vec::new());
+
+/// Kinds of notifications the daemon shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    LowBattery,
+    CriticalBattery,
+    Connect,
+    CaseBattery,
+    SessionSummary,
+    FirmwareChanged,
+    Charging,
+}
+
+impl Category {
+    /// Minimum time between two notifications of this category
+    fn min_interval(&self) -> Duration {
+        match self {
+            Category::LowBattery => Duration::from_secs(5 * 60),
+            Category::CriticalBattery => Duration::from_secs(60),
+            Category::Connect => Duration::from_secs(10),
+            Category::CaseBattery => Duration::from_secs(30 * 60),
+            Category::SessionSummary => Duration::from_secs(60),
+            Category::FirmwareChanged => Duration::from_secs(60),
+            Category::Charging => Duration::from_secs(10 * 60),
+        }
+    }
+
+    /// Whether a new notification should replace the previous one
+    fn replaces(&self) -> bool {
+        matches!(self, Category::LowBattery | Category::CriticalBattery)
+    }
+
+    /// Urgency used if the user didn't configure one
+    fn default_urgency(&self) -> Urgency {
+        match self {
+            Category::CriticalBattery => Urgency::Critical,
+            _ => Urgency::Normal,
+        }
+    }
+
+    // Returns the user defined style of this category
+    fn style<'a>(&self, config: &'a Config) -> Option<&'a NotificationStyle> {
+        let notifications = config.notifications.as_ref()?;
+        match self {
+            Category::LowBattery => notifications.low_battery.as_ref(),
+            Category::CriticalBattery => notifications.critical_battery.as_ref(),
+            Category::Connect => notifications.connect.as_ref(),
+            Category::CaseBattery => notifications.case_battery.as_ref(),
+            Category::SessionSummary => notifications.session_summary.as_ref(),
+            Category::FirmwareChanged => notifications.firmware_changed.as_ref(),
+            Category::Charging => notifications.charging.as_ref(),
+        }
+    }
+}
+
+/// A notification with the values of its placeholders, eg. `left` for `{left}`.
+/// Templates of the user replace the default title and body
+pub struct Message {
+    notification: Notification,
+    values: Vec<(&'static str, String)>,
+}
+
+impl Message {
+    pub fn new(notification: Notification) -> Self {
+        Self {
+            notification,
+            values: Vec::new(),
+        }
+    }
+
+    /// Set the value of a placeholder
+    pub fn value<T: ToString>(mut self, key: &'static str, value: T) -> Self {
+        self.values.push((key, value.to_string()));
+        self
+    }
+
+    // Replace the placeholders of a template
+    fn render(&self, template: &str, device: &str) -> String {
+        self.values.iter().fold(
+            template.replace("{device}", device),
+            |text, (key, value)| text.replace(&format!("{{{}}}", key), value),
+        )
+    }
+}
+
+// The last shown notification of a category
+struct Shown {
+    time: Instant,
+    content: String,
+    id: u32,
+}
+
+/// A shown notification kept across restarts of the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShownEntry {
+    pub category: Category,
+    // Unix time it was shown at
+    pub time: u64,
+    pub content: String,
+    pub id: u32,
+}
+
+/// Deduplicates and throttles desktop notifications
+#[derive(Default)]
+pub struct Notifier {
+    device: String,
+    shown: HashMap<Category, Shown>,
+}
+
+impl Notifier {
+    pub fn new(device: &str) -> Self {
+        Self {
+            device: device.to_owned(),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the notifications which still throttle new ones
+    pub fn export(&self) -> Vec<ShownEntry> {
+        let now = SystemTime::now();
+        self.shown
+            .iter()
+            .filter(|(_, shown)| shown.time.elapsed() < DEDUP_WINDOW)
+            .filter_map(|(category, shown)| {
+                let time = now.checked_sub(shown.time.elapsed())?;
+                Some(ShownEntry {
+                    category: *category,
+                    time: time.duration_since(UNIX_EPOCH).ok()?.as_secs(),
+                    content: shown.content.clone(),
+                    id: shown.id,
+                })
+            })
+            .collect()
+    }
+
+    /// Throttle as if the exported notifications were shown by this notifier
+    pub fn restore(&mut self, entries: Vec<ShownEntry>) {
+        for entry in entries {
+            let elapsed = match SystemTime::now()
+                .duration_since(UNIX_EPOCH + Duration::from_secs(entry.time))
+            {
+                Ok(v) => v,
+                Err(_) => continue,
+            };
+
+            if let Some(time) = Instant::now().checked_sub(elapsed) {
+                let shown = Shown {
+                    time,
+                    content: entry.content,
+                    id: entry.id,
+                };
+                self.shown.insert(entry.category, shown);
+            }
+        }
+    }
+
+    /// Show a notification unless it was shown recently. Returns true if it was displayed
+    pub fn show(&mut self, category: Category, message: Message, config: &Config) -> bool {
+        if is_muted(&self.device) {
+            return false;
+        }
+
+        let style = category.style(config);
+        let mut notification = message.notification.clone();
+        if let Some(title) = style.and_then(|i| i.title.as_ref()) {
+            notification.summary(&message.render(title, &self.device));
+        }
+        if let Some(body) = style.and_then(|i| i.body.as_ref()) {
+            notification.body(&message.render(body, &self.device));
+        }
+
+        let content = format!("{}\n{}", notification.summary, notification.body);
+
+        if let Some(last) = self.shown.get(&category) {
+            let elapsed = last.time.elapsed();
+            if elapsed < category.min_interval()
+                || (elapsed < DEDUP_WINDOW && last.content == content)
+            {
+                return false;
+            }
+
+            // Replace the previous notification instead of stacking them
+            if category.replaces() {
+                notification.id(last.id);
+            }
+        }
+
+        notification.urgency(category.default_urgency());
+        if let Some(style) = style {
+            apply_style(&mut notification, style);
+        }
+
+        match notification.show() {
+            Ok(handle) => {
+                self.shown.insert(
+                    category,
+                    Shown {
+                        time: Instant::now(),
+                        content,
+                        id: handle.id(),
+                    },
+                );
+                true
+            }
+            Err(err) => {
+                eprintln!("Can't show notification: {:?}", err);
+                false
+            }
+        }
+    }
+}
+
+/// Mute the notifications of a device for the given time or until unmuted.
+/// Returns the end of the mute
+pub fn mute(device: &str, duration: Option<Duration>) -> Option<SystemTime> {
+    let until = duration.map(|i| SystemTime::now() + i);
+    if let Ok(mut muted) = MUTED.lock() {
+        muted.retain(|(d, _)| d != device);
+        muted.push((device.to_owned(), until));
+    }
+    until
+}
+
+/// Show the notifications of a device again
+pub fn unmute(device: &str) {
+    if let Ok(mut muted) = MUTED.lock() {
+        muted.retain(|(d, _)| d != device);
+    }
+}
+
+/// Returns true if the notifications of a device are muted
+pub fn is_muted(device: &str) -> bool {
+    let mut muted = match MUTED.lock() {
+        Ok(muted) => muted,
+        Err(_) => return false,
+    };
+
+    // Forget expired mutes
+    let now = SystemTime::now();
+    muted.retain(|(_, until)| until.map_or(true, |i| i > now));
+    muted.iter().any(|(d, _)| d == device)
+}
+
+// Apply the user defined style to a notification
+fn apply_style(notification: &mut Notification, style: &NotificationStyle) {
+    if let Some(ref urgency) = style.urgency {
+        notification.urgency(match urgency.as_str() {
+            "low" => Urgency::Low,
+            "critical" => Urgency::Critical,
+            _ => Urgency::Normal,
+        });
+    }
+
+    if let Some(timeout) = style.timeout {
+        notification.timeout(match timeout {
+            0 => Timeout::Never,
+            ms => Timeout::Milliseconds(ms),
+        });
+    }
+
+    if let Some(ref app_name) = style.app_name {
+        notification.appname(app_name);
+    }
+
+    if let Some(ref icon) = style.icon {
+        notification.icon(icon);
+    }
+}
@@ -0,0 +1,46 @@
+use std::process::Command;
+
+/// Run pactl with a non localized output. Works with pulseaudio and pipewire-pulse
+pub fn pactl(args: &[&str]) -> Result<String, String> {
+    let output = Command::new("pactl")
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| e.to_string())?;
+
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_owned());
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Returns true if an application records from an input like a microphone.
+/// Recordings of monitor sources (eg. screen casts with desktop audio) don't count
+pub fn has_input_stream() -> bool {
+    let (sources, outputs) = match (
+        pactl(&["list", "short", "sources"]),
+        pactl(&["list", "short", "source-outputs"]),
+    ) {
+        (Ok(sources), Ok(outputs)) => (sources, outputs),
+        _ => return false,
+    };
+
+    // Columns are index and name for sources, index and source for outputs
+    let monitors: Vec<&str> = sources
+        .lines()
+        .filter_map(|i| {
+            let mut columns = i.split('\t');
+            let index = columns.next()?;
+            columns
+                .next()
+                .filter(|name| name.ends_with(".monitor"))
+                .map(|_| index)
+        })
+        .collect();
+
+    outputs
+        .lines()
+        .filter_map(|i| i.split('\t').nth(1))
+        .any(|source| !monitors.contains(&source))
+}
@@ -0,0 +1,175 @@
+/*
+ * Pairing of remote clients. The daemon hands out a one-time code to a local
+ * user, a remote client exchanges it for a long-lived token and sends that
+ * token with each request. Tokens are kept in the state directory.
+ */
+
+use super::history::get_state_dir;
+
+use async_std::fs::{self, OpenOptions};
+use async_std::io::prelude::*;
+use async_std::os::unix::fs::OpenOptionsExt;
+use async_std::path::PathBuf;
+use log::info;
+
+use std::{
+    io::Read,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+const CODE_VALIDITY: Duration = Duration::from_secs(300);
+// Wrong guesses before a code gets invalid
+const MAX_ATTEMPTS: u8 = 3;
+
+struct PairingCode {
+    code: String,
+    created: Instant,
+    attempts: u8,
+}
+
+static CODE: Mutex<Option<PairingCode>> = Mutex::new(None);
+
+/// Create a new one-time code, replacing the previous one
+pub fn new_code() -> Result<String, String> {
+    let code = format!("{:06}", random_u32()? % 1_000_000);
+    info!("Created a pairing code for remote clients");
+
+    if let Ok(mut current) = CODE.lock() {
+        *current = Some(PairingCode {
+            code: code.clone(),
+            created: Instant::now(),
+            attempts: 0,
+        });
+    }
+
+    Ok(code)
+}
+
+/// Exchange a one-time code for a token
+pub async fn pair(code: &str) -> Result<String, String> {
+    {
+        let mut current = CODE.lock().map_err(|e| e.to_string())?;
+        let pending = match current.as_mut() {
+            Some(p) if p.created.elapsed() < CODE_VALIDITY => p,
+            _ => return Err("No pairing code active. Run `earbuds remote pair`".to_owned()),
+        };
+
+        if !constant_time_eq(&pending.code, code.trim()) {
+            pending.attempts += 1;
+            if pending.attempts >= MAX_ATTEMPTS {
+                *current = None;
+            }
+            return Err("Wrong pairing code".to_owned());
+        }
+
+        *current = None;
+    }
+
+    let token = random_hex(32)?;
+    let mut tokens = read_tokens().await?;
+    tokens.push(token.clone());
+    write_tokens(&tokens).await?;
+
+    info!("Paired a new remote client");
+    Ok(token)
+}
+
+/// Returns true if the token belongs to a paired client
+pub async fn is_authorized(token: Option<&str>) -> bool {
+    let token = match token {
+        Some(t) if !t.is_empty() => t,
+        _ => return false,
+    };
+
+    read_tokens()
+        .await
+        .map(|tokens| {
+            tokens
+                .iter()
+                .fold(false, |found, i| constant_time_eq(i, token) | found)
+        })
+        .unwrap_or(false)
+}
+
+/// Forget all paired clients. Returns how many got removed
+pub async fn revoke_all() -> Result<usize, String> {
+    let count = read_tokens().await?.len();
+    write_tokens(&[]).await?;
+    Ok(count)
+}
+
+async fn read_tokens() -> Result<Vec<String>, String> {
+    let file = get_tokens_file()?;
+    if !file.exists().await {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(&file).await.map_err(|e| e.to_string())?;
+    Ok(content
+        .lines()
+        .map(|i| i.trim().to_owned())
+        .filter(|i| !i.is_empty())
+        .collect())
+}
+
+// Only the user may read the tokens
+async fn write_tokens(tokens: &[String]) -> Result<(), String> {
+    let file = get_tokens_file()?;
+    if let Some(dir) = file.parent() {
+        fs::create_dir_all(dir).await.map_err(|e| e.to_string())?;
+    }
+
+    let mut content = tokens.join("\n");
+    content.push('\n');
+
+    let mut f = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&file)
+        .await
+        .map_err(|e| e.to_string())?;
+    f.write_all(content.as_bytes())
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Compare secrets without leaking how much of them matched through the timing
+fn constant_time_eq(a: &str, b: &str) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.bytes()
+        .zip(b.bytes())
+        .fold(0, |diff, (x, y)| diff | (x ^ y))
+        == 0
+}
+
+fn get_tokens_file() -> Result<PathBuf, String> {
+    Ok(get_state_dir()
+        .ok_or("Can't find state directory")?
+        .join("remote_tokens"))
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, String> {
+    let mut buf = vec![0u8; len];
+    std::fs::File::open("/dev/urandom")
+        .and_then(|mut f| f.read_exact(&mut buf))
+        .map_err(|e| format!("Can't read random data: {}", e))?;
+    Ok(buf)
+}
+
+fn random_u32() -> Result<u32, String> {
+    let bytes = random_bytes(4)?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn random_hex(len: usize) -> Result<String, String> {
+    Ok(random_bytes(len)?
+        .iter()
+        .map(|i| format!("{:02x}", i))
+        .collect())
+}
@@ -0,0 +1,218 @@
+/*
+ * Parsers for values given on the command line, in requests and in the
+ * config, so they accept the same formats and fail with the same messages.
+ */
+
+use serde::{Deserialize, Deserializer};
+
+use std::{str::FromStr, time::Duration};
+
+// About 100 years. Longer durations are typos and would overflow when added to an instant
+const MAX_SECONDS: f64 = 100.0 * 365.0 * 24.0 * 60.0 * 60.0;
+
+/// Parse a boolean like on/off, true/false, yes/no, enabled/disabled or 1/0
+pub fn bool(input: &str) -> Result<bool, String> {
+    match input.trim().to_lowercase().as_str() {
+        "1" | "true" | "yes" | "y" | "enabled" | "on" => Ok(true),
+        "0" | "false" | "no" | "n" | "disabled" | "off" => Ok(false),
+        _ => Err(format!("Invalid value '{}'. Use on or off", input)),
+    }
+}
+
+/// Parse a duration like 90s, 30m, 2h, 1d, 500ms or combined like 1h30m.
+/// Numbers without unit are seconds
+pub fn duration(input: &str) -> Result<Duration, String> {
+    let err = || format!("Invalid duration '{}'. Use eg. 90s, 30m or 2h", input);
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(err());
+    }
+
+    if let Ok(secs) = input.parse::<f64>() {
+        return seconds(secs).ok_or_else(err);
+    }
+
+    let mut total = 0_f64;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .ok_or_else(err)?;
+        let (value, tail) = rest.split_at(split);
+        let unit_len = tail
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or_else(|| tail.len());
+        let (unit, tail) = tail.split_at(unit_len);
+
+        let value = value.parse::<f64>().map_err(|_| err())?;
+        total += value
+            * match unit {
+                "ms" => 0.001,
+                "s" => 1.0,
+                "m" => 60.0,
+                "h" => 60.0 * 60.0,
+                "d" => 24.0 * 60.0 * 60.0,
+                _ => return Err(err()),
+            };
+        rest = tail;
+    }
+
+    seconds(total).ok_or_else(err)
+}
+
+/// Parse a duration which has to be longer than zero, eg. an interval
+pub fn positive_duration(input: &str) -> Result<Duration, String> {
+    match duration(input)? {
+        d if d.is_zero() => Err(format!("Duration '{}' has to be longer than zero", input)),
+        d => Ok(d),
+    }
+}
+
+/// Parse a percentage like 80 or 80%
+pub fn percent(input: &str) -> Result<u8, String> {
+    let value = input.trim().trim_end_matches('%');
+    match value.parse::<u8>() {
+        Ok(v) if v <= 100 => Ok(v),
+        _ => Err(format!(
+            "Invalid percentage '{}'. Use a number from 0 to 100",
+            input
+        )),
+    }
+}
+
+/// Parse a level from 0 to `max`, eg. of ambient sound
+pub fn level(input: &str, max: u8) -> Result<u8, String> {
+    match input.trim().parse::<u8>() {
+        Ok(v) if v <= max => Ok(v),
+        _ => Err(format!(
+            "Invalid level '{}'. Use a number from 0 to {}",
+            input, max
+        )),
+    }
+}
+
+/// Parse a plain number
+pub fn number<T: FromStr>(input: &str) -> Result<T, String> {
+    input
+        .trim()
+        .parse::<T>()
+        .map_err(|_| format!("Invalid number '{}'", input))
+}
+
+/// Deserialize seconds of the config, given as number or duration like '10m'
+pub fn deserialize_seconds<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Seconds {
+        Number(u64),
+        Text(String),
+    }
+
+    match Option::<Seconds>::deserialize(deserializer)? {
+        Some(Seconds::Number(v)) => Ok(Some(v)),
+        Some(Seconds::Text(s)) => duration(&s)
+            .map(|i| Some(i.as_secs()))
+            .map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+fn seconds(secs: f64) -> Option<Duration> {
+    if (0.0..=MAX_SECONDS).contains(&secs) {
+        Some(Duration::from_secs_f64(secs))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bool() {
+        assert_eq!(bool("On"), Ok(true));
+        assert_eq!(bool(" yes "), Ok(true));
+        assert_eq!(bool("disabled"), Ok(false));
+        assert_eq!(bool("0"), Ok(false));
+        assert_eq!(
+            bool("maybe"),
+            Err("Invalid value 'maybe'. Use on or off".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(duration("90"), Ok(Duration::from_secs(90)));
+        assert_eq!(duration("1.5"), Ok(Duration::from_millis(1500)));
+        assert_eq!(duration("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(duration("30m"), Ok(Duration::from_secs(30 * 60)));
+        assert_eq!(duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(duration("1d"), Ok(Duration::from_secs(24 * 60 * 60)));
+        assert_eq!(duration("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(duration("1h30m"), Ok(Duration::from_secs(90 * 60)));
+        assert_eq!(duration("0"), Ok(Duration::from_secs(0)));
+    }
+
+    #[test]
+    fn test_invalid_duration() {
+        let err = |input: &str| {
+            Err(format!(
+                "Invalid duration '{}'. Use eg. 90s, 30m or 2h",
+                input
+            ))
+        };
+
+        assert_eq!(duration(""), err(""));
+        assert_eq!(duration("-5"), err("-5"));
+        assert_eq!(duration("10x"), err("10x"));
+        assert_eq!(duration("m"), err("m"));
+        assert_eq!(duration("NaN"), err("NaN"));
+        assert_eq!(duration("inf"), err("inf"));
+        assert_eq!(duration("1e300"), err("1e300"));
+        assert_eq!(duration("999999999999d"), err("999999999999d"));
+    }
+
+    #[test]
+    fn test_positive_duration() {
+        assert_eq!(positive_duration("5s"), Ok(Duration::from_secs(5)));
+        assert_eq!(
+            positive_duration("0s"),
+            Err("Duration '0s' has to be longer than zero".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent("80"), Ok(80));
+        assert_eq!(percent("80%"), Ok(80));
+        assert_eq!(percent("0"), Ok(0));
+        assert_eq!(percent("100"), Ok(100));
+        assert_eq!(
+            percent("101"),
+            Err("Invalid percentage '101'. Use a number from 0 to 100".to_owned())
+        );
+        assert!(percent("-1").is_err());
+        assert!(percent("half").is_err());
+    }
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level("0", 4), Ok(0));
+        assert_eq!(level(" 4 ", 4), Ok(4));
+        assert_eq!(
+            level("5", 4),
+            Err("Invalid level '5'. Use a number from 0 to 4".to_owned())
+        );
+        assert!(level("high", 4).is_err());
+    }
+
+    #[test]
+    fn test_number() {
+        assert_eq!(number::<u8>("42"), Ok(42));
+        assert_eq!(number::<u8>("256"), Err("Invalid number '256'".to_owned()));
+    }
+}
@@ -0,0 +1,52 @@
+/*
+ * Decides how often the buds get asked for data which they don't push by
+ * themselves (eg. voltage and temperature). Without a client or a feature
+ * needing fresh values, the daemon only sends a slow heartbeat to save power,
+ * or nothing at all to buds which push their status.
+ */
+
+use super::buds_config::Config;
+use super::buds_info::BudsInfo;
+use super::models::{self, UpdateMode};
+
+use async_std::sync::{Arc, Mutex};
+
+use std::time::Duration;
+
+const ACTIVE_INTERVAL: Duration = Duration::from_secs(8);
+const IDLE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Returns the interval to request data of a device at, or None if it
+/// doesn't need to be asked
+pub async fn interval(
+    has_subscribers: bool,
+    info: &BudsInfo,
+    config: &Arc<Mutex<Config>>,
+) -> Option<Duration> {
+    // Ask until the settings are known
+    if has_subscribers || !info.inner.ready {
+        return Some(ACTIVE_INTERVAL);
+    }
+
+    // The input source gets checked at the same interval
+    let auto_mic = config
+        .lock()
+        .await
+        .get_device_config(&info.inner.address)
+        .map(|i| i.auto_mic())
+        .unwrap_or(false);
+
+    if auto_mic {
+        return Some(ACTIVE_INTERVAL);
+    }
+
+    // Unknown models may not push anything
+    if info.inner.battery_only {
+        return Some(IDLE_INTERVAL);
+    }
+
+    match models::get(info.inner.model).update_mode() {
+        UpdateMode::Push => None,
+        UpdateMode::Poll => Some(IDLE_INTERVAL),
+    }
+}
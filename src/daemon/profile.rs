@@ -0,0 +1,170 @@
+/*
+ * Switches the bluetooth card profile of the buds to headset while a call
+ * application records audio and back to the previous profile afterwards.
+ * Uses pactl which is available for pulseaudio and pipewire-pulse.
+ */
+
+use super::bluetooth::rfcomm_connector::ConnectionData;
+use super::buds_config::Config;
+use super::pactl::pactl;
+
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use log::info;
+
+use std::{collections::HashMap, time::Duration};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Bluetooth card of a device
+pub(super) struct Card {
+    pub name: String,
+    pub active_profile: String,
+    pub profiles: Vec<String>,
+}
+
+/// Watch for call streams and switch profiles of devices with `auto_profile` enabled
+pub async fn run(cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) {
+    // Profiles to restore once the call ended
+    let mut restore: HashMap<String, String> = HashMap::new();
+
+    loop {
+        task::sleep(POLL_INTERVAL).await;
+
+        let connected: Vec<String> = cd.lock().await.data.keys().cloned().collect();
+        restore.retain(|addr, _| connected.contains(addr));
+
+        let (devices, apps) = {
+            let cfg = config.lock().await;
+            let devices: Vec<String> = connected
+                .into_iter()
+                .filter(|i| {
+                    cfg.get_device_config(i)
+                        .map(|c| c.auto_profile())
+                        .unwrap_or(false)
+                })
+                .collect();
+            (devices, cfg.call_apps())
+        };
+
+        if devices.is_empty() {
+            continue;
+        }
+
+        let call_active = task::spawn_blocking(move || is_call_active(&apps)).await;
+
+        for addr in devices {
+            let previous = restore.get(&addr).cloned();
+            if !call_active && previous.is_none() {
+                continue;
+            }
+
+            let result =
+                task::spawn_blocking(move || update_profile(&addr, call_active, previous)).await;
+
+            match result {
+                Ok((addr, Some(profile))) => {
+                    restore.insert(addr, profile);
+                }
+                Ok((addr, None)) => {
+                    restore.remove(&addr);
+                }
+                Err(err) => eprintln!("Can't switch profile: {}", err),
+            }
+        }
+    }
+}
+
+// Switch to headset during calls and back afterwards. Returns the profile to restore later
+fn update_profile(
+    addr: &str,
+    call_active: bool,
+    previous: Option<String>,
+) -> Result<(String, Option<String>), String> {
+    let card = get_card(addr).ok_or("Bluetooth card not found")?;
+
+    match previous {
+        None if call_active && !is_headset_profile(&card.active_profile) => {
+            let headset = card
+                .profiles
+                .iter()
+                .find(|i| is_headset_profile(i))
+                .ok_or("No headset profile available")?;
+
+            info!("Call started. Switching {} to {}", addr, headset);
+            pactl(&["set-card-profile", &card.name, headset])?;
+            Ok((addr.to_owned(), Some(card.active_profile)))
+        }
+        Some(profile) if !call_active => {
+            info!("Call ended. Switching {} back to {}", addr, profile);
+            pactl(&["set-card-profile", &card.name, &profile])?;
+            Ok((addr.to_owned(), None))
+        }
+        previous => Ok((addr.to_owned(), previous)),
+    }
+}
+
+// Returns true if a call application records audio
+fn is_call_active(apps: &[String]) -> bool {
+    let output = match pactl(&["list", "source-outputs"]) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+
+    output
+        .lines()
+        .map(|i| i.trim())
+        .filter(|i| {
+            i.starts_with("application.name") || i.starts_with("application.process.binary")
+        })
+        .any(|line| {
+            let line = line.to_lowercase();
+            apps.iter().any(|app| line.contains(&app.to_lowercase()))
+        })
+}
+
+fn is_headset_profile(profile: &str) -> bool {
+    profile.starts_with("headset") || profile.starts_with("handsfree")
+}
+
+/// Parse the card of a device from `pactl list cards`
+pub(super) fn get_card(addr: &str) -> Option<Card> {
+    let output = pactl(&["list", "cards"]).ok()?;
+    let name = format!("bluez_card.{}", addr.replace(':', "_"));
+
+    let mut lines = output
+        .lines()
+        .skip_while(|i| !i.trim().eq_ignore_ascii_case(&format!("Name: {}", name)));
+    lines.next()?;
+
+    let mut active_profile = None;
+    let mut profiles = Vec::new();
+    let mut in_profiles = false;
+
+    for line in lines {
+        let trimmed = line.trim();
+        if trimmed.starts_with("Card #") {
+            break;
+        }
+
+        if let Some(profile) = trimmed.strip_prefix("Active Profile: ") {
+            active_profile = Some(profile.to_owned());
+        } else if trimmed == "Profiles:" {
+            in_profiles = true;
+        } else if in_profiles && line.starts_with("\t\t") {
+            if let Some(profile) = trimmed.split(':').next() {
+                profiles.push(profile.to_owned());
+            }
+        } else {
+            in_profiles = false;
+        }
+    }
+
+    Some(Card {
+        name,
+        active_profile: active_profile?,
+        profiles,
+    })
+}
@@ -0,0 +1,147 @@
+use super::crc::crc16;
+use super::models;
+
+use galaxy_buds_rs::model::Model;
+
+/// Message ids which aren't covered by galaxy_buds_rs
+pub mod ids {
+    // Switches between off (0), ANC (1) and ambient sound (2) on newer buds
+    pub const NOISE_CONTROLS: u8 = 0x78;
+    pub const NOISE_CONTROLS_UPDATE: u8 = 0x79;
+    // ANC intensity of the Buds Pro, low (0) or high (1)
+    pub const NOISE_REDUCTION_LEVEL: u8 = 0x83;
+    pub const SET_SIDETONE: u8 = 0x8B;
+    // An empty message with this id makes the buds send their current settings
+    pub const EXTENDED_STATUS_UPDATED: u8 = 0x61;
+    // An empty message with this id makes the buds send their hardware and software versions
+    pub const VERSION_INFO: u8 = 0x63;
+    // Starts (1) or stops (0) playing the fit test tone
+    pub const CHECK_FIT: u8 = 0x9D;
+    pub const CHECK_FIT_RESULT: u8 = 0x9E;
+    // Makes both buds play a loud chirp until stopped
+    pub const FIND_MY_EARBUDS_START: u8 = 0xA0;
+    pub const FIND_MY_EARBUDS_STOP: u8 = 0xA1;
+    // Mutes the chirp of single buds, payload is (left, right) with 1 for muted
+    pub const MUTE_EARBUD: u8 = 0xA2;
+
+    /// Returns the name of a known message id
+    pub fn name(id: u8) -> Option<&'static str> {
+        Some(match id {
+            0x26 => "DEBUG_GET_ALL_DATA",
+            0x60 => "STATUS_UPDATED",
+            EXTENDED_STATUS_UPDATED => "EXTENDED_STATUS_UPDATED",
+            VERSION_INFO => "VERSION_INFO",
+            NOISE_CONTROLS => "NOISE_CONTROLS",
+            NOISE_CONTROLS_UPDATE => "NOISE_CONTROLS_UPDATE",
+            0x80 => "SET_AMBIENT_MODE",
+            NOISE_REDUCTION_LEVEL => "NOISE_REDUCTION_LEVEL",
+            0x84 => "AMBIENT_VOLUME",
+            0x86 => "EQUALIZER",
+            0x88 => "MANAGER_INFO",
+            SET_SIDETONE => "SET_SIDETONE",
+            0x90 => "LOCK_TOUCHPAD",
+            0x92 => "SET_TOUCHPAD_OPTION",
+            0x96 => "EXTRA_HIGH_AMBIENT",
+            0x98 => "SET_NOISE_REDUCTION",
+            CHECK_FIT => "CHECK_FIT",
+            CHECK_FIT_RESULT => "CHECK_FIT_RESULT",
+            FIND_MY_EARBUDS_START => "FIND_MY_EARBUDS_START",
+            FIND_MY_EARBUDS_STOP => "FIND_MY_EARBUDS_STOP",
+            MUTE_EARBUD => "MUTE_EARBUD",
+            _ => return None,
+        })
+    }
+}
+
+/// A message built from its id and raw payload. Used for
+/// messages galaxy_buds_rs doesn't provide (yet)
+#[derive(Debug, Clone)]
+pub struct RawMessage {
+    pub id: u8,
+    pub payload: Vec<u8>,
+}
+
+impl RawMessage {
+    pub fn new(id: u8, payload: Vec<u8>) -> Self {
+        Self { id, payload }
+    }
+
+    /// Encode the message into a frame understood by the given model
+    pub fn to_byte_array(&self, model: Model) -> Vec<u8> {
+        let (som, eom) = models::get(model).frame_markers();
+
+        // Size includes the id and the crc
+        let size = (self.payload.len() + 3) as u16;
+
+        let mut body = vec![self.id];
+        body.extend_from_slice(&self.payload);
+        let crc = crc16(&body);
+
+        let mut frame = vec![som, (size & 0xFF) as u8, (size >> 8) as u8];
+        frame.extend_from_slice(&body);
+        frame.push((crc & 0xFF) as u8);
+        frame.push((crc >> 8) as u8);
+        frame.push(eom);
+        frame
+    }
+}
+
+/// Returns a human readable description of a message frame
+pub fn describe_frame(frame: &[u8]) -> String {
+    let (id, payload) = match split_frame(frame) {
+        Some(v) => v,
+        None => return format!("INVALID [{}]", to_hex(frame)),
+    };
+
+    format!(
+        "{} (0x{:02x}) payload: [{}]",
+        ids::name(id).unwrap_or("UNKNOWN"),
+        id,
+        to_hex(payload)
+    )
+}
+
+/// Returns the id and payload of a message frame
+pub fn split_frame(frame: &[u8]) -> Option<(u8, &[u8])> {
+    // SOM, 2 bytes size, id, payload, 2 bytes crc, EOM
+    if frame.len() < 7 {
+        return None;
+    }
+
+    Some((frame[3], &frame[4..frame.len() - 3]))
+}
+
+/// Parse a message id like `0x9d` or `157`
+pub fn parse_id(s: &str) -> Result<u8, String> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u8::from_str_radix(hex, 16),
+        None => s.parse::<u8>(),
+    }
+    .map_err(|_| format!("Invalid message id '{}'", s))
+}
+
+/// Parse hex bytes like `01 ff` or `01ff`
+pub fn from_hex(s: &str) -> Result<Vec<u8>, String> {
+    let digits: String = s.chars().filter(|i| !i.is_whitespace()).collect();
+    if !digits.chars().all(|i| i.is_ascii_hexdigit()) {
+        return Err(format!("Invalid hex payload '{}'", s));
+    }
+    if digits.len() % 2 != 0 {
+        return Err("Odd number of hex digits".to_string());
+    }
+
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).unwrap())
+        .collect())
+}
+
+/// Format bytes as space separated hex values
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|i| format!("{:02x}", i))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
@@ -0,0 +1,176 @@
+/*
+ * Keeps runtime state of the devices across restarts of the daemon, eg. for
+ * upgrades: settings the daemon has set, players it paused and recently shown
+ * notifications. Written to the state dir on shutdown and handoff, and
+ * applied once a device connects to the new daemon.
+ */
+
+use super::buds_info::BudsInfo;
+use super::history;
+use super::media_control::PausedPlayer;
+use super::notifications::ShownEntry;
+use super::settings::{self, Value};
+
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+const FILE_NAME: &str = "runtime.json";
+// State older than this belongs to another session
+const MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+// State of the devices which got loaded or disconnected, by address
+static DEVICES: Mutex<Option<HashMap<String, DeviceState>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct DeviceState {
+    // Unix time the state was taken
+    time: u64,
+    // Settings set by the daemon as key, parameter and value
+    settings: Vec<(String, Option<String>, Value)>,
+    paused_music_earlier: bool,
+    // Player paused on removal and the unix time it happened
+    paused_player: Option<(PausedPlayer, u64)>,
+    did_battery_notify: bool,
+    did_critical_battery_notify: bool,
+    did_case_battery_notify: bool,
+    notifications: Vec<ShownEntry>,
+}
+
+impl DeviceState {
+    fn new(info: &BudsInfo) -> Self {
+        Self {
+            time: now(),
+            settings: info
+                .desired_settings
+                .iter()
+                .map(|((key, param), value)| (key.to_string(), param.map(|i| i.to_owned()), *value))
+                .collect(),
+            paused_music_earlier: info.inner.paused_music_earlier,
+            paused_player: info
+                .paused_player
+                .as_ref()
+                .map(|(player, at)| (player.clone(), now().saturating_sub(at.elapsed().as_secs()))),
+            did_battery_notify: info.inner.did_battery_notify,
+            did_critical_battery_notify: info.did_critical_battery_notify,
+            did_case_battery_notify: info.did_case_battery_notify,
+            notifications: info.notifier.export(),
+        }
+    }
+
+    fn apply(self, info: &mut BudsInfo) {
+        for (key, param, value) in self.settings {
+            if let Some(setting) = settings::find(&key) {
+                for param in setting.affected_params(param.as_deref()) {
+                    info.desired_settings.insert((setting.key(), param), value);
+                }
+            }
+        }
+
+        info.inner.paused_music_earlier = self.paused_music_earlier;
+        info.paused_player = self
+            .paused_player
+            .and_then(|(player, time)| Some((player, instant_at(time)?)));
+        info.inner.did_battery_notify = self.did_battery_notify;
+        info.did_critical_battery_notify = self.did_critical_battery_notify;
+        info.did_case_battery_notify = self.did_case_battery_notify;
+        info.notifier.restore(self.notifications);
+    }
+}
+
+/// Read the state written by the previous daemon. The file gets removed, so a
+/// daemon restarting after a crash doesn't apply it again
+pub fn load() {
+    let path = get_path();
+    let devices: HashMap<String, DeviceState> = path
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    if let Some(path) = path {
+        fs::remove_file(path).ok();
+    }
+
+    let devices: HashMap<String, DeviceState> = devices
+        .into_iter()
+        .filter(|(_, state)| now().saturating_sub(state.time) < MAX_AGE.as_secs())
+        .collect();
+
+    if !devices.is_empty() {
+        info!("Loaded the runtime state of {} device(s)", devices.len());
+    }
+
+    if let Ok(mut lock) = DEVICES.lock() {
+        *lock = Some(devices);
+    }
+}
+
+/// Apply the stored state to a newly connected device
+pub fn restore(info: &mut BudsInfo) {
+    let state = DEVICES
+        .lock()
+        .ok()
+        .and_then(|mut lock| lock.as_mut()?.remove(&info.inner.address));
+
+    if let Some(state) = state {
+        state.apply(info);
+    }
+}
+
+/// Keep the state of a disconnecting device until the daemon shuts down
+pub fn remember(info: &BudsInfo) {
+    if let Ok(mut lock) = DEVICES.lock() {
+        lock.get_or_insert_with(HashMap::new)
+            .insert(info.inner.address.clone(), DeviceState::new(info));
+    }
+}
+
+/// Write the state of the connected and remembered devices
+pub fn save<'a, I: Iterator<Item = &'a BudsInfo>>(connected: I) {
+    for info in connected {
+        remember(info);
+    }
+
+    let devices = match DEVICES.lock() {
+        Ok(lock) => lock.clone().unwrap_or_default(),
+        Err(_) => return,
+    };
+
+    if let Err(err) = write(&devices) {
+        error!("Can't save the runtime state: {}", err);
+    }
+}
+
+fn write(devices: &HashMap<String, DeviceState>) -> Result<(), String> {
+    let path = get_path().ok_or("Can't find state directory")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+
+    let content = serde_json::to_string(devices).map_err(|e| e.to_string())?;
+    fs::write(path, content).map_err(|e| e.to_string())
+}
+
+fn get_path() -> Option<PathBuf> {
+    Some(history::get_state_dir()?.join(FILE_NAME))
+}
+
+// Returns the instant of a unix time in the past
+fn instant_at(time: u64) -> Option<Instant> {
+    Instant::now().checked_sub(Duration::from_secs(now().checked_sub(time)?))
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|i| i.as_secs())
+        .unwrap_or_default()
+}
@@ -0,0 +1,206 @@
+/*
+ * Reacts to the session getting locked and unlocked, announced by the
+ * screensaver D-Bus interfaces of freedesktop (eg. KDE) and GNOME. Depending
+ * on the `lock_action` of a device, playback gets paused and/or ambient sound
+ * enabled on lock. Both get restored on unlock.
+ */
+
+use super::bluetooth::rfcomm_connector::ConnectionData;
+use super::buds_config::Config;
+use super::media_control::{try_pause, try_play};
+use super::settings::{self, Value};
+
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use futures_lite::StreamExt;
+use log::{error, info};
+use zbus::{dbus_proxy, Connection};
+
+use std::collections::HashMap;
+
+// Settings changed on lock, in the order they get set
+const AMBIENT_SETTINGS: [&str; 2] = ["noise_reduction", "ambient_volume"];
+
+#[dbus_proxy(
+    interface = "org.freedesktop.ScreenSaver",
+    default_service = "org.freedesktop.ScreenSaver",
+    default_path = "/org/freedesktop/ScreenSaver"
+)]
+trait ScreenSaver {
+    #[dbus_proxy(signal)]
+    fn active_changed(&self, active: bool) -> zbus::Result<()>;
+}
+
+// Separate module since the signal types have the same names
+mod gnome {
+    use zbus::dbus_proxy;
+
+    #[dbus_proxy(
+        interface = "org.gnome.ScreenSaver",
+        default_service = "org.gnome.ScreenSaver",
+        default_path = "/org/gnome/ScreenSaver"
+    )]
+    trait ScreenSaver {
+        #[dbus_proxy(signal)]
+        fn active_changed(&self, active: bool) -> zbus::Result<()>;
+    }
+}
+
+/// What got changed on lock
+#[derive(Default)]
+struct Restore {
+    paused: bool,
+    // Previous values of the ambient settings by device address
+    settings: HashMap<String, Vec<(&'static str, Value)>>,
+}
+
+/// Apply the lock actions of all devices until the daemon exits
+pub async fn run(cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) {
+    let connection = match Connection::session().await {
+        Ok(c) => c,
+        Err(err) => {
+            error!("Can't listen for screen locks: {}", err);
+            return;
+        }
+    };
+
+    let freedesktop = match ScreenSaverProxy::new(&connection).await {
+        Ok(p) => p,
+        Err(err) => {
+            error!("Can't listen for screen locks: {}", err);
+            return;
+        }
+    };
+    let gnome = match gnome::ScreenSaverProxy::new(&connection).await {
+        Ok(p) => p,
+        Err(err) => {
+            error!("Can't listen for screen locks: {}", err);
+            return;
+        }
+    };
+
+    let (freedesktop, gnome) = match (
+        freedesktop.receive_active_changed().await,
+        gnome.receive_active_changed().await,
+    ) {
+        (Ok(f), Ok(g)) => (f, g),
+        (Err(err), _) | (_, Err(err)) => {
+            error!("Can't listen for screen locks: {}", err);
+            return;
+        }
+    };
+
+    let mut changes = freedesktop
+        .filter_map(|i| i.args().ok().map(|a| *a.active()))
+        .or(gnome.filter_map(|i| i.args().ok().map(|a| *a.active())));
+
+    let mut locked = false;
+    let mut restore = Restore::default();
+
+    while let Some(active) = changes.next().await {
+        // Both interfaces might announce the same change
+        if active == locked {
+            continue;
+        }
+        locked = active;
+
+        if locked {
+            on_lock(&cd, &config, &mut restore).await;
+        } else {
+            on_unlock(&cd, &mut restore).await;
+        }
+    }
+}
+
+async fn on_lock(
+    cd: &Arc<Mutex<ConnectionData>>,
+    config: &Arc<Mutex<Config>>,
+    restore: &mut Restore,
+) {
+    let connected: Vec<String> = cd.lock().await.data.keys().cloned().collect();
+    let actions: Vec<(String, bool, bool)> = {
+        let cfg = config.lock().await;
+        connected
+            .into_iter()
+            .filter_map(|addr| {
+                let (pause, ambient) = cfg.get_device_config(&addr)?.lock_action();
+                Some((addr, pause, ambient))
+            })
+            .collect()
+    };
+
+    if actions.iter().any(|i| i.1) {
+        info!("Session locked, pausing playback");
+        restore.paused = task::spawn_blocking(try_pause).await;
+    }
+
+    for (addr, _, _) in actions.iter().filter(|i| i.2) {
+        match enable_ambient(cd, addr).await {
+            Ok(Some(previous)) => {
+                info!("Session locked, enabled ambient sound of {}", addr);
+                restore.settings.insert(addr.clone(), previous);
+            }
+            Ok(None) => (),
+            Err(err) => eprintln!("Can't enable ambient sound on lock: {}", err),
+        }
+    }
+}
+
+async fn on_unlock(cd: &Arc<Mutex<ConnectionData>>, restore: &mut Restore) {
+    if restore.paused {
+        info!("Session unlocked, resuming playback");
+        task::spawn_blocking(try_play).await;
+    }
+
+    for (addr, previous) in restore.settings.drain() {
+        let mut connection_data = cd.lock().await;
+        let info = match connection_data.get_device_mut(&addr) {
+            Some(info) => info,
+            None => continue,
+        };
+
+        // Turn ambient sound off before enabling noise reduction again
+        for (key, value) in previous.iter().rev() {
+            if let Err(err) = settings::set(info, key, &value.to_string(), None, false).await {
+                eprintln!("Can't restore {} after unlock: {}", key, err);
+            }
+        }
+    }
+
+    *restore = Restore::default();
+}
+
+// Switch a device to ambient sound. Returns the previous values if anything got changed
+async fn enable_ambient(
+    cd: &Arc<Mutex<ConnectionData>>,
+    addr: &str,
+) -> Result<Option<Vec<(&'static str, Value)>>, String> {
+    let mut connection_data = cd.lock().await;
+    let info = match connection_data.get_device_mut(addr) {
+        Some(info) if info.inner.ready => info,
+        _ => return Ok(None),
+    };
+
+    if info.inner.ambient_sound_enabled && !info.inner.noise_reduction {
+        return Ok(None);
+    }
+
+    // Models without noise reduction don't have the setting
+    let previous: Vec<(&'static str, Value)> = AMBIENT_SETTINGS
+        .iter()
+        .filter_map(|key| Some((*key, settings::get(&info.inner, key, None).ok()?)))
+        .collect();
+
+    let level = info.inner.ambient_sound_volume.max(1);
+    for (key, _) in previous.iter() {
+        let value = match *key {
+            "noise_reduction" => "false".to_owned(),
+            _ => level.to_string(),
+        };
+        settings::set(info, key, &value, None, false).await?;
+    }
+
+    Ok(Some(previous))
+}
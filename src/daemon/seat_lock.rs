@@ -0,0 +1,91 @@
+use log::warn;
+use nix::fcntl::{flock, FlockArg};
+use nix::unistd::{access, AccessFlags};
+
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::os::unix::{fs::OpenOptionsExt, io::AsRawFd};
+use std::path::{Path, PathBuf};
+
+// Shared by all users if writable. The sticky bit keeps others from replacing
+// our lock files. Many distributions only let root write to it
+const LOCK_DIR: &str = "/run/lock";
+
+/// An exclusive lock on a device, shared by the daemons of all users. Only the
+/// daemon holding it talks to the buds, so daemons of different seats don't fight
+/// over the RFCOMM connection. The lock gets released on drop.
+#[derive(Debug)]
+pub struct SeatLock {
+    // None if the lock can't be shared with other users
+    _file: Option<File>,
+}
+
+impl SeatLock {
+    /// Try to acquire the lock for a device without blocking
+    pub fn acquire(address: &str) -> Result<Self, String> {
+        let path = match get_path(address) {
+            Some(path) => path,
+            None => return Ok(Self { _file: None }),
+        };
+
+        let file = match open(&path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::PermissionDenied => {
+                warn!(
+                    "Can't open lock file {}: {}. Not coordinating with daemons of other users",
+                    path.display(),
+                    err
+                );
+                return Ok(Self { _file: None });
+            }
+            Err(err) => return Err(format!("Can't open lock file {}: {}", path.display(), err)),
+        };
+
+        flock(file.as_raw_fd(), FlockArg::LockExclusiveNonblock)
+            .map_err(|_| "Device is controlled by the daemon of another user".to_string())?;
+
+        Ok(Self { _file: Some(file) })
+    }
+}
+
+// A flock doesn't need write access, so daemons of other users can lock the
+// file read only. Symlinks are never followed
+fn open(path: &Path) -> std::io::Result<File> {
+    let open_existing = || {
+        OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOFOLLOW)
+            .open(path)
+    };
+
+    match open_existing() {
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            let created = OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o644)
+                .custom_flags(libc::O_NOFOLLOW)
+                .open(path);
+
+            // Another daemon created it in the meantime
+            match created {
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => open_existing(),
+                res => res,
+            }
+        }
+        res => res,
+    }
+}
+
+// Without a writable /run/lock only the daemons of the current user get
+// coordinated, without a runtime directory none at all
+fn get_path(address: &str) -> Option<PathBuf> {
+    let name = format!("earbuds-{}.lock", address.replace(':', ""));
+    if access(LOCK_DIR, AccessFlags::W_OK).is_ok() {
+        return Some(Path::new(LOCK_DIR).join(name));
+    }
+
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|i| !i.is_empty())
+        .map(|dir| PathBuf::from(dir).join(name))
+}
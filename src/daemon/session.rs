@@ -0,0 +1,97 @@
+use super::buds_info::BudsInfoInner;
+
+use galaxy_buds_rs::message::bud_property::Placement;
+use serde::Serialize;
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Keeps track of a connection to calculate a summary on disconnect
+#[derive(Debug, Clone)]
+pub struct Session {
+    started: SystemTime,
+    last_update: Instant,
+    connected: Duration,
+    worn: Duration,
+    anc_worn: Duration,
+    // Battery levels of the first status update
+    battery_start: Option<(i8, i8)>,
+}
+
+/// Summary of a finished session
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    pub device: String,
+    pub started: u64,
+    pub duration_secs: u64,
+    pub worn_secs: u64,
+    pub battery_used_left: i8,
+    pub battery_used_right: i8,
+    // Percentage of the worn time with ANC turned on
+    pub anc_percent: u8,
+}
+
+impl Session {
+    pub fn new() -> Self {
+        Self {
+            started: SystemTime::now(),
+            last_update: Instant::now(),
+            connected: Duration::default(),
+            worn: Duration::default(),
+            anc_worn: Duration::default(),
+            battery_start: None,
+        }
+    }
+
+    /// Account the time since the last update to the given (previous) state
+    pub fn update(&mut self, state: &BudsInfoInner) {
+        let elapsed = self.last_update.elapsed();
+        self.last_update = Instant::now();
+        self.connected += elapsed;
+
+        // Count single bud usage as worn as well
+        if state.placement_left == Placement::Ear || state.placement_right == Placement::Ear {
+            self.worn += elapsed;
+            if state.noise_reduction {
+                self.anc_worn += elapsed;
+            }
+        }
+
+        if self.battery_start.is_none() && state.batt_left > 0 && state.batt_right > 0 {
+            self.battery_start = Some((state.batt_left, state.batt_right));
+        }
+    }
+
+    /// Time the connection was established
+    pub fn started(&self) -> SystemTime {
+        self.started
+    }
+
+    /// Finish the session and calculate its summary
+    pub fn summary(&mut self, state: &BudsInfoInner) -> SessionSummary {
+        self.update(state);
+
+        let (start_left, start_right) = self
+            .battery_start
+            .unwrap_or((state.batt_left, state.batt_right));
+
+        let anc_percent = if self.worn.as_secs() > 0 {
+            (self.anc_worn.as_secs() * 100 / self.worn.as_secs()) as u8
+        } else {
+            0
+        };
+
+        SessionSummary {
+            device: state.address.clone(),
+            started: self
+                .started
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            duration_secs: self.connected.as_secs(),
+            worn_secs: self.worn.as_secs(),
+            battery_used_left: (start_left - state.batt_left).max(0),
+            battery_used_right: (start_right - state.batt_right).max(0),
+            anc_percent,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use super::{Setting, Value, ValueType};
+use crate::daemon::{
+    buds_info::BudsInfoInner,
+    raw_message::{ids, RawMessage},
+};
+
+/// Pass through ambient sound during calls
+pub struct AmbientDuringCalls;
+
+impl Setting for AmbientDuringCalls {
+    fn key(&self) -> &'static str {
+        "ambient_during_calls"
+    }
+
+    fn value_type(&self) -> ValueType {
+        ValueType::Bool
+    }
+
+    fn check_supported(&self, info: &BudsInfoInner) -> Result<(), String> {
+        if !info.supports_ambient_during_calls() {
+            return Err("Feature not supported by your model".to_string());
+        }
+        Ok(())
+    }
+
+    fn get(&self, _param: Option<&str>, info: &BudsInfoInner) -> Value {
+        Value::Bool(info.ambient_during_calls.unwrap_or(false))
+    }
+
+    fn is_known(&self, _param: Option<&str>, info: &BudsInfoInner) -> bool {
+        info.ambient_during_calls.is_some()
+    }
+
+    fn encode(
+        &self,
+        value: Value,
+        _param: Option<&str>,
+        info: &BudsInfoInner,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let msg = RawMessage::new(ids::SET_SIDETONE, vec![value.as_bool() as u8]);
+        Ok(vec![msg.to_byte_array(info.model)])
+    }
+
+    fn apply(&self, value: Value, _param: Option<&str>, info: &mut BudsInfoInner) {
+        info.ambient_during_calls = Some(value.as_bool());
+    }
+}
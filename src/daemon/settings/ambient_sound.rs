@@ -0,0 +1,43 @@
+use super::{check_feature, Setting, Value, ValueType};
+use crate::daemon::buds_info::BudsInfoInner;
+
+use galaxy_buds_rs::{
+    message::{ambient_mode, Payload},
+    model::Feature,
+};
+
+/// Ambient sound (passthrough) on or off, keeping its volume level
+pub struct AmbientSound;
+
+impl Setting for AmbientSound {
+    fn key(&self) -> &'static str {
+        "ambient_sound"
+    }
+
+    fn value_type(&self) -> ValueType {
+        ValueType::Bool
+    }
+
+    fn check_supported(&self, info: &BudsInfoInner) -> Result<(), String> {
+        check_feature(info, Feature::AmbientSound)
+    }
+
+    fn get(&self, _param: Option<&str>, info: &BudsInfoInner) -> Value {
+        Value::Bool(info.ambient_sound_enabled)
+    }
+
+    fn encode(
+        &self,
+        value: Value,
+        _param: Option<&str>,
+        _info: &BudsInfoInner,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        Ok(vec![
+            ambient_mode::SetAmbientMode::new(value.as_bool()).to_byte_array()
+        ])
+    }
+
+    fn apply(&self, value: Value, _param: Option<&str>, info: &mut BudsInfoInner) {
+        info.ambient_sound_enabled = value.as_bool();
+    }
+}
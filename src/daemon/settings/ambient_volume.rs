@@ -0,0 +1,97 @@
+use super::{check_feature, Setting, Value, ValueType};
+use crate::daemon::buds_info::BudsInfoInner;
+
+use galaxy_buds_rs::{
+    message::{ambient_mode, Payload},
+    model::Feature,
+};
+
+/// Ambient sound volume. Level 0 turns ambient sound off
+pub struct AmbientVolume;
+
+impl Setting for AmbientVolume {
+    fn key(&self) -> &'static str {
+        "ambient_volume"
+    }
+
+    fn value_type(&self) -> ValueType {
+        ValueType::Number
+    }
+
+    fn check_supported(&self, info: &BudsInfoInner) -> Result<(), String> {
+        check_feature(info, Feature::AmbientSound)
+    }
+
+    fn values(&self, info: &BudsInfoInner) -> Vec<(u8, Option<&'static str>)> {
+        (0..=info.get_max_ambientsound_volume_level())
+            .map(|i| (i, if i == 0 { Some("off") } else { None }))
+            .collect()
+    }
+
+    fn get(&self, _param: Option<&str>, info: &BudsInfoInner) -> Value {
+        if info.ambient_sound_enabled {
+            Value::Number(info.ambient_sound_volume)
+        } else {
+            Value::Number(0)
+        }
+    }
+
+    fn encode(
+        &self,
+        value: Value,
+        _param: Option<&str>,
+        info: &BudsInfoInner,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let val = value.as_number();
+        if val > info.get_max_ambientsound_volume_level() {
+            return Err("Invalid volume level".to_string());
+        }
+
+        let mut frames = Vec::new();
+        let mut enabled = info.ambient_sound_enabled;
+
+        // Enable/disable extra high ambient volume if needed or not.
+        if info.has_feature(Feature::ExtraHighAmbientVolume) {
+            if val == 4 && !info.extra_high_ambient_volume {
+                // Enable ambient sound if not already enabled
+                if !enabled {
+                    frames.push(ambient_mode::SetAmbientMode::new(true).to_byte_array());
+                    enabled = true;
+                }
+
+                frames.push(ambient_mode::SetExtraHighVolume::new(true).to_byte_array());
+            } else if val != 4 && info.extra_high_ambient_volume {
+                frames.push(ambient_mode::SetExtraHighVolume::new(false).to_byte_array());
+            }
+        }
+
+        // Enable/disable the ambient mode feature
+        if val == 0 && enabled {
+            // Don't set the volume after disabling it
+            frames.push(ambient_mode::SetAmbientMode::new(false).to_byte_array());
+            return Ok(frames);
+        } else if val != 0 && !enabled {
+            frames.push(ambient_mode::SetAmbientMode::new(true).to_byte_array());
+        }
+
+        // We don't need to send volume 0 to the buds
+        if val != 0 {
+            frames.push(ambient_mode::SetAmbientVolume::new(val).to_byte_array());
+        }
+
+        Ok(frames)
+    }
+
+    fn apply(&self, value: Value, _param: Option<&str>, info: &mut BudsInfoInner) {
+        let val = value.as_number();
+
+        if info.has_feature(Feature::ExtraHighAmbientVolume) {
+            info.extra_high_ambient_volume = val == 4;
+        }
+
+        info.ambient_sound_enabled = val != 0;
+        if val != 0 {
+            info.ambient_sound_volume = val;
+        }
+    }
+}
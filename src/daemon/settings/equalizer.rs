@@ -0,0 +1,53 @@
+use super::{Setting, Value, ValueType};
+use crate::daemon::{buds_info::BudsInfoInner, labels};
+
+use galaxy_buds_rs::message::{
+    bud_property::{BudProperty, EqualizerType},
+    simple::new_equalizer,
+    Payload,
+};
+
+/// Equalizer preset
+pub struct Equalizer;
+
+impl Setting for Equalizer {
+    fn key(&self) -> &'static str {
+        "equalizer"
+    }
+
+    fn value_type(&self) -> ValueType {
+        ValueType::Number
+    }
+
+    fn values(&self, _info: &BudsInfoInner) -> Vec<(u8, Option<&'static str>)> {
+        [
+            EqualizerType::Normal,
+            EqualizerType::BassBoost,
+            EqualizerType::Soft,
+            EqualizerType::Dynamic,
+            EqualizerType::Clear,
+            EqualizerType::TrebleBoost,
+        ]
+        .iter()
+        .map(|i| (i.encode(), Some(labels::equalizer_key(*i))))
+        .collect()
+    }
+
+    fn get(&self, _param: Option<&str>, info: &BudsInfoInner) -> Value {
+        Value::Number(info.equalizer_type.encode())
+    }
+
+    fn encode(
+        &self,
+        value: Value,
+        _param: Option<&str>,
+        _info: &BudsInfoInner,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let eq_type = EqualizerType::decode(value.as_number());
+        Ok(vec![new_equalizer(eq_type).to_byte_array()])
+    }
+
+    fn apply(&self, value: Value, _param: Option<&str>, info: &mut BudsInfoInner) {
+        info.equalizer_type = EqualizerType::decode(value.as_number());
+    }
+}
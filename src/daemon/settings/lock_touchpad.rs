@@ -0,0 +1,129 @@
+use super::{Setting, Value, ValueType};
+use crate::daemon::buds_info::BudsInfoInner;
+
+use galaxy_buds_rs::{
+    message::{
+        lock_touchpad::{self, ExtLockTouchpad},
+        Payload,
+    },
+    model::Feature,
+};
+
+/// Touchpad lock. Models with extended lock support can lock single
+/// functions selected by the optional parameter
+pub struct LockTouchpad;
+
+impl LockTouchpad {
+    // Build the extended lock message for a value
+    fn ext_message(
+        &self,
+        value: Value,
+        param: Option<&str>,
+        info: &BudsInfoInner,
+    ) -> Result<ExtLockTouchpad, String> {
+        let mut msg = ExtLockTouchpad::from_ext_tap_lock_status(info.tab_lock_status);
+
+        // 'value' is true if lock but we want to know when to enable them here
+        let val = !value.as_bool();
+
+        match param.map(|i| i.to_lowercase()) {
+            Some(p) => match p.as_str() {
+                "tap" => msg.tap_on = val,
+                "doubletap" | "dtap" => msg.double_tap = val,
+                "tripletap" | "ttap" => msg.tripple_tap = val,
+                "hold" => msg.touch_and_hold = val,
+                _ => {
+                    return Err(format!(
+                        "Unsupported touchpad function {:?}. Choose from {{tap, doubletap, tripletap, hold}}",
+                        p
+                    ))
+                }
+            },
+            None => {
+                msg.tap_on = val;
+                msg.touch_and_hold = val;
+            }
+        }
+        msg.touch_controls = true;
+
+        Ok(msg)
+    }
+}
+
+impl Setting for LockTouchpad {
+    fn key(&self) -> &'static str {
+        "lock_touchpad"
+    }
+
+    fn value_type(&self) -> ValueType {
+        ValueType::Bool
+    }
+
+    // Models without extended lock support report the same value for all functions
+    fn params(&self) -> &'static [Option<&'static str>] {
+        &[
+            None,
+            Some("tap"),
+            Some("doubletap"),
+            Some("tripletap"),
+            Some("hold"),
+        ]
+    }
+
+    // Without a function, tap and hold get locked
+    fn affected_params(&self, param: Option<&str>) -> Vec<Option<&'static str>> {
+        match param.map(|i| i.to_lowercase()).as_deref() {
+            None => vec![Some("tap"), Some("hold")],
+            Some("tap") => vec![Some("tap")],
+            Some("doubletap") | Some("dtap") => vec![Some("doubletap")],
+            Some("tripletap") | Some("ttap") => vec![Some("tripletap")],
+            Some("hold") => vec![Some("hold")],
+            Some(_) => vec![],
+        }
+    }
+
+    fn get(&self, param: Option<&str>, info: &BudsInfoInner) -> Value {
+        if !info.has_feature(Feature::ExtTouchpadLock) {
+            return Value::Bool(info.touchpads_blocked);
+        }
+
+        // Locked means the function is disabled
+        let status = &info.tab_lock_status;
+        let locked = match param.map(|i| i.to_lowercase()).as_deref() {
+            Some("tap") => !status.tap_on,
+            Some("doubletap") | Some("dtap") => !status.double_tap_on,
+            Some("tripletap") | Some("ttap") => !status.triple_tap_on,
+            Some("hold") => !status.touch_an_hold_on,
+            _ => !status.tap_on && !status.touch_an_hold_on,
+        };
+        Value::Bool(locked)
+    }
+
+    fn encode(
+        &self,
+        value: Value,
+        param: Option<&str>,
+        info: &BudsInfoInner,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        if info.has_feature(Feature::ExtTouchpadLock) {
+            return Ok(vec![self.ext_message(value, param, info)?.to_byte_array()]);
+        }
+
+        Ok(vec![lock_touchpad::new(value.as_bool()).to_byte_array()])
+    }
+
+    fn apply(&self, value: Value, param: Option<&str>, info: &mut BudsInfoInner) {
+        if !info.has_feature(Feature::ExtTouchpadLock) {
+            info.touchpads_blocked = value.as_bool();
+            return;
+        }
+
+        if let Ok(msg) = self.ext_message(value, param, info) {
+            info.tab_lock_status.tap_on = msg.tap_on;
+            info.tab_lock_status.triple_tap_on = msg.tripple_tap;
+            info.tab_lock_status.double_tap_on = msg.double_tap;
+            info.tab_lock_status.touch_controls_on = msg.touch_controls;
+            info.tab_lock_status.touch_an_hold_on = msg.touch_and_hold;
+        }
+    }
+}
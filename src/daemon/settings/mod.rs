@@ -0,0 +1,401 @@
+/*
+ * Settings of the buds which can be set, toggled and read. Adding a new
+ * setting means implementing `Setting` in its own module and listing it
+ * in `SETTINGS`.
+ */
+
+mod ambient_during_calls;
+mod ambient_sound;
+mod ambient_volume;
+mod equalizer;
+mod lock_touchpad;
+pub mod noise_control;
+mod noise_reduction;
+mod touchpad_action;
+
+use super::buds_info::{BudsInfo, BudsInfoInner};
+use super::firmware::FirmwareVersion;
+use super::{models, parse};
+
+use galaxy_buds_rs::model::Feature;
+use serde::{Deserialize, Serialize};
+
+use std::fmt;
+
+/// A single setting of the buds
+pub trait Setting: Sync {
+    /// Key used in requests
+    fn key(&self) -> &'static str;
+
+    /// Type of the settings value
+    fn value_type(&self) -> ValueType;
+
+    /// Returns an error if the model doesn't support this setting
+    fn check_supported(&self, _info: &BudsInfoInner) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Parameters the setting has separate values for
+    fn params(&self) -> &'static [Option<&'static str>] {
+        &[None]
+    }
+
+    /// Parameters of `params` a value set with `param` applies to. Empty if
+    /// `param` selects something without a separate value (eg. a single touchpad function)
+    fn affected_params(&self, param: Option<&str>) -> Vec<Option<&'static str>> {
+        self.params()
+            .iter()
+            .filter(|i| param.is_none() || **i == param)
+            .copied()
+            .collect()
+    }
+
+    /// Values accepted by a number setting and their names
+    fn values(&self, _info: &BudsInfoInner) -> Vec<(u8, Option<&'static str>)> {
+        Vec::new()
+    }
+
+    /// The current value of the setting
+    fn get(&self, param: Option<&str>, info: &BudsInfoInner) -> Value;
+
+    /// Returns false if the buds don't report the value and the daemon hasn't set it yet
+    fn is_known(&self, _param: Option<&str>, _info: &BudsInfoInner) -> bool {
+        true
+    }
+
+    /// Encode the message frames setting the value on the buds
+    fn encode(
+        &self,
+        value: Value,
+        param: Option<&str>,
+        info: &BudsInfoInner,
+    ) -> Result<Vec<Vec<u8>>, String>;
+
+    /// Apply a successfully sent value to the local state
+    fn apply(&self, value: Value, param: Option<&str>, info: &mut BudsInfoInner);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueType {
+    Bool,
+    Number,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Value {
+    Bool(bool),
+    Number(u8),
+}
+
+static SETTINGS: &[&dyn Setting] = &[
+    &noise_reduction::NoiseReduction,
+    &noise_control::NoiseControl,
+    &ambient_sound::AmbientSound,
+    &ambient_volume::AmbientVolume,
+    &ambient_during_calls::AmbientDuringCalls,
+    &equalizer::Equalizer,
+    &lock_touchpad::LockTouchpad,
+    &touchpad_action::TouchpadAction,
+];
+
+/// Returns the setting with the given key
+pub fn find(key: &str) -> Option<&'static dyn Setting> {
+    SETTINGS.iter().find(|i| i.key() == key).copied()
+}
+
+/// Parse a value of a setting. Returns an error for unknown keys and parameters
+pub fn parse(
+    key: &str,
+    param: Option<&str>,
+    value: &str,
+) -> Result<(&'static dyn Setting, Value), String> {
+    let setting = find(key).ok_or(format!("Invalid key {}", key))?;
+    if param.is_some() && setting.affected_params(param).is_empty() {
+        return Err(format!("Invalid parameter for {}", key));
+    }
+
+    Ok((setting, setting.value_type().parse(value)?))
+}
+
+/// Returns an error if a value can't be set on the buds. Unlike `get`, this
+/// works for values the buds don't report
+pub fn validate(
+    info: &BudsInfoInner,
+    key: &str,
+    param: Option<&str>,
+    value: &str,
+) -> Result<(), String> {
+    let (setting, _) = parse(key, param, value)?;
+    check(setting, info)
+}
+
+/// Parse and set a value on the buds. Nothing gets sent if the value is already
+/// set, unless `force` is true. Returns false if nothing was sent
+pub async fn set(
+    info: &mut BudsInfo,
+    key: &str,
+    value: &str,
+    param: Option<&str>,
+    force: bool,
+) -> Result<bool, String> {
+    let setting = find(key).ok_or("Invalid key to set to")?;
+    let value = setting.value_type().parse(value)?;
+
+    // The state is only known after the first extended status update
+    if !force && info.inner.ready && is_set(setting, value, param, &info.inner) {
+        check(setting, &info.inner)?;
+        remember(info, setting, value, param);
+        return Ok(false);
+    }
+
+    set_value(info, setting, value, param).await?;
+    Ok(true)
+}
+
+// Returns true if every value a set with `param` changes already is `value`
+fn is_set(setting: &dyn Setting, value: Value, param: Option<&str>, info: &BudsInfoInner) -> bool {
+    let affected = setting.affected_params(param);
+    if affected.is_empty() {
+        return setting.is_known(param, info) && setting.get(param, info) == value;
+    }
+
+    affected
+        .into_iter()
+        .all(|i| setting.is_known(i, info) && setting.get(i, info) == value)
+}
+
+/// Invert a boolean setting on the buds. Returns the new value
+pub async fn toggle(info: &mut BudsInfo, key: &str, param: Option<&str>) -> Result<Value, String> {
+    let setting = find(key).ok_or("Invalid key")?;
+    if !setting.is_known(param, &info.inner) {
+        return Err(unknown(setting));
+    }
+
+    let value = match setting.get(param, &info.inner) {
+        Value::Bool(v) => Value::Bool(!v),
+        Value::Number(_) => return Err("Only boolean settings can be toggled".to_string()),
+    };
+
+    set_value(info, setting, value, param).await?;
+    Ok(value)
+}
+
+/// Returns the current value of a setting
+pub fn get(info: &BudsInfoInner, key: &str, param: Option<&str>) -> Result<Value, String> {
+    let setting = find(key).ok_or("Invalid key")?;
+    check(setting, info)?;
+    if !setting.is_known(param, info) {
+        return Err(unknown(setting));
+    }
+    Ok(setting.get(param, info))
+}
+
+fn unknown(setting: &dyn Setting) -> String {
+    format!(
+        "The buds don't report the current value of {}. Set it once to make it known",
+        setting.key()
+    )
+}
+
+/// Send a value to the buds and apply it to the local state
+pub async fn set_value(
+    info: &mut BudsInfo,
+    setting: &dyn Setting,
+    value: Value,
+    param: Option<&str>,
+) -> Result<(), String> {
+    check(setting, &info.inner)?;
+
+    for frame in setting.encode(value, param, &info.inner)? {
+        info.write_frame(&frame).await?;
+    }
+
+    setting.apply(value, param, &mut info.inner);
+    remember(info, setting, value, param);
+
+    Ok(())
+}
+
+// Remember what the user wants to detect changes made by others
+fn remember(info: &mut BudsInfo, setting: &dyn Setting, value: Value, param: Option<&str>) {
+    if info.dry_run.is_some() {
+        return;
+    }
+
+    for param in setting.affected_params(param) {
+        info.desired_settings.insert((setting.key(), param), value);
+    }
+}
+
+/// Returns the keys of all supported settings
+pub fn supported(info: &BudsInfoInner) -> Vec<&'static str> {
+    SETTINGS
+        .iter()
+        .filter(|i| check(**i, info).is_ok())
+        .map(|i| i.key())
+        .collect()
+}
+
+/// A value accepted by a key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AllowedValue {
+    pub value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+}
+
+/// Description of a key for clients generating their UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyInfo {
+    pub key: String,
+    // "setting" of the buds or "config" of the daemon
+    pub kind: String,
+    pub value_type: ValueType,
+    pub toggleable: bool,
+    // Sides with separate values, eg. "left"
+    pub params: Vec<String>,
+    pub values: Vec<AllowedValue>,
+    pub supported: bool,
+    // Why the device doesn't support the key
+    pub requirement: Option<String>,
+}
+
+impl KeyInfo {
+    /// Describe a boolean key
+    pub fn new_bool(key: &str, kind: &str) -> Self {
+        Self {
+            key: key.to_owned(),
+            kind: kind.to_owned(),
+            value_type: ValueType::Bool,
+            toggleable: true,
+            params: vec![],
+            values: [true, false]
+                .iter()
+                .map(|i| AllowedValue {
+                    value: Value::Bool(*i),
+                    name: None,
+                })
+                .collect(),
+            supported: true,
+            requirement: None,
+        }
+    }
+}
+
+/// Describe all settings for the given device
+pub fn describe(info: &BudsInfoInner) -> Vec<KeyInfo> {
+    SETTINGS
+        .iter()
+        .map(|setting| {
+            let requirement = check(*setting, info).err();
+            let value_type = setting.value_type();
+
+            let mut key = KeyInfo::new_bool(setting.key(), "setting");
+            if value_type == ValueType::Number {
+                key.value_type = value_type;
+                key.toggleable = false;
+                key.values = setting
+                    .values(info)
+                    .into_iter()
+                    .map(|(value, name)| AllowedValue {
+                        value: Value::Number(value),
+                        name: name.map(|i| i.to_owned()),
+                    })
+                    .collect();
+            }
+
+            key.params = setting
+                .params()
+                .iter()
+                .flatten()
+                .map(|i| i.to_string())
+                .collect();
+            key.supported = requirement.is_none();
+            key.requirement = requirement;
+            key
+        })
+        .collect()
+}
+
+/// Returns the values of all supported settings
+pub fn snapshot(info: &BudsInfoInner) -> Vec<(&'static str, Option<&'static str>, Value)> {
+    SETTINGS
+        .iter()
+        .filter(|i| check(**i, info).is_ok())
+        .flat_map(|setting| {
+            setting
+                .params()
+                .iter()
+                .filter(move |param| setting.is_known(**param, info))
+                .map(move |param| (setting.key(), *param, setting.get(*param, info)))
+        })
+        .collect()
+}
+
+// Returns an error if the model or its firmware doesn't support a setting
+fn check(setting: &dyn Setting, info: &BudsInfoInner) -> Result<(), String> {
+    if info.battery_only {
+        return Err("Unknown model, only the battery can be read".to_string());
+    }
+    setting.check_supported(info)?;
+
+    // Allow everything as long as the firmware is unknown
+    let min = models::get(info.model)
+        .min_firmware(setting.key())
+        .and_then(FirmwareVersion::parse);
+    match (min, info.firmware) {
+        (Some(min), Some(firmware)) if firmware < min => Err(format!(
+            "UnsupportedOnFirmware: {} needs firmware {} or newer, installed is {}",
+            setting.key(),
+            min,
+            firmware
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Checks a given feature and returns an error if the feature is unsupported.
+fn check_feature(info: &BudsInfoInner, feature: Feature) -> Result<(), String> {
+    if !info.has_feature(feature) {
+        Err("Feature not supported by your model".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+impl ValueType {
+    /// Parse a value of this type
+    pub fn parse(&self, s: &str) -> Result<Value, String> {
+        match self {
+            ValueType::Bool => parse::bool(s).map(Value::Bool),
+            ValueType::Number => parse::number::<u8>(s).map(Value::Number),
+        }
+    }
+}
+
+impl Value {
+    pub fn as_bool(&self) -> bool {
+        match *self {
+            Value::Bool(v) => v,
+            Value::Number(v) => v != 0,
+        }
+    }
+
+    pub fn as_number(&self) -> u8 {
+        match *self {
+            Value::Bool(v) => v as u8,
+            Value::Number(v) => v,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Bool(v) => write!(f, "{}", v),
+            Value::Number(v) => write!(f, "{}", v),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use super::{Setting, Value, ValueType};
+use crate::daemon::buds_info::BudsInfoInner;
+use crate::daemon::models;
+use crate::daemon::raw_message::{ids, RawMessage};
+
+pub const OFF: u8 = 0;
+pub const AMBIENT: u8 = 1;
+pub const ANC_LOW: u8 = 2;
+pub const ANC_HIGH: u8 = 3;
+
+// Modes of the NOISE_CONTROLS message
+const MODE_OFF: u8 = 0;
+const MODE_ANC: u8 = 1;
+const MODE_AMBIENT: u8 = 2;
+
+/// Noise controls of newer buds, switching between off, ambient
+/// sound and ANC with its intensity
+pub struct NoiseControl;
+
+impl Setting for NoiseControl {
+    fn key(&self) -> &'static str {
+        "noise_control"
+    }
+
+    fn value_type(&self) -> ValueType {
+        ValueType::Number
+    }
+
+    fn check_supported(&self, info: &BudsInfoInner) -> Result<(), String> {
+        if models::get(info.model).supports_noise_controls() {
+            Ok(())
+        } else {
+            Err("Feature not supported by your model".to_string())
+        }
+    }
+
+    fn values(&self, info: &BudsInfoInner) -> Vec<(u8, Option<&'static str>)> {
+        let mut values = vec![OFF, AMBIENT, ANC_HIGH];
+        if models::get(info.model).supports_anc_level() {
+            values.insert(2, ANC_LOW);
+        }
+        values.into_iter().map(|i| (i, Some(name(i)))).collect()
+    }
+
+    fn get(&self, _param: Option<&str>, info: &BudsInfoInner) -> Value {
+        Value::Number(if info.noise_reduction {
+            // Buds without adjustable level always cancel at full strength
+            if models::get(info.model).supports_anc_level() && info.noise_reduction_level == Some(0)
+            {
+                ANC_LOW
+            } else {
+                ANC_HIGH
+            }
+        } else if info.ambient_sound_enabled {
+            AMBIENT
+        } else {
+            OFF
+        })
+    }
+
+    // The buds only report whether ANC is on, not its level
+    fn is_known(&self, _param: Option<&str>, info: &BudsInfoInner) -> bool {
+        !info.noise_reduction
+            || !models::get(info.model).supports_anc_level()
+            || info.noise_reduction_level.is_some()
+    }
+
+    fn encode(
+        &self,
+        value: Value,
+        _param: Option<&str>,
+        info: &BudsInfoInner,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let model = models::get(info.model);
+        let mode = match value.as_number() {
+            OFF => MODE_OFF,
+            AMBIENT => MODE_AMBIENT,
+            ANC_LOW | ANC_HIGH => MODE_ANC,
+            _ => return Err("Invalid noise control mode".to_string()),
+        };
+
+        let mut frames =
+            vec![RawMessage::new(ids::NOISE_CONTROLS, vec![mode]).to_byte_array(info.model)];
+
+        if mode == MODE_ANC && model.supports_anc_level() {
+            let level = value.as_number() - ANC_LOW;
+            frames.push(
+                RawMessage::new(ids::NOISE_REDUCTION_LEVEL, vec![level]).to_byte_array(info.model),
+            );
+        } else if value.as_number() == ANC_LOW {
+            return Err(format!("The {} have no ANC levels", model.name()));
+        }
+
+        Ok(frames)
+    }
+
+    fn apply(&self, value: Value, _param: Option<&str>, info: &mut BudsInfoInner) {
+        let value = value.as_number();
+        info.noise_reduction = value == ANC_LOW || value == ANC_HIGH;
+        info.ambient_sound_enabled = value == AMBIENT;
+        if info.noise_reduction {
+            info.noise_reduction_level = Some(value - ANC_LOW);
+        }
+    }
+}
+
+/// Apply a NOISE_CONTROLS_UPDATE sent after changing the mode on the buds
+pub fn handle_update(payload: &[u8], info: &mut BudsInfoInner) {
+    if let Some(mode) = payload.first() {
+        info.noise_reduction = *mode == MODE_ANC;
+        info.ambient_sound_enabled = *mode == MODE_AMBIENT;
+    }
+}
+
+/// Parse a mode like 'low' or 'ambient'
+pub fn parse(value: &str) -> Option<u8> {
+    Some(match value.to_lowercase().as_str() {
+        "off" => OFF,
+        "ambient" => AMBIENT,
+        "low" => ANC_LOW,
+        "high" => ANC_HIGH,
+        _ => return None,
+    })
+}
+
+/// Returns the name of a mode as accepted by `parse`
+pub fn name(value: u8) -> &'static str {
+    match value {
+        OFF => "off",
+        AMBIENT => "ambient",
+        ANC_LOW => "low",
+        ANC_HIGH => "high",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,43 @@
+use super::{check_feature, Setting, Value, ValueType};
+use crate::daemon::buds_info::BudsInfoInner;
+
+use galaxy_buds_rs::{
+    message::{set_noise_reduction, Payload},
+    model::Feature,
+};
+
+/// Active noise cancelling
+pub struct NoiseReduction;
+
+impl Setting for NoiseReduction {
+    fn key(&self) -> &'static str {
+        "noise_reduction"
+    }
+
+    fn value_type(&self) -> ValueType {
+        ValueType::Bool
+    }
+
+    fn check_supported(&self, info: &BudsInfoInner) -> Result<(), String> {
+        check_feature(info, Feature::Anc)
+    }
+
+    fn get(&self, _param: Option<&str>, info: &BudsInfoInner) -> Value {
+        Value::Bool(info.noise_reduction)
+    }
+
+    fn encode(
+        &self,
+        value: Value,
+        _param: Option<&str>,
+        _info: &BudsInfoInner,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        Ok(vec![
+            set_noise_reduction::new(value.as_bool()).to_byte_array()
+        ])
+    }
+
+    fn apply(&self, value: Value, _param: Option<&str>, info: &mut BudsInfoInner) {
+        info.noise_reduction = value.as_bool();
+    }
+}
@@ -0,0 +1,91 @@
+use super::{Setting, Value, ValueType};
+use crate::daemon::{buds_info::BudsInfoInner, utils};
+
+use galaxy_buds_rs::message::{
+    bud_property::{BudProperty, Side, TouchpadOption},
+    set_touchpad_option, Payload,
+};
+
+/// Touch and hold action. The optional parameter selects a single side
+pub struct TouchpadAction;
+
+impl TouchpadAction {
+    // Returns the (left, right) options after applying the value
+    fn options(
+        &self,
+        value: Value,
+        param: Option<&str>,
+        info: &BudsInfoInner,
+    ) -> Result<(TouchpadOption, TouchpadOption), String> {
+        let option = TouchpadOption::decode(value.as_number());
+
+        match param {
+            Some(side) => match utils::str_to_side(side).ok_or("Invalid side")? {
+                Side::Left => Ok((option, info.touchpad_option_right)),
+                Side::Right => Ok((info.touchpad_option_left, option)),
+            },
+            None => Ok((option, option)),
+        }
+    }
+}
+
+impl Setting for TouchpadAction {
+    fn key(&self) -> &'static str {
+        "touchpad_action"
+    }
+
+    fn value_type(&self) -> ValueType {
+        ValueType::Number
+    }
+
+    fn params(&self) -> &'static [Option<&'static str>] {
+        &[Some("left"), Some("right")]
+    }
+
+    fn affected_params(&self, param: Option<&str>) -> Vec<Option<&'static str>> {
+        match param.map(utils::str_to_side) {
+            None => self.params().to_vec(),
+            Some(Some(Side::Left)) => vec![Some("left")],
+            Some(Some(Side::Right)) => vec![Some("right")],
+            Some(None) => vec![],
+        }
+    }
+
+    fn values(&self, _info: &BudsInfoInner) -> Vec<(u8, Option<&'static str>)> {
+        [
+            TouchpadOption::VoiceCommand,
+            TouchpadOption::NoiseCanceling,
+            TouchpadOption::Volume,
+            TouchpadOption::Spotify,
+            TouchpadOption::Disconnect,
+        ]
+        .iter()
+        .map(|i| (i.encode(), Some(utils::touchpad_option_to_str(*i))))
+        .collect()
+    }
+
+    fn get(&self, param: Option<&str>, info: &BudsInfoInner) -> Value {
+        let option = match param.and_then(utils::str_to_side) {
+            Some(Side::Right) => info.touchpad_option_right,
+            _ => info.touchpad_option_left,
+        };
+        Value::Number(option.encode())
+    }
+
+    fn encode(
+        &self,
+        value: Value,
+        param: Option<&str>,
+        info: &BudsInfoInner,
+    ) -> Result<Vec<Vec<u8>>, String> {
+        let (left, right) = self.options(value, param, info)?;
+        Ok(vec![set_touchpad_option::new(left, right).to_byte_array()])
+    }
+
+    fn apply(&self, value: Value, param: Option<&str>, info: &mut BudsInfoInner) {
+        if let Ok((left, right)) = self.options(value, param, info) {
+            info.touchpad_option_left = left;
+            info.touchpad_option_right = right;
+        }
+    }
+}
@@ -0,0 +1,109 @@
+/*
+ * Mutes the default sink on auto pause, for a while for apps which ignore
+ * MPRIS or until the buds get put on with the pulseaudio media control
+ * backend. Only a sink muted by the daemon gets unmuted again.
+ *
+ * pactl runs on a blocking thread, since the callers hold the connection data
+ * lock. Requests are ordered by a generation, so a mute finishing after a later
+ * unmute doesn't leave the sink muted.
+ */
+
+#[cfg(feature = "pulse-sink")]
+use super::pactl::pactl;
+
+use async_std::task;
+#[cfg(feature = "pulse-sink")]
+use log::info;
+
+#[cfg(feature = "pulse-sink")]
+use std::sync::Mutex;
+use std::{
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+// Whether the daemon muted the sink
+#[cfg(feature = "pulse-sink")]
+static MUTED: Mutex<bool> = Mutex::new(false);
+// Invalidates pending mutes and unmute timers
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+
+/// Mute the default sink in the background until `unmute`. Returns the
+/// generation of the request
+pub fn mute() -> u64 {
+    let generation = GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    task::spawn_blocking(move || mute_sink(generation));
+    generation
+}
+
+/// Mute the default sink and unmute it after the given duration
+pub fn mute_for(duration: Duration) {
+    let generation = mute();
+
+    task::spawn(async move {
+        task::sleep(duration).await;
+        if GENERATION.load(Ordering::SeqCst) == generation {
+            unmute();
+        }
+    });
+}
+
+/// Unmute the default sink in the background if it was muted by `mute`
+pub fn unmute() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    task::spawn_blocking(unmute_sink);
+}
+
+#[cfg(feature = "pulse-sink")]
+fn mute_sink(generation: u64) {
+    let mut muted = match MUTED.lock() {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    // Already unmuted again
+    if GENERATION.load(Ordering::SeqCst) != generation {
+        return;
+    }
+
+    // Don't take over a mute of the user
+    if !*muted && is_muted().unwrap_or(true) {
+        return;
+    }
+
+    if let Err(err) = pactl(&["set-sink-mute", "@DEFAULT_SINK@", "1"]) {
+        eprintln!("Can't mute default sink: {}", err);
+        return;
+    }
+    *muted = true;
+    info!("Muted default sink");
+}
+
+#[cfg(feature = "pulse-sink")]
+fn unmute_sink() {
+    let mut muted = match MUTED.lock() {
+        Ok(v) => v,
+        Err(_) => return,
+    };
+
+    if !*muted {
+        return;
+    }
+    *muted = false;
+
+    if let Err(err) = pactl(&["set-sink-mute", "@DEFAULT_SINK@", "0"]) {
+        eprintln!("Can't unmute default sink: {}", err);
+    }
+}
+
+#[cfg(feature = "pulse-sink")]
+fn is_muted() -> Result<bool, String> {
+    let output = pactl(&["get-sink-mute", "@DEFAULT_SINK@"])?;
+    Ok(output.trim().ends_with("yes"))
+}
+
+#[cfg(not(feature = "pulse-sink"))]
+fn mute_sink(_generation: u64) {}
+
+#[cfg(not(feature = "pulse-sink"))]
+fn unmute_sink() {}
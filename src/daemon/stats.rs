@@ -0,0 +1,41 @@
+use super::models::UpdateMode;
+
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+/// Counters of a connection, reported by the `get_stats` request
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub frames_received: u64,
+    // Identical status updates which didn't need to be handled
+    #[serde(default)]
+    pub frames_skipped: u64,
+    pub frames_sent: u64,
+    // Writes repeated after a transient error
+    pub write_retries: u64,
+    // Writes which failed after all retries
+    pub write_failures: u64,
+    // Time spent handling received frames, including waiting for the lock
+    #[serde(default)]
+    pub handler_micros: u64,
+    #[serde(default)]
+    pub handler_max_micros: u64,
+    // Whether the model pushes its status or gets polled
+    #[serde(default)]
+    pub update_mode: UpdateMode,
+    // Data gets requested periodically right now
+    #[serde(default)]
+    pub polling: bool,
+    #[serde(default)]
+    pub debug_requests: u64,
+}
+
+impl Stats {
+    /// Account the time it took to handle a frame
+    pub fn record_handling(&mut self, elapsed: Duration) {
+        let micros = elapsed.as_micros() as u64;
+        self.handler_micros += micros;
+        self.handler_max_micros = self.handler_max_micros.max(micros);
+    }
+}
@@ -0,0 +1,89 @@
+use super::buds_info::BudsInfoInner;
+
+use serde_json::{json, Map, Value};
+
+/// Returns the fields which differ between two states
+pub fn diff(old: &BudsInfoInner, new: &BudsInfoInner) -> Map<String, Value> {
+    let old = to_map(old);
+    let mut changed = Map::new();
+
+    for (key, value) in to_map(new) {
+        if old.get(&key) != Some(&value) {
+            changed.insert(key, value);
+        }
+    }
+
+    changed
+}
+
+/// Returns an event containing only the changed values or None if nothing changed
+pub fn diff_event(old: &BudsInfoInner, new: &BudsInfoInner) -> Option<String> {
+    let changed = diff(old, new);
+    if changed.is_empty() {
+        return None;
+    }
+
+    Some(json!({ "device": new.address, "changed": changed }).to_string())
+}
+
+/// Returns an event containing the full state of a device
+pub fn snapshot_event(info: &BudsInfoInner) -> String {
+    json!({ "device": info.address, "snapshot": info }).to_string()
+}
+
+/// Returns an event which isn't a state change (eg. a reminder)
+pub fn named_event(address: &str, name: &str, data: Value) -> String {
+    json!({ "device": address, "event": name, "data": data }).to_string()
+}
+
+/// Reduce an event to the given categories (eg. battery or wear) or event
+/// names. Returns None if nothing of interest is left
+pub fn filter_event(event: &str, only: &[String]) -> Option<String> {
+    let mut value: Value = serde_json::from_str(event).ok()?;
+
+    // Snapshots are requested explicitly
+    if value.get("snapshot").is_some() {
+        return Some(event.to_owned());
+    }
+
+    if let Some(name) = value.get("event").and_then(|i| i.as_str()) {
+        let wanted = only
+            .iter()
+            .any(|i| i == name || (i == "connection" && is_connection_event(name)));
+        return if wanted { Some(event.to_owned()) } else { None };
+    }
+
+    let changed = value.get_mut("changed")?.as_object_mut()?;
+    changed.retain(|key, _| {
+        only.iter()
+            .any(|i| category_keys(i).contains(&key.as_str()))
+    });
+    if changed.is_empty() {
+        return None;
+    }
+
+    Some(value.to_string())
+}
+
+fn is_connection_event(name: &str) -> bool {
+    matches!(name, "connection_state" | "connected" | "disconnected")
+}
+
+// Status values belonging to a category of `earbuds watch --only`
+fn category_keys(category: &str) -> &'static [&'static str] {
+    match category {
+        "battery" => &["batt_left", "batt_right", "batt_case", "charging"],
+        "wear" => &["placement_left", "placement_right"],
+        "anc" => &["noise_reduction"],
+        "ambient" => &["ambient_sound_enabled", "ambient_sound_volume"],
+        "connection" => &["connection", "ready"],
+        _ => &[],
+    }
+}
+
+fn to_map(info: &BudsInfoInner) -> Map<String, Value> {
+    match serde_json::to_value(info) {
+        Ok(Value::Object(map)) => map,
+        _ => Map::new(),
+    }
+}
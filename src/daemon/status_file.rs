@@ -0,0 +1,96 @@
+/*
+ * Writes a compact status snapshot of all connected devices to a file in the
+ * runtime directory (tmpfs) on every update, for consumers like shell prompts
+ * which can't afford a socket round trip. SIGUSR1 rewrites it right away.
+ */
+
+use super::bluetooth::rfcomm_connector::{ConnectionData, Subscriber};
+use super::buds_config::Config;
+use super::buds_info::BudsInfoInner;
+use super::labels;
+
+use async_std::{
+    channel::Receiver,
+    fs,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use futures_lite::FutureExt;
+use galaxy_buds_rs::message::bud_property::Placement;
+use log::error;
+
+/// Path of the snapshot file of the current user
+pub fn get_path() -> PathBuf {
+    match std::env::var_os("XDG_RUNTIME_DIR").filter(|i| !i.is_empty()) {
+        Some(dir) => PathBuf::from(dir).join("earbuds-status"),
+        None => PathBuf::from(format!("/tmp/earbuds-{}.status", nix::unistd::getuid())),
+    }
+}
+
+/// Keep the snapshot file up to date if enabled. `rewrite` receives SIGUSR1
+pub async fn run(
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+    rewrite: Receiver<()>,
+) {
+    if !config.lock().await.status_file() {
+        return;
+    }
+
+    let (subscriber, receiver) = Subscriber::new(None, true);
+    cd.lock().await.subscribers.push(subscriber);
+
+    let path = get_path();
+    let mut last = None;
+
+    loop {
+        let update = async { receiver.recv().await.map(|_| false) };
+        let signal = async { rewrite.recv().await.map(|_| true) };
+        let forced = match update.or(signal).await {
+            Ok(forced) => forced,
+            Err(_) => break,
+        };
+
+        let content: String = cd
+            .lock()
+            .await
+            .data
+            .values()
+            .map(|info| format_line(&info.inner))
+            .collect();
+        if !forced && last.as_ref() == Some(&content) {
+            continue;
+        }
+
+        if let Err(err) = write(&path, &content).await {
+            error!("Can't write status file: {}", err);
+        }
+        last = Some(content);
+    }
+
+    fs::remove_file(&path).await.ok();
+}
+
+// One line per device, eg. `device=AA:BB:.. left=64 right=71 case=90 noise=anc eq=normal worn=1`
+fn format_line(info: &BudsInfoInner) -> String {
+    let labels = labels::get(info, Some("en"));
+    let worn = info.placement_left == Placement::Ear || info.placement_right == Placement::Ear;
+
+    format!(
+        "device={} left={} right={} case={} noise={} eq={} worn={}\n",
+        info.address,
+        info.batt_left,
+        info.batt_right,
+        info.batt_case,
+        labels.noise_control.key,
+        labels.equalizer.key,
+        worn as u8
+    )
+}
+
+// Replace the file at once, so readers never see a partial snapshot
+async fn write(path: &PathBuf, content: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, content).await?;
+    fs::rename(&tmp, path).await
+}
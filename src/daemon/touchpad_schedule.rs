@@ -0,0 +1,204 @@
+/*
+ * Locks the touchpads of devices according to their `touchpad_lock_schedule`,
+ * eg. during workout hours or while a specific app runs. The previous lock
+ * state gets restored once no rule matches anymore, even if the buds were
+ * disconnected in between.
+ */
+
+use super::bluetooth::rfcomm_connector::ConnectionData;
+use super::buds_config::{Config, LockRule};
+use super::settings::{self, Value};
+
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use log::info;
+
+use std::{collections::HashMap, fs, time::Duration};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+const DAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// Local time to match rules against
+struct LocalTime {
+    // Minutes since midnight
+    minutes: u32,
+    // 0 is sunday
+    weekday: usize,
+}
+
+/// Apply the touchpad lock schedules of all connected devices
+pub async fn run(cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) {
+    // Lock states to restore by device address. They are kept while the
+    // buds are disconnected, since the buds keep their lock state
+    let mut restore: HashMap<String, bool> = HashMap::new();
+
+    loop {
+        task::sleep(POLL_INTERVAL).await;
+
+        let connected: Vec<String> = cd.lock().await.data.keys().cloned().collect();
+
+        // Devices whose schedule got removed get their lock state restored
+        let schedules: Vec<(String, Vec<LockRule>)> = {
+            let cfg = config.lock().await;
+            connected
+                .into_iter()
+                .filter_map(|addr| {
+                    let rules = cfg
+                        .get_device_config(&addr)
+                        .and_then(|i| i.touchpad_lock_schedule.clone());
+                    if rules.is_none() && !restore.contains_key(&addr) {
+                        return None;
+                    }
+                    Some((addr, rules.unwrap_or_default()))
+                })
+                .collect()
+        };
+
+        if schedules.is_empty() {
+            continue;
+        }
+
+        let now = match local_time() {
+            Some(v) => v,
+            None => continue,
+        };
+        let processes = task::spawn_blocking(running_processes).await;
+
+        for (addr, rules) in schedules {
+            let locked = rules.iter().any(|i| matches(i, &now, &processes));
+            if let Err(err) = update(&cd, &addr, locked, &mut restore).await {
+                eprintln!("Can't apply touchpad lock schedule: {}", err);
+            }
+        }
+    }
+}
+
+// Lock the touchpads when a rule starts matching and restore them afterwards
+async fn update(
+    cd: &Arc<Mutex<ConnectionData>>,
+    addr: &str,
+    locked: bool,
+    restore: &mut HashMap<String, bool>,
+) -> Result<(), String> {
+    let mut connection_data = cd.lock().await;
+    let info = match connection_data.get_device_mut(addr) {
+        Some(info) if info.inner.ready => info,
+        _ => return Ok(()),
+    };
+
+    match restore.get(addr).copied() {
+        None if locked => {
+            let previous = settings::get(&info.inner, "lock_touchpad", None)?.as_bool();
+            info!("Locking touchpads of {} as scheduled", addr);
+            settings::set(info, "lock_touchpad", "true", None, false).await?;
+            restore.insert(addr.to_owned(), previous);
+        }
+        Some(previous) if !locked => {
+            info!("Schedule of {} ended, restoring touchpad lock", addr);
+            let value = Value::Bool(previous).to_string();
+            settings::set(info, "lock_touchpad", &value, None, false).await?;
+            restore.remove(addr);
+        }
+        _ => (),
+    }
+
+    Ok(())
+}
+
+// A rule matches if all of its conditions match
+fn matches(rule: &LockRule, now: &LocalTime, processes: &[String]) -> bool {
+    if let Some(ref hours) = rule.hours {
+        match parse_hours(hours) {
+            // Ranges may wrap around midnight
+            Ok((start, end)) if start <= end => {
+                if now.minutes < start || now.minutes >= end {
+                    return false;
+                }
+            }
+            Ok((start, end)) => {
+                if now.minutes < start && now.minutes >= end {
+                    return false;
+                }
+            }
+            Err(_) => return false,
+        }
+    }
+
+    if let Some(ref days) = rule.days {
+        if !days.iter().any(|i| i.to_lowercase() == DAYS[now.weekday]) {
+            return false;
+        }
+    }
+
+    if let Some(ref app) = rule.app {
+        // Process names are cut off after 15 characters
+        let app: String = app.to_lowercase().chars().take(15).collect();
+        if !processes.iter().any(|i| *i == app) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Returns an error if a rule can't be applied
+pub fn check_rule(rule: &LockRule) -> Result<(), String> {
+    if rule.hours.is_none() && rule.days.is_none() && rule.app.is_none() {
+        return Err("Touchpad lock rules need hours, days or an app".to_string());
+    }
+
+    if let Some(ref hours) = rule.hours {
+        parse_hours(hours)?;
+    }
+
+    for day in rule.days.iter().flatten() {
+        if !DAYS.contains(&day.to_lowercase().as_str()) {
+            return Err(format!("Invalid day '{}'. Use eg. 'mon' or 'sat'", day));
+        }
+    }
+
+    Ok(())
+}
+
+// Parse a range like "06:30-08:00" into minutes since midnight
+fn parse_hours(hours: &str) -> Result<(u32, u32), String> {
+    let err = || format!("Invalid hours '{}'. Use eg. '06:30-08:00'", hours);
+    let parse = |time: &str| -> Option<u32> {
+        let (h, m) = time.trim().split_once(':')?;
+        let (h, m) = (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?);
+        (h <= 24 && m < 60 && h * 60 + m <= 24 * 60).then(|| h * 60 + m)
+    };
+
+    let (start, end) = hours.split_once('-').ok_or_else(err)?;
+    Ok((parse(start).ok_or_else(err)?, parse(end).ok_or_else(err)?))
+}
+
+fn local_time() -> Option<LocalTime> {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return None;
+    }
+
+    Some(LocalTime {
+        minutes: (tm.tm_hour * 60 + tm.tm_min) as u32,
+        weekday: tm.tm_wday as usize,
+    })
+}
+
+// Names of all running processes, lowercase
+fn running_processes() -> Vec<String> {
+    let entries = match fs::read_dir("/proc") {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+
+    entries
+        .filter_map(|i| i.ok())
+        .filter(|i| i.file_name().to_string_lossy().parse::<u32>().is_ok())
+        .filter_map(|i| fs::read_to_string(i.path().join("comm")).ok())
+        .map(|i| i.trim().to_lowercase())
+        .collect()
+}
@@ -0,0 +1,92 @@
+use super::super::buds_config::Config;
+use super::super::history;
+use super::{Request, Response};
+
+use async_std::{
+    os::unix::{io::AsRawFd, net::UnixStream},
+    sync::Mutex,
+};
+use nix::sys::socket::{getsockopt, sockopt::PeerCredentials};
+use serde_json::json;
+
+/// Process which sent a request
+#[derive(Debug, Clone, Copy)]
+pub struct Peer {
+    pub pid: i32,
+    pub uid: u32,
+}
+
+/// Returns the process on the other side of the socket
+pub fn peer(stream: &UnixStream) -> Option<Peer> {
+    let cred = getsockopt(stream.as_raw_fd(), PeerCredentials).ok()?;
+    Some(Peer {
+        pid: cred.pid(),
+        uid: cred.uid(),
+    })
+}
+
+/// Returns true if the request changes the buds, the config or the connection
+pub fn is_mutating(payload: &Request) -> bool {
+    if payload.dry_run {
+        return false;
+    }
+
+    match payload.cmd.as_str() {
+        "set_value" | "apply_settings" | "toggle_value" | "set_config" | "connect"
+        | "disconnect" | "handoff" | "pairing_code" | "revoke_tokens" | "mute_notifications"
+        | "set_desired_state" | "pair_device" | "grab" | "raw_send" | "find_device" => true,
+        "mic" => payload.opt_param1.as_deref() == Some("set_default"),
+        _ => false,
+    }
+}
+
+/// Record a request and its response if it changes something and auditing is enabled
+pub async fn audit(config: &Mutex<Config>, peer: Option<Peer>, payload: &Request, response: &str) {
+    if is_mutating(payload) && config.lock().await.audit_requests() {
+        record(peer, payload, response).await;
+    }
+}
+
+/// Audit a request handled outside of the unix socket (eg. over D-Bus or HTTP),
+/// whose sender isn't known
+pub async fn audit_result(config: &Mutex<Config>, payload: &Request, result: Result<(), &str>) {
+    let device = payload.device.clone().unwrap_or_default();
+    let response: Response<()> = match result {
+        Ok(()) => Response::new_success(device, None),
+        Err(err) => Response::new_error(device, err, None),
+    };
+
+    audit(
+        config,
+        None,
+        payload,
+        &serde_json::to_string(&response).unwrap(),
+    )
+    .await;
+}
+
+/// Append a request together with its sender and result to the history store
+pub async fn record(peer: Option<Peer>, payload: &Request, response: &str) {
+    let response: serde_json::Value = serde_json::from_str(response).unwrap_or_default();
+
+    let entry = json!({
+        "pid": peer.map(|i| i.pid),
+        "uid": peer.map(|i| i.uid),
+        "process": peer.and_then(|i| process_name(i.pid)),
+        "cmd": payload.cmd,
+        "device": response["device"],
+        "params": [payload.opt_param1, payload.opt_param2, payload.opt_param3],
+        "status": response["status"],
+        "status_message": response["status_message"],
+    });
+
+    if let Err(err) = history::append("audit", &entry).await {
+        eprintln!("Can't write audit entry: {}", err);
+    }
+}
+
+fn process_name(pid: i32) -> Option<String> {
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .ok()
+        .map(|i| i.trim().to_owned())
+}
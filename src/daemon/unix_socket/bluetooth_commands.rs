@@ -1,3 +1,5 @@
+use super::super::bluetooth::bt_connection_listener::supported_device;
+
 use blurz::{BluetoothAdapter, BluetoothDevice, BluetoothSession};
 
 // Connect or disconnect to the buds
@@ -49,3 +51,18 @@ pub async fn change_connection_status<S: AsRef<str>>(device_addr: S, connect: bo
 
     "success".to_string()
 }
+
+/// Returns (address, name) of all paired buds, connected or not
+pub fn get_paired_buds() -> Result<Vec<(String, String)>, String> {
+    let session = BluetoothSession::create_session(None).map_err(|e| e.to_string())?;
+    let adapter = BluetoothAdapter::init(&session).map_err(|e| e.to_string())?;
+    let devices = adapter.get_device_list().map_err(|e| e.to_string())?;
+
+    Ok(devices
+        .into_iter()
+        .map(|i| BluetoothDevice::new(&session, i))
+        .filter(|i| i.is_paired().unwrap_or(false) && supported_device(i))
+        .filter_map(|i| Some((i.get_address().ok()?, i.get_name().ok()?)))
+        .filter(|(_, name)| name.to_lowercase().contains("buds"))
+        .collect())
+}
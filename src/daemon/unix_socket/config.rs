@@ -1,10 +1,29 @@
 use super::Request;
 use super::{request_handler::get_err, Response};
 
-use crate::daemon::{buds_config::Config, buds_info::BudsInfoInner, utils};
+use crate::daemon::{
+    buds_config::{BudsConfig, Config},
+    buds_info::BudsInfoInner,
+    parse, settings,
+};
 
 use async_std::sync::{Arc, Mutex};
 
+use std::collections::HashMap;
+
+/// Keys of the config options which can be set per device
+pub const KEYS: [&str; 9] = [
+    "auto_pause",
+    "auto_play",
+    "smart_sink",
+    "low_battery_notification",
+    "touchpad_lock_in_case",
+    "connect_notification",
+    "session_summary_notification",
+    "auto_mic",
+    "auto_profile",
+];
+
 // Set the value of a config option for a device
 pub async fn set_value(payload: &Request, address: String, config: Arc<Mutex<Config>>) -> String {
     let mut config = config.lock().await;
@@ -21,7 +40,10 @@ pub async fn set_value(payload: &Request, address: String, config: Arc<Mutex<Con
     }
 
     let key = payload.opt_param1.clone().unwrap();
-    let value = utils::str_to_bool(payload.opt_param2.clone().unwrap());
+    let value = match parse::bool(payload.opt_param2.as_deref().unwrap_or_default()) {
+        Ok(v) => v,
+        Err(err) => return get_err(&err),
+    };
 
     // Get the right config entry mutable
     let cfg = config.get_device_config_mut(&address);
@@ -36,6 +58,11 @@ pub async fn set_value(payload: &Request, address: String, config: Arc<Mutex<Con
         "auto_play" => cfg.auto_resume_music = Some(value),
         "smart_sink" => cfg.smart_sink = Some(value),
         "low_battery_notification" => cfg.low_battery_notification = Some(value),
+        "touchpad_lock_in_case" => cfg.touchpad_lock_in_case = Some(value),
+        "connect_notification" => cfg.connect_notification = Some(value),
+        "session_summary_notification" => cfg.session_summary_notification = Some(value),
+        "auto_mic" => cfg.auto_mic = Some(value),
+        "auto_profile" => cfg.auto_profile = Some(value),
         _ => {
             return get_err("Invalid key");
         }
@@ -49,3 +76,46 @@ pub async fn set_value(payload: &Request, address: String, config: Arc<Mutex<Con
     let a: Response<BudsInfoInner> = Response::new_success(address.clone(), None);
     serde_json::to_string(&a).unwrap()
 }
+
+/// Merge settings (a json object like the desired_state table) into the
+/// desired state of a device and save the config
+pub async fn set_desired_state(
+    payload: &Request,
+    address: String,
+    config: Arc<Mutex<Config>>,
+) -> String {
+    let state: HashMap<String, toml::Value> =
+        match payload.opt_param1.as_deref().map(serde_json::from_str) {
+            Some(Ok(v)) => v,
+            Some(Err(err)) => return get_err(&format!("Invalid settings: {}", err)),
+            None => return get_err("Missing parameter"),
+        };
+
+    let mut config = config.lock().await;
+    let cfg = match config.get_device_config_mut(&address) {
+        Some(v) => v,
+        None => return get_err("Device has no config!"),
+    };
+
+    // Check the values before touching the config
+    let new = BudsConfig {
+        desired_state: Some(state),
+        ..Default::default()
+    };
+    for (key, param, value) in new.desired_state() {
+        if let Err(err) = settings::parse(&key, param.as_deref(), &value) {
+            return get_err(&err);
+        }
+    }
+
+    cfg.desired_state
+        .get_or_insert_with(HashMap::new)
+        .extend(new.desired_state.unwrap_or_default());
+
+    if let Err(err) = config.save().await {
+        return get_err(format!("Err saving config: {}", err).as_str());
+    }
+
+    let a: Response<BudsInfoInner> = Response::new_success(address, None);
+    serde_json::to_string(&a).unwrap()
+}
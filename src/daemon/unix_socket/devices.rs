@@ -0,0 +1,52 @@
+use super::super::bluetooth::rfcomm_connector::ConnectionData;
+use super::{bluetooth_commands, Response};
+
+use serde::{Deserialize, Serialize};
+
+/// A pair of buds known to the daemon or bluez
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceEntry {
+    pub address: String,
+    pub name: Option<String>,
+    // "connected" or "paired"
+    pub state: String,
+}
+
+/// List connected buds and paired ones which aren't connected
+pub fn list(connection_data: &ConnectionData) -> String {
+    let mut devices: Vec<DeviceEntry> = match bluetooth_commands::get_paired_buds() {
+        Ok(paired) => paired
+            .into_iter()
+            .map(|(address, name)| DeviceEntry {
+                state: state(connection_data, &address).to_owned(),
+                address,
+                name: Some(name),
+            })
+            .collect(),
+        Err(err) => {
+            eprintln!("Can't list paired devices: {}", err);
+            Vec::new()
+        }
+    };
+
+    // Connected devices unknown to bluez (eg. simulated ones)
+    for address in connection_data.data.keys() {
+        if !devices.iter().any(|i| i.address == *address) {
+            devices.push(DeviceEntry {
+                address: address.clone(),
+                name: None,
+                state: "connected".to_owned(),
+            });
+        }
+    }
+
+    serde_json::to_string(&Response::new_success("", Some(devices))).unwrap()
+}
+
+fn state(connection_data: &ConnectionData, address: &str) -> &'static str {
+    if connection_data.data.contains_key(address) {
+        "connected"
+    } else {
+        "paired"
+    }
+}
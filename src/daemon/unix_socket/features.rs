@@ -0,0 +1,75 @@
+use super::super::buds_config::Config;
+use super::Response;
+
+use serde::{Deserialize, Serialize};
+
+/// An optional subsystem of the daemon
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureEntry {
+    pub name: String,
+    // Built with the cargo feature it depends on
+    pub compiled: bool,
+    // Compiled in and enabled by the config
+    pub active: bool,
+    // What's needed to activate it
+    pub requires: String,
+}
+
+/// List the optional subsystems and whether they're compiled in and active
+pub fn list(config: &Config) -> String {
+    let devices = &config.buds_settings;
+    let http = config.http_address.is_some();
+
+    let features = vec![
+        entry("dbus", cfg!(feature = "dbus"), true, "feature 'dbus'"),
+        entry(
+            "screen_lock",
+            cfg!(feature = "dbus"),
+            devices.iter().any(|i| i.lock_action.is_some()),
+            "feature 'dbus' and lock_action of a device",
+        ),
+        entry("http", true, http, "http_address"),
+        entry(
+            "tls",
+            cfg!(feature = "tls"),
+            http && config.http_tls_cert.is_some() && config.http_tls_key.is_some(),
+            "feature 'tls', http_address, http_tls_cert and http_tls_key",
+        ),
+        entry(
+            "pulse_sink",
+            cfg!(feature = "pulse-sink"),
+            devices.iter().any(|i| i.smart_sink()),
+            "feature 'pulse-sink' and smart_sink of a device",
+        ),
+        entry(
+            "sqlite",
+            cfg!(feature = "sqlite"),
+            config.history_backend.as_deref() == Some("sqlite"),
+            "feature 'sqlite' and history_backend = 'sqlite'",
+        ),
+        entry("status_file", true, config.status_file(), "status_file"),
+        entry(
+            "device_sockets",
+            true,
+            devices.iter().any(|i| i.device_socket()),
+            "device_socket of a device",
+        ),
+        entry(
+            "hooks",
+            true,
+            config.hooks.as_ref().map_or(false, |i| !i.is_empty()),
+            "[[hooks]]",
+        ),
+    ];
+
+    serde_json::to_string(&Response::new_success("", Some(features))).unwrap()
+}
+
+fn entry(name: &str, compiled: bool, enabled: bool, requires: &str) -> FeatureEntry {
+    FeatureEntry {
+        name: name.to_owned(),
+        compiled,
+        active: compiled && enabled,
+        requires: requires.to_owned(),
+    }
+}
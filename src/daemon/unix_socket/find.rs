@@ -0,0 +1,39 @@
+use super::super::buds_info::BudsInfo;
+use super::super::raw_message::{ids, RawMessage};
+use super::request_handler::get_err;
+use super::{Request, Response};
+
+/// Start or stop the locator beep. `opt_param1` is "start" or "stop",
+/// `opt_param2` the bud to beep: "left", "right" or "both" (default)
+pub async fn handle(payload: &Request, device: &mut BudsInfo) -> String {
+    let start = match payload.opt_param1.as_deref().unwrap_or("start") {
+        "start" => true,
+        "stop" => false,
+        _ => return get_err("Invalid find action. Use start or stop"),
+    };
+
+    // Which buds to keep silent
+    let muted = match payload.opt_param2.as_deref().unwrap_or("both") {
+        "both" => [0, 0],
+        "left" => [0, 1],
+        "right" => [1, 0],
+        _ => return get_err("Invalid bud. Use left, right or both"),
+    };
+
+    let mut messages = vec![];
+    if start {
+        messages.push(RawMessage::new(ids::FIND_MY_EARBUDS_START, Vec::new()));
+        messages.push(RawMessage::new(ids::MUTE_EARBUD, muted.to_vec()));
+    } else {
+        messages.push(RawMessage::new(ids::FIND_MY_EARBUDS_STOP, Vec::new()));
+    }
+
+    for msg in messages {
+        if let Err(err) = device.send_raw(msg).await {
+            return get_err(&err);
+        }
+    }
+
+    let response: Response<String> = Response::new_success(device.inner.address.clone(), None);
+    serde_json::to_string(&response).unwrap()
+}
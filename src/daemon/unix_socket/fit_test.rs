@@ -0,0 +1,140 @@
+use super::super::bluetooth::rfcomm_connector::ConnectionData;
+use super::super::buds_config::Config;
+use super::super::models;
+use super::super::raw_message::{ids, RawMessage};
+use super::{
+    request_handler::{get_err, report_progress},
+    Request, Response,
+};
+
+use async_std::{
+    io::BufWriter,
+    os::unix::net::UnixStream,
+    sync::{Arc, Mutex},
+};
+use galaxy_buds_rs::message::bud_property::Placement;
+use serde::{Deserialize, Serialize};
+
+use std::time::{Duration, Instant};
+
+// The buds play a tone for a few seconds before answering
+const FIT_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Seal quality of a single bud
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum FitQuality {
+    Good,
+    Bad,
+    // The buds couldn't measure, eg. because of noise
+    Failed,
+}
+
+/// Result of a fit test
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct FitTestResult {
+    pub left: FitQuality,
+    pub right: FitQuality,
+}
+
+impl FitTestResult {
+    /// Decode the payload of a CHECK_FIT_RESULT message
+    pub fn decode(payload: &[u8]) -> Option<Self> {
+        let quality = |i: u8| match i {
+            0 => FitQuality::Bad,
+            1 => FitQuality::Good,
+            _ => FitQuality::Failed,
+        };
+
+        Some(Self {
+            left: quality(*payload.get(0)?),
+            right: quality(*payload.get(1)?),
+        })
+    }
+}
+
+/// Run a fit test on a worn pair of buds. Can't run while holding the connection
+/// data lock since the listener needs it to handle the result
+pub async fn run(
+    payload: &Request,
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+    write_stream: &mut BufWriter<&UnixStream>,
+) -> String {
+    let address = {
+        let mut connection_data = cd.lock().await;
+        if connection_data.get_device_count() == 0 {
+            return get_err("No connected device found");
+        }
+
+        let req_dev_addr = payload.device.clone().unwrap_or_default();
+        let address = match connection_data
+            .get_device_address(&req_dev_addr, &config)
+            .await
+        {
+            Some(addr) => addr,
+            None => return get_err("Device not found"),
+        };
+
+        let info = match connection_data.get_device_mut(&address) {
+            Some(info) => info,
+            None => return get_err("Device not found"),
+        };
+
+        if info.inner.battery_only || !models::get(info.inner.model).supports_fit_test() {
+            return get_err("The fit test isn't supported by your buds");
+        }
+
+        if info.inner.placement_left != Placement::Ear
+            || info.inner.placement_right != Placement::Ear
+        {
+            return get_err("Put both buds in your ears first");
+        }
+
+        info.fit_test = None;
+        if let Err(err) = info
+            .send_raw(RawMessage::new(ids::CHECK_FIT, vec![1]))
+            .await
+        {
+            return get_err(&err);
+        }
+
+        address
+    };
+
+    let start = Instant::now();
+    let mut result = None;
+    let mut last_progress = 0;
+    while start.elapsed() < FIT_TEST_TIMEOUT {
+        async_std::task::sleep(Duration::from_millis(50)).await;
+
+        // The buds don't report progress, estimate it by the timeout
+        let progress = (start.elapsed().as_millis() * 100 / FIT_TEST_TIMEOUT.as_millis()) as u8;
+        if progress != last_progress {
+            last_progress = progress;
+            report_progress(payload, progress.min(99), write_stream).await;
+        }
+
+        let connection_data = cd.lock().await;
+        result = connection_data
+            .get_device(&address)
+            .and_then(|i| i.fit_test);
+        if result.is_some() {
+            break;
+        }
+    }
+
+    // Stop the tone, even if the buds didn't answer
+    if let Some(info) = cd.lock().await.get_device_mut(&address) {
+        info.send_raw(RawMessage::new(ids::CHECK_FIT, vec![0]))
+            .await
+            .ok();
+    }
+
+    match result {
+        Some(result) => {
+            serde_json::to_string(&Response::new_success(address, Some(result))).unwrap()
+        }
+        None => get_err("The buds didn't answer"),
+    }
+}
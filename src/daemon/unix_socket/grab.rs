@@ -0,0 +1,63 @@
+use super::super::bluetooth::rfcomm_connector::ConnectionData;
+use super::super::buds_config::Config;
+use super::{bluetooth_commands, request_handler::get_err, Response};
+
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use log::info;
+
+use std::time::Duration;
+
+// Time for the buds to notice the disconnect before connecting again
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// Take the buds over from another host like the "switch device" button of the
+/// phone app does: disconnect if connected and connect again
+pub async fn run(
+    device: Option<String>,
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+) -> String {
+    let (address, connected) = {
+        let mut connection_data = cd.lock().await;
+        let req_dev_addr = device.unwrap_or_default();
+
+        // Disconnected buds can be grabbed as well
+        let address = match connection_data
+            .get_device_address(&req_dev_addr, &config)
+            .await
+        {
+            Some(addr) => addr,
+            None if !req_dev_addr.is_empty() => req_dev_addr,
+            None => return get_err("Device not found"),
+        };
+
+        let connected = match connection_data.get_device_mut(&address) {
+            Some(info) => {
+                info.disconnect_requested = true;
+                true
+            }
+            None => false,
+        };
+        (address, connected)
+    };
+
+    info!("Grabbing {}", address);
+
+    if connected {
+        let res = bluetooth_commands::change_connection_status(&address, false).await;
+        if res != "success" {
+            return get_err(&format!("Can't disconnect: {}", res));
+        }
+        task::sleep(RECONNECT_DELAY).await;
+    }
+
+    match bluetooth_commands::change_connection_status(&address, true).await {
+        res if res == "success" => {
+            serde_json::to_string(&Response::new_success(&address, Some(connected))).unwrap()
+        }
+        res => get_err(&format!("Can't connect: {}", res)),
+    }
+}
@@ -0,0 +1,28 @@
+use super::super::bluetooth::rfcomm_connector::ConnectionData;
+use super::super::runtime_state;
+use super::Response;
+
+use async_std::sync::{Arc, Mutex};
+use log::info;
+
+use std::{net::Shutdown, time::Duration};
+
+// Time for the listeners to finish the sessions of the closed connections
+const CLOSE_DELAY: Duration = Duration::from_millis(500);
+
+/// Hand the devices over to a newly started daemon: close all RFCOMM connections and remove
+/// the socket so the new daemon can connect and bind. The caller has to exit afterwards
+pub async fn release(cd: Arc<Mutex<ConnectionData>>, socket_path: &str) -> String {
+    info!("Handing off to a new daemon");
+
+    for info in cd.lock().await.data.values_mut() {
+        info.disconnect_requested = true;
+        info.stream.shutdown(Shutdown::Both).ok();
+    }
+
+    async_std::task::sleep(CLOSE_DELAY).await;
+    runtime_state::save(cd.lock().await.data.values());
+    std::fs::remove_file(socket_path).ok();
+
+    serde_json::to_string(&Response::<()>::new_success("", None)).unwrap()
+}
@@ -0,0 +1,13 @@
+use super::super::buds_info::BudsInfo;
+use super::super::settings::{self, KeyInfo};
+use super::{config, Response};
+
+/// List the settings of the device and the config options, with their
+/// values and whether the device supports them
+pub fn list(device: &BudsInfo) -> String {
+    let mut keys = settings::describe(&device.inner);
+    keys.extend(config::KEYS.iter().map(|i| KeyInfo::new_bool(i, "config")));
+
+    let response = Response::new_success(device.inner.address.clone(), Some(keys));
+    serde_json::to_string(&response).unwrap()
+}
@@ -0,0 +1,66 @@
+use super::super::bluetooth::rfcomm_connector::ConnectionData;
+use super::super::stats::Stats;
+use super::Response;
+
+use serde::{Deserialize, Serialize};
+
+/// Counters of a connected device
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceLoad {
+    pub address: String,
+    pub alias: Option<String>,
+    pub stats: Stats,
+    // Connections established after the first one
+    pub reconnects: u64,
+}
+
+/// Queue of a client listening for events
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriberLoad {
+    pub device: Option<String>,
+    pub passive: bool,
+    pub queued: usize,
+    pub dropped: u64,
+}
+
+/// Counters of all devices and subscribers, shown by `earbuds top`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Load {
+    pub devices: Vec<DeviceLoad>,
+    pub subscribers: Vec<SubscriberLoad>,
+}
+
+/// Respond with the counters of all devices and subscribers
+pub fn get(connection_data: &ConnectionData) -> String {
+    let mut devices: Vec<DeviceLoad> = connection_data
+        .data
+        .iter()
+        .map(|(address, info)| DeviceLoad {
+            address: address.clone(),
+            alias: info.inner.alias.clone(),
+            stats: info.stats.clone(),
+            reconnects: connection_data
+                .connects
+                .get(address)
+                .map_or(0, |i| i.saturating_sub(1)),
+        })
+        .collect();
+    devices.sort_by(|a, b| a.address.cmp(&b.address));
+
+    let subscribers = connection_data
+        .subscribers
+        .iter()
+        .map(|i| SubscriberLoad {
+            device: i.device.clone(),
+            passive: i.passive,
+            queued: i.queued(),
+            dropped: i.dropped(),
+        })
+        .collect();
+
+    let load = Load {
+        devices,
+        subscribers,
+    };
+    serde_json::to_string(&Response::new_success("", Some(load))).unwrap()
+}
@@ -0,0 +1,25 @@
+use super::request_handler::get_err;
+use super::{Request, Response};
+
+use crate::daemon::mic::{self, MicSource};
+
+/// List the input sources of the buds or make them the default input
+pub fn handle(payload: &Request, device_addr: String) -> String {
+    match payload.opt_param1.as_deref().unwrap_or("list") {
+        "list" => match mic::list_sources(&device_addr) {
+            Ok(sources) => {
+                serde_json::to_string(&Response::new_success(device_addr, Some(sources))).unwrap()
+            }
+            Err(err) => get_err(&err),
+        },
+        "set_default" => match mic::set_default(&device_addr) {
+            Ok(source) => {
+                let response: Response<Vec<MicSource>> =
+                    Response::new_success(device_addr, Some(vec![source]));
+                serde_json::to_string(&response).unwrap()
+            }
+            Err(err) => get_err(&err),
+        },
+        _ => get_err("Invalid mic action"),
+    }
+}
@@ -1,8 +1,24 @@
+pub mod audit;
 pub mod bluetooth_commands;
 mod config;
+pub mod devices;
+pub mod features;
+mod find;
+pub mod fit_test;
+mod grab;
+mod handoff;
+mod keys;
+pub mod load;
+mod mic;
+pub mod mute;
+pub mod pair;
+mod raw;
 pub mod request_handler;
-mod set_value;
+pub mod rtt;
+pub mod set_value;
 pub mod socket;
+mod status;
+mod telemetry;
 
 use serde::{Deserialize, Serialize};
 
@@ -14,6 +30,15 @@ pub struct Request {
     pub opt_param1: Option<String>,
     pub opt_param2: Option<String>,
     pub opt_param3: Option<String>,
+    // Don't send anything to the buds but respond with the messages which would be sent
+    #[serde(default)]
+    pub dry_run: bool,
+    // Send values even if the buds already have them set
+    #[serde(default)]
+    pub force: bool,
+    // Send progress frames before the response of long operations
+    #[serde(default)]
+    pub progress: bool,
 }
 
 impl Request {
@@ -24,6 +49,9 @@ impl Request {
             opt_param1: None,
             opt_param2: None,
             opt_param3: None,
+            dry_run: false,
+            force: false,
+            progress: false,
         }
     }
 
@@ -35,6 +63,12 @@ impl Request {
     }
 }
 
+/// Progress frame (in percent) sent before the final response
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Progress {
+    pub progress: u8,
+}
+
 /// Unix connection response
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Response<T>
@@ -0,0 +1,43 @@
+use super::request_handler::get_err;
+use super::{Request, Response};
+
+use crate::daemon::notifications;
+
+use serde::{Deserialize, Serialize};
+
+use std::time::{Duration, UNIX_EPOCH};
+
+/// Whether the notifications of a device are muted
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MuteState {
+    pub muted: bool,
+    // Unix time in seconds. None if muted until unmuted
+    pub until: Option<u64>,
+}
+
+/// Mute the notifications of a device. `opt_param1` is the duration in seconds or "off" to unmute
+pub fn handle(payload: &Request, device_addr: String) -> String {
+    let state = match payload.opt_param1.as_deref() {
+        Some("off") => {
+            notifications::unmute(&device_addr);
+            MuteState {
+                muted: false,
+                until: None,
+            }
+        }
+        param => {
+            let duration = match param.map(|i| i.parse::<u64>()) {
+                None => None,
+                Some(Ok(secs)) => Some(Duration::from_secs(secs)),
+                Some(Err(_)) => return get_err("Invalid duration"),
+            };
+
+            let until = notifications::mute(&device_addr, duration)
+                .and_then(|i| i.duration_since(UNIX_EPOCH).ok())
+                .map(|i| i.as_secs());
+            MuteState { muted: true, until }
+        }
+    };
+
+    serde_json::to_string(&Response::new_success(device_addr, Some(state))).unwrap()
+}
@@ -0,0 +1,152 @@
+use super::super::buds_config::{BudsConfig, Config};
+use super::super::models;
+use super::{request_handler::get_err, Request, Response};
+
+use async_std::{
+    sync::{Arc, Mutex},
+    task,
+};
+use blurz::{BluetoothAdapter, BluetoothDevice, BluetoothDiscoverySession, BluetoothSession};
+use serde::{Deserialize, Serialize};
+
+use std::{thread, time::Duration};
+
+const DEFAULT_TIMEOUT: u64 = 20;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Buds which got paired by `earbuds pair`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairedEntry {
+    pub address: String,
+    pub name: String,
+    pub connected: bool,
+}
+
+// Buds found during discovery
+struct Found {
+    path: String,
+    address: String,
+    name: String,
+}
+
+/// Discover buds in pairing mode, pair and trust them and create their config
+/// entry. Searches for `opt_param1` seconds
+pub async fn run(payload: &Request, config: Arc<Mutex<Config>>) -> String {
+    let timeout = payload
+        .opt_param1
+        .as_ref()
+        .and_then(|i| i.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_TIMEOUT);
+
+    let found = match task::spawn_blocking(move || discover(Duration::from_secs(timeout))).await {
+        Ok(v) => v,
+        Err(err) => return get_err(&err),
+    };
+    if found.is_empty() {
+        return get_err("No buds in pairing mode found. Put them into the open case and hold both touchpads until they blink");
+    }
+
+    let mut paired = Vec::new();
+    let mut last_err = None;
+    for device in found {
+        if let Err(err) = pair(&device.path).await {
+            last_err = Some(format!("Can't pair {}: {}", device.name, err));
+            continue;
+        }
+
+        let path = device.path.clone();
+        let connected = task::spawn_blocking(move || connect(&path)).await.is_ok();
+
+        {
+            let mut config = config.lock().await;
+            if !config.has_device_config(&device.address) {
+                if let Err(err) = config
+                    .set_device_config(BudsConfig::new(device.address.clone()))
+                    .await
+                {
+                    return get_err(&err);
+                }
+            }
+        }
+
+        paired.push(PairedEntry {
+            address: device.address,
+            name: device.name,
+            connected,
+        });
+    }
+
+    match (paired.is_empty(), last_err) {
+        (true, Some(err)) => get_err(&err),
+        _ => serde_json::to_string(&Response::new_success("", Some(paired))).unwrap(),
+    }
+}
+
+#[cfg(feature = "dbus")]
+async fn pair(path: &str) -> Result<(), String> {
+    super::super::bluetooth::agent::pair_and_trust(path)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Without the agent, bluez can't be asked to confirm the pairing
+#[cfg(not(feature = "dbus"))]
+async fn pair(_path: &str) -> Result<(), String> {
+    Err("the daemon was built without the dbus feature".to_owned())
+}
+
+// Run a discovery until unpaired buds show up or `timeout` is over
+fn discover(timeout: Duration) -> Result<Vec<Found>, String> {
+    let session = BluetoothSession::create_session(None).map_err(|e| e.to_string())?;
+    let adapter = BluetoothAdapter::init(&session).map_err(|e| e.to_string())?;
+    if !adapter.is_powered().unwrap_or(false) {
+        adapter.set_powered(true).map_err(|e| e.to_string())?;
+    }
+
+    let discovery = BluetoothDiscoverySession::create_session(&session, adapter.get_id())
+        .map_err(|e| e.to_string())?;
+    discovery.start_discovery().map_err(|e| e.to_string())?;
+
+    let mut waited = Duration::from_secs(0);
+    let mut found = Vec::new();
+    while waited < timeout && found.is_empty() {
+        thread::sleep(POLL_INTERVAL);
+        waited += POLL_INTERVAL;
+
+        found = adapter
+            .get_device_list()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|i| BluetoothDevice::new(&session, i))
+            .filter(|i| !i.is_paired().unwrap_or(true))
+            .filter_map(|i| {
+                let name = i.get_name().ok()?;
+                if !is_buds_name(&name) {
+                    return None;
+                }
+                Some(Found {
+                    path: i.get_id(),
+                    address: i.get_address().ok()?,
+                    name,
+                })
+            })
+            .collect();
+    }
+
+    discovery.stop_discovery().ok();
+    Ok(found)
+}
+
+// Phones and watches are named "Galaxy .." as well
+fn is_buds_name(name: &str) -> bool {
+    models::find_by_name(name).is_some()
+        || (models::is_samsung_name(name) && name.to_lowercase().contains("buds"))
+}
+
+// The daemon picks the buds up once they're connected
+fn connect(path: &str) -> Result<(), String> {
+    let session = BluetoothSession::create_session(None).map_err(|e| e.to_string())?;
+    BluetoothDevice::new(&session, path.to_owned())
+        .connect(8000)
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,78 @@
+use super::super::buds_info::BudsInfo;
+use super::super::diagnostics;
+use super::super::raw_message::{self, ids, RawMessage};
+use super::request_handler::{get_err, respond};
+use super::{Request, Response};
+
+use async_std::{io::BufWriter, os::unix::net::UnixStream};
+use serde_json::json;
+
+/// Send a message the daemon doesn't know. `opt_param1` is the id,
+/// `opt_param2` the payload as hex. Responds with a description of the frame
+pub async fn send(payload: &Request, device: &mut BudsInfo) -> String {
+    let id = match raw_message::parse_id(payload.opt_param1.as_deref().unwrap_or_default()) {
+        Ok(id) => id,
+        Err(err) => return get_err(&err),
+    };
+    let bytes = match raw_message::from_hex(payload.opt_param2.as_deref().unwrap_or_default()) {
+        Ok(bytes) => bytes,
+        Err(err) => return get_err(&err),
+    };
+
+    let msg = RawMessage::new(id, bytes);
+    let description = raw_message::describe_frame(&msg.to_byte_array(device.inner.model));
+
+    // Dry runs only describe the frame
+    let res = if payload.dry_run {
+        Ok(())
+    } else {
+        device.send_raw(msg).await
+    };
+
+    match res {
+        Ok(()) => {
+            let response = Response::new_success(device.inner.address.clone(), Some(description));
+            serde_json::to_string(&response).unwrap()
+        }
+        Err(err) => get_err(&err),
+    }
+}
+
+/// Stream received messages as json lines until the client disconnects.
+/// `opt_param1` limits them to a single id
+pub async fn watch(payload: &Request, write_stream: &mut BufWriter<&UnixStream>) {
+    let only = match payload.opt_param1.as_deref().map(raw_message::parse_id) {
+        Some(Ok(id)) => Some(id),
+        Some(Err(err)) => {
+            respond(get_err(&err), write_stream).await;
+            return;
+        }
+        None => None,
+    };
+
+    let receiver = diagnostics::follow_frames();
+
+    while let Ok((address, frame)) = receiver.recv().await {
+        if payload.device.is_some() && payload.device.as_ref() != Some(&address) {
+            continue;
+        }
+
+        let (id, bytes) = match raw_message::split_frame(&frame) {
+            Some(v) => v,
+            None => continue,
+        };
+        if only.is_some() && only != Some(id) {
+            continue;
+        }
+
+        let line = json!({
+            "device": address,
+            "id": format!("0x{:02x}", id),
+            "name": ids::name(id),
+            "payload": raw_message::to_hex(bytes),
+        });
+        if !respond(format!("{}\n", line), write_stream).await {
+            return;
+        }
+    }
+}
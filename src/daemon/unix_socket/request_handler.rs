@@ -1,8 +1,19 @@
+use super::super::bluetooth::rfcomm_connector::Subscriber;
 use super::super::buds_info::BudsInfoInner;
-use super::set_value;
+use super::super::connection_history;
+use super::super::diagnostics;
+use super::super::integrations;
+use super::super::lost;
+use super::super::pairing;
+use super::super::status_diff;
 use super::{super::bluetooth::rfcomm_connector::ConnectionData, config};
 use super::{super::buds_config::Config, bluetooth_commands};
-use super::{Request, Response};
+use super::{
+    audit, devices, features, find, fit_test, grab, handoff, keys, load, mic, mute, pair, raw, rtt,
+    set_value, status, telemetry,
+};
+use super::{Progress, Request, Response};
+use crate::daemon_utils;
 
 use async_std::{
     io::{prelude::*, BufReader, BufWriter},
@@ -10,12 +21,36 @@ use async_std::{
     sync::{Arc, Mutex},
 };
 
-/// Handle a unix socket connection
+// Commands affecting a single device, allowed on device sockets
+const DEVICE_COMMANDS: [&str; 17] = [
+    "subscribe",
+    "measure_rtt",
+    "get_status",
+    "telemetry",
+    "fit_test",
+    "get_stats",
+    "set_value",
+    "apply_settings",
+    "get_value",
+    "toggle_value",
+    "list_keys",
+    "mic",
+    "mute_notifications",
+    "find_device",
+    "connect",
+    "disconnect",
+    "grab",
+];
+
+/// Handle a unix socket connection. Connections of a device socket
+/// pass its address as `scope` and are limited to that device
 pub async fn handle_client(
     stream: UnixStream,
     cd: Arc<Mutex<ConnectionData>>,
     config: Arc<Mutex<Config>>,
+    scope: Option<String>,
 ) {
+    let peer = audit::peer(&stream);
     let mut read_stream = BufReader::new(&stream);
     let mut write_stream = BufWriter::new(&stream);
 
@@ -26,13 +61,153 @@ pub async fn handle_client(
     }
 
     // Parse the request
-    let payload = match serde_json::from_str::<Request>(buff.as_str()) {
+    let mut payload = match serde_json::from_str::<Request>(buff.as_str()) {
         Ok(p) => p,
         Err(_) => return,
     };
 
+    if let Some(addr) = scope {
+        if let Err(err) = restrict(&mut payload, addr) {
+            respond(get_err(&err), &mut write_stream).await;
+            return;
+        }
+    }
+
+    // Subscriptions keep the connection open
+    if payload.cmd == "subscribe" {
+        subscribe(&payload, cd, &mut write_stream).await;
+        return;
+    }
+
+    // A new daemon takes over the devices
+    if payload.cmd == "handoff" {
+        let response = handoff::release(cd, &daemon_utils::get_daemon_path()).await;
+        audit::audit(&config, peer, &payload, &response).await;
+        respond(response, &mut write_stream).await;
+        std::process::exit(0);
+    }
+
+    // Logs are available without any connected device
+    if payload.cmd == "get_logs" {
+        get_logs(&payload, &mut write_stream).await;
+        return;
+    }
+
+    // The history is available without any connected device
+    if payload.cmd == "connection_stats" {
+        let response = match connection_history::summarize().await {
+            Ok(days) => serde_json::to_string(&Response::new_success("", Some(days))).unwrap(),
+            Err(err) => get_err(&err),
+        };
+        respond(response, &mut write_stream).await;
+        return;
+    }
+
+    // Lost devices are looked up in the history
+    if payload.cmd == "get_lost" {
+        let response = match lost::list().await {
+            Ok(entries) => {
+                serde_json::to_string(&Response::new_success("", Some(entries))).unwrap()
+            }
+            Err(err) => get_err(&err),
+        };
+        respond(response, &mut write_stream).await;
+        return;
+    }
+
+    // Tell which optional subsystems are compiled in and enabled
+    if payload.cmd == "get_features" {
+        let response = features::list(&*config.lock().await);
+        respond(response, &mut write_stream).await;
+        return;
+    }
+
+    // Tell which desktop integrations the daemon can reach
+    if payload.cmd == "get_integrations" {
+        let integrations = async_std::task::spawn_blocking(integrations::check).await;
+        let response = Response::new_success("", Some(integrations));
+        respond(serde_json::to_string(&response).unwrap(), &mut write_stream).await;
+        return;
+    }
+
+    // Pairing remote clients doesn't need a device either
+    if payload.cmd == "pairing_code" || payload.cmd == "revoke_tokens" {
+        let response = if payload.cmd == "pairing_code" {
+            pairing::new_code()
+                .map(|code| serde_json::to_string(&Response::new_success("", Some(code))).unwrap())
+        } else {
+            pairing::revoke_all().await.map(|count| {
+                serde_json::to_string(&Response::new_success("", Some(count))).unwrap()
+            })
+        };
+        let response = response.unwrap_or_else(|err| get_err(&err));
+
+        audit::audit(&config, peer, &payload, &response).await;
+        respond(response, &mut write_stream).await;
+        return;
+    }
+
+    // New buds aren't connected yet
+    if payload.cmd == "pair_device" {
+        let response = pair::run(&payload, Arc::clone(&config)).await;
+        audit::audit(&config, peer, &payload, &response).await;
+        respond(response, &mut write_stream).await;
+        return;
+    }
+
+    // Reconnecting takes a while and needs the listener to clean up in between
+    if payload.cmd == "grab" {
+        let response = grab::run(payload.device.clone(), cd, Arc::clone(&config)).await;
+        audit::audit(&config, peer, &payload, &response).await;
+        respond(response, &mut write_stream).await;
+        return;
+    }
+
+    // Received messages are streamed until the client disconnects
+    if payload.cmd == "raw_watch" {
+        raw::watch(&payload, &mut write_stream).await;
+        return;
+    }
+
+    // Measurements wait for answers of the buds and can't block other requests
+    if payload.cmd == "measure_rtt" {
+        respond(rtt::measure(&payload, cd, config).await, &mut write_stream).await;
+        return;
+    }
+
+    // The status waits for the current settings of the buds
+    if payload.cmd == "get_status" {
+        respond(status::get(&payload, cd, config).await, &mut write_stream).await;
+        return;
+    }
+
+    // Telemetry keeps requesting data from the buds
+    if payload.cmd == "telemetry" {
+        telemetry::stream(&payload, cd, config, &mut write_stream).await;
+        return;
+    }
+
+    // The fit test waits for the buds to measure
+    if payload.cmd == "fit_test" {
+        let response = fit_test::run(&payload, cd, config, &mut write_stream).await;
+        respond(response, &mut write_stream).await;
+        return;
+    }
+
     let mut connection_data = cd.lock().await;
 
+    // Paired devices are listed even if none is connected
+    if payload.cmd == "list_devices" {
+        respond(devices::list(&connection_data), &mut write_stream).await;
+        return;
+    }
+
+    // Works without connected devices to show the subscribers
+    if payload.cmd == "get_load" {
+        respond(load::get(&connection_data), &mut write_stream).await;
+        return;
+    }
+
     // Respond with error if no device is connected and no connect request was made
     if connection_data.get_device_count() == 0 && payload.cmd != "connect" {
         respond(get_err("No connected device found"), &mut write_stream).await;
@@ -52,12 +227,39 @@ pub async fn handle_client(
     };
 
     // Execute the command
-    let new_payload = run_payload_cmd(&payload, device_addr, &mut connection_data, config).await;
+    let new_payload = run_payload_cmd(
+        &payload,
+        device_addr,
+        &mut connection_data,
+        Arc::clone(&config),
+    )
+    .await;
     if new_payload.is_none() {
         return;
     }
+    let new_payload = new_payload.unwrap();
+
+    // Keep track of who changed what
+    audit::audit(&config, peer, &payload, &new_payload).await;
+
+    respond(new_payload, &mut write_stream).await;
+}
+
+// Limit a request of a device socket to its device
+fn restrict(payload: &mut Request, addr: String) -> Result<(), String> {
+    if !DEVICE_COMMANDS.contains(&payload.cmd.as_str()) {
+        return Err(format!("{} isn't allowed on a device socket", payload.cmd));
+    }
 
-    respond(new_payload.unwrap(), &mut write_stream).await;
+    match payload.device {
+        Some(ref device) if !device.eq_ignore_ascii_case(&addr) => {
+            Err("Only the device of this socket can be used".to_string())
+        }
+        _ => {
+            payload.device = Some(addr);
+            Ok(())
+        }
+    }
 }
 
 // Run the requested command
@@ -68,29 +270,52 @@ async fn run_payload_cmd(
     config: Arc<Mutex<Config>>,
 ) -> Option<String> {
     Some(match payload.cmd.as_str() {
-        "get_status" => {
-            let response = Response::new_success(
-                &device_addr,
-                Some(
-                    connection_data
-                        .get_device(&device_addr)
-                        .unwrap()
-                        .inner
-                        .clone(),
-                ),
-            );
+        "get_stats" => {
+            let device = connection_data.get_device(&device_addr).unwrap();
+            let response = Response::new_success(&device_addr, Some(device.stats.clone()));
             serde_json::to_string(&response).unwrap()
         }
         "set_value" => {
             let mut device = connection_data.get_device_mut(&device_addr).unwrap();
-            set_value::set(&payload, &mut device).await
+            set_value::set(&payload, &mut device, &config).await
+        }
+        "apply_settings" => {
+            let mut device = connection_data.get_device_mut(&device_addr).unwrap();
+            set_value::apply(&payload, &mut device, &config).await
+        }
+        "get_value" => {
+            let device = connection_data.get_device(&device_addr).unwrap();
+            set_value::get(&payload, device)
+        }
+        "list_keys" => {
+            let device = connection_data.get_device(&device_addr).unwrap();
+            keys::list(device)
         }
         "toggle_value" => {
             let mut device = connection_data.get_device_mut(&device_addr).unwrap();
             set_value::toggle(&payload, &mut device).await
         }
+        "mic" => mic::handle(&payload, device_addr.clone()),
+        "mute_notifications" => mute::handle(&payload, device_addr.clone()),
+        "find_device" => {
+            let mut device = connection_data.get_device_mut(&device_addr).unwrap();
+            find::handle(&payload, &mut device).await
+        }
+        "raw_send" => {
+            let mut device = connection_data.get_device_mut(&device_addr).unwrap();
+            raw::send(&payload, &mut device).await
+        }
         "set_config" => config::set_value(&payload, device_addr.clone(), config).await,
+        "set_desired_state" => {
+            config::set_desired_state(&payload, device_addr.clone(), config).await
+        }
         "disconnect" | "connect" => {
+            if payload.cmd == "disconnect" {
+                if let Some(device) = connection_data.get_device_mut(&device_addr) {
+                    device.disconnect_requested = true;
+                }
+            }
+
             bluetooth_commands::change_connection_status(
                 device_addr.clone(),
                 payload.cmd == "connect",
@@ -102,8 +327,81 @@ async fn run_payload_cmd(
     })
 }
 
+// Stream status changes to the client until it disconnects
+async fn subscribe(
+    payload: &Request,
+    cd: Arc<Mutex<ConnectionData>>,
+    write_stream: &mut BufWriter<&UnixStream>,
+) {
+    let (subscriber, receiver) = Subscriber::new(payload.device.clone(), false);
+
+    // Comma separated categories or event names to send
+    let only: Vec<String> = payload
+        .opt_param2
+        .as_deref()
+        .unwrap_or_default()
+        .split(',')
+        .map(|i| i.trim().to_lowercase())
+        .filter(|i| !i.is_empty())
+        .collect();
+
+    {
+        let mut connection_data = cd.lock().await;
+
+        // Send the full state first if requested
+        if payload.opt_param1.as_deref() == Some("snapshot") {
+            for info in connection_data.data.values() {
+                if payload.device.is_none() || payload.device.as_ref() == Some(&info.inner.address)
+                {
+                    subscriber.send(&status_diff::snapshot_event(&info.inner));
+                }
+            }
+        }
+
+        connection_data.subscribers.push(subscriber);
+    }
+
+    while let Ok(event) = receiver.recv().await {
+        let event = if only.is_empty() {
+            event
+        } else {
+            match status_diff::filter_event(&event, &only) {
+                Some(event) => event,
+                None => continue,
+            }
+        };
+
+        if !respond(format!("{}\n", event), write_stream).await {
+            return;
+        }
+    }
+}
+
+// Respond with the recent log lines. Keep streaming new ones if requested
+async fn get_logs(payload: &Request, write_stream: &mut BufWriter<&UnixStream>) {
+    if payload.opt_param1.as_deref() != Some("follow") {
+        let response = Response::new_success("", Some(diagnostics::recent_logs()));
+        respond(serde_json::to_string(&response).unwrap(), write_stream).await;
+        return;
+    }
+
+    let (recent, receiver) = diagnostics::follow_logs();
+
+    for line in recent {
+        if !respond(format!("{}\n", line), write_stream).await {
+            return;
+        }
+    }
+
+    while let Ok(line) = receiver.recv().await {
+        if !respond(format!("{}\n", line), write_stream).await {
+            return;
+        }
+    }
+}
+
 // Respond to client. Return true on success
-async fn respond(response: String, write_stream: &mut BufWriter<&UnixStream>) -> bool {
+pub async fn respond(response: String, write_stream: &mut BufWriter<&UnixStream>) -> bool {
     // Write response
     if let Err(err) = write_stream.write(response.as_bytes()).await {
         eprintln!("Err: {:?}", err);
@@ -118,6 +416,21 @@ async fn respond(response: String, write_stream: &mut BufWriter<&UnixStream>) ->
     true
 }
 
+/// Send a progress frame, if the client asked for them
+pub async fn report_progress(
+    payload: &Request,
+    progress: u8,
+    write_stream: &mut BufWriter<&UnixStream>,
+) -> bool {
+    if !payload.progress {
+        return true;
+    }
+
+    let mut frame = serde_json::to_string(&Progress { progress }).unwrap();
+    frame.push('\n');
+    respond(frame, write_stream).await
+}
+
 // Return an serializeable error
 pub fn get_err(msg: &str) -> String {
     let err: Response<BudsInfoInner> = Response::new_error("".to_owned(), msg.to_owned(), None);
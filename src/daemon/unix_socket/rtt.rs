@@ -0,0 +1,86 @@
+use super::super::bluetooth::rfcomm_connector::ConnectionData;
+use super::super::buds_config::Config;
+use super::super::buds_info::DEBUG_ANSWER_TIMEOUT;
+use super::{request_handler::get_err, Request, Response};
+
+use async_std::{
+    future,
+    sync::{Arc, Mutex},
+};
+use serde::{Deserialize, Serialize};
+
+use std::time::Duration;
+
+const DEFAULT_SAMPLES: usize = 5;
+const MAX_SAMPLES: usize = 50;
+
+/// Result of a round trip measurement
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct RttResult {
+    // Round trip times in ms
+    pub samples: Vec<f64>,
+    // Probes without answer
+    pub lost: usize,
+}
+
+/// Measure the round trip time of a debug request to the buds. Can't run while holding
+/// the connection data lock since the listener needs it to handle the answers
+pub async fn measure(
+    payload: &Request,
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+) -> String {
+    let samples = payload
+        .opt_param1
+        .as_ref()
+        .and_then(|i| i.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_SAMPLES);
+    if samples == 0 || samples > MAX_SAMPLES {
+        return get_err(&format!("Samples must be between 1 and {}", MAX_SAMPLES));
+    }
+
+    let address = {
+        let req_dev_addr = payload.device.clone().unwrap_or_default();
+        match cd
+            .lock()
+            .await
+            .get_device_address(&req_dev_addr, &config)
+            .await
+        {
+            Some(addr) => addr,
+            None => return get_err("Device not found"),
+        }
+    };
+
+    let mut result = RttResult::default();
+    for _ in 0..samples {
+        match measure_once(&address, &cd).await {
+            Ok(Some(rtt)) => result.samples.push(rtt.as_secs_f64() * 1000_f64),
+            Ok(None) => result.lost += 1,
+            Err(err) => return get_err(err.as_str()),
+        }
+    }
+
+    serde_json::to_string(&Response::new_success(address, Some(result))).unwrap()
+}
+
+// Send a single probe and wait for its answer
+async fn measure_once(
+    address: &str,
+    cd: &Arc<Mutex<ConnectionData>>,
+) -> Result<Option<Duration>, String> {
+    let answer = {
+        let mut connection_data = cd.lock().await;
+        let info = connection_data
+            .get_device_mut(address)
+            .ok_or("Device disconnected")?;
+
+        info.probe_debug_data().await?
+    };
+
+    // Lost probes and disconnects end up in the timeout
+    match future::timeout(DEBUG_ANSWER_TIMEOUT, answer.recv()).await {
+        Ok(Ok(rtt)) => Ok(Some(rtt)),
+        _ => Ok(None),
+    }
+}
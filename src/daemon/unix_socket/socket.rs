@@ -2,9 +2,16 @@ use super::super::bluetooth::rfcomm_connector::ConnectionData;
 use super::super::buds_config::Config;
 use super::request_handler;
 
-use async_std::{os::unix::net::UnixListener, prelude::*, sync::Mutex};
+use async_std::{fs, os::unix::net::UnixListener, prelude::*, sync::Mutex, task};
+use log::error;
 
-use std::{path::Path, sync::Arc};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+// Paths of the device sockets, to remove them on shutdown
+static DEVICE_SOCKETS: std::sync::Mutex<Vec<PathBuf>> = std::sync::Mutex::new(Vec::new());
 
 /// Runs the unix socket which provides the user API
 pub async fn run<P: AsRef<Path>>(p: P, cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) {
@@ -17,6 +24,89 @@ pub async fn run<P: AsRef<Path>>(p: P, cd: Arc<Mutex<ConnectionData>>, config: A
                 stream.unwrap(),
                 Arc::clone(&cd),
                 Arc::clone(&config),
+                None,
+            ));
+        }
+    }
+}
+
+/// Path of the socket of a single device. Colons of the address are replaced
+/// since Flatpak uses them to separate the mode in `--filesystem` permissions.
+/// Other users could create them in a shared directory, so there is no fallback
+/// without a runtime directory
+pub fn get_device_socket_path(addr: &str) -> Result<PathBuf, String> {
+    let name = format!("livebuds-{}.sock", addr.replace(':', "_"));
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .filter(|i| !i.is_empty())
+        .map(|dir| PathBuf::from(dir).join(name))
+        .ok_or_else(|| "Device sockets need XDG_RUNTIME_DIR to be set".to_owned())
+}
+
+/// Remove the sockets of all devices
+pub fn remove_device_sockets() {
+    if let Ok(paths) = DEVICE_SOCKETS.lock() {
+        for path in paths.iter() {
+            std::fs::remove_file(path).ok();
+        }
+    }
+}
+
+/// Runs a socket for each device with `device_socket` enabled, which only
+/// accepts commands for that device
+pub async fn run_device_sockets(cd: Arc<Mutex<ConnectionData>>, config: Arc<Mutex<Config>>) {
+    let addresses: Vec<String> = config
+        .lock()
+        .await
+        .buds_settings
+        .iter()
+        .filter(|i| i.device_socket())
+        .map(|i| i.address.clone())
+        .collect();
+
+    for addr in addresses {
+        task::spawn(run_device_socket(
+            addr,
+            Arc::clone(&cd),
+            Arc::clone(&config),
+        ));
+    }
+}
+
+async fn run_device_socket(
+    addr: String,
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+) {
+    let path = match get_device_socket_path(&addr) {
+        Ok(v) => v,
+        Err(err) => {
+            error!("Can't create the socket of {}: {}", addr, err);
+            return;
+        }
+    };
+
+    // Left over by a daemon which didn't shut down cleanly
+    fs::remove_file(&path).await.ok();
+
+    let listener = match UnixListener::bind(&path).await {
+        Ok(v) => v,
+        Err(err) => {
+            error!("Can't create the socket of {}: {}", addr, err);
+            return;
+        }
+    };
+    if let Ok(mut paths) = DEVICE_SOCKETS.lock() {
+        paths.push(path);
+    }
+
+    let mut incoming = listener.incoming();
+    while let Some(stream) = incoming.next().await {
+        if let Ok(stream) = stream {
+            task::spawn(request_handler::handle_client(
+                stream,
+                Arc::clone(&cd),
+                Arc::clone(&config),
+                Some(addr.clone()),
             ));
         }
     }
@@ -0,0 +1,104 @@
+use super::super::bluetooth::rfcomm_connector::ConnectionData;
+use super::super::buds_config::Config;
+use super::super::labels;
+use super::super::settings;
+use super::{request_handler::get_err, Request, Response};
+
+use async_std::sync::{Arc, Mutex};
+
+use std::time::Duration;
+
+const REFRESH_TIMEOUT: Duration = Duration::from_secs(1);
+// Settings received more recently are reported without asking the buds again
+const MAX_AGE: Duration = Duration::from_secs(30);
+
+/// Respond with the status of a device. Outdated settings get queried from the buds first since
+/// they might have been changed from a phone, eg. the touchpad actions. Can't wait while holding
+/// the connection data lock since the listener needs it to handle the answer.
+/// `opt_param1` may set the language of the labels, eg. "de"
+pub async fn get(
+    payload: &Request,
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+) -> String {
+    let address = {
+        let connection_data = cd.lock().await;
+        let req_dev_addr = payload.device.clone().unwrap_or_default();
+
+        // Tell clients why the buds aren't available yet
+        if connection_data.get_device(&req_dev_addr).is_none()
+            || connection_data.get_device_count() == 0
+        {
+            if let Some((address, state)) = connection_data.get_pending_connection(&req_dev_addr) {
+                let response = Response::new_error(address, state.describe(), Some(state));
+                return serde_json::to_string(&response).unwrap();
+            }
+        }
+
+        if connection_data.get_device_count() == 0 {
+            return get_err("No connected device found");
+        }
+
+        match connection_data
+            .get_device_address(&req_dev_addr, &config)
+            .await
+        {
+            Some(addr) => addr,
+            None => return get_err("Device not found"),
+        }
+    };
+
+    let refreshed = {
+        let mut connection_data = cd.lock().await;
+        let info = match connection_data.get_device_mut(&address) {
+            Some(info) => info,
+            None => return get_err("No connected device found"),
+        };
+
+        // Nothing gets sent to devices of unknown models
+        if info.inner.battery_only {
+            let response = Response::new_success(&address, Some(info.inner.clone()));
+            return serde_json::to_string(&response).unwrap();
+        }
+
+        let fresh = info
+            .last_extended_status
+            .map_or(false, |i| i.elapsed() < MAX_AGE);
+
+        // Report the known values if the buds can't be asked. Debug data
+        // isn't polled frequently while no client is subscribed
+        if fresh {
+            None
+        } else {
+            let refreshed = info.wait_extended_status();
+            if let Err(err) = info.request_extended_status().await {
+                eprintln!("Error requesting settings {:?}", err);
+            }
+            if let Err(err) = info.request_debug_data().await {
+                eprintln!("Error sending debug request {:?}", err);
+            }
+            Some(refreshed)
+        }
+    };
+
+    if let Some(refreshed) = refreshed {
+        async_std::future::timeout(REFRESH_TIMEOUT, refreshed.recv())
+            .await
+            .ok();
+    }
+
+    let connection_data = cd.lock().await;
+    match connection_data.get_device(&address) {
+        Some(info) => {
+            let mut inner = info.inner.clone();
+            inner.connection = connection_data.get_connection_state(&address);
+            inner.labels = Some(labels::get(&inner, payload.opt_param1.as_deref()));
+            inner.capabilities = settings::supported(&inner)
+                .into_iter()
+                .map(|i| i.to_owned())
+                .collect();
+            serde_json::to_string(&Response::new_success(&address, Some(inner))).unwrap()
+        }
+        None => get_err("Device disconnected"),
+    }
+}
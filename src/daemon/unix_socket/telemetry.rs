@@ -0,0 +1,115 @@
+use super::super::bluetooth::rfcomm_connector::ConnectionData;
+use super::super::buds_config::Config;
+use super::super::buds_info::BudsInfo;
+use super::request_handler::{get_err, respond};
+use super::Request;
+
+use async_std::{
+    io::BufWriter,
+    os::unix::net::UnixStream,
+    sync::{Arc, Mutex},
+};
+use serde::Serialize;
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_INTERVAL: u64 = 2;
+
+/// Sensor values of both buds at a given time
+#[derive(Debug, Serialize)]
+struct Sample {
+    // Unix time in ms
+    time: u128,
+    device: String,
+    voltage_left: f32,
+    voltage_right: f32,
+    current_left: f64,
+    current_right: f64,
+    temperature_left: f32,
+    temperature_right: f32,
+    batt_left: i8,
+    batt_right: i8,
+    noise_reduction: bool,
+    ambient_sound: bool,
+}
+
+impl Sample {
+    fn new(info: &BudsInfo) -> Self {
+        let debug = &info.inner.debug;
+        Self {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+            device: info.inner.address.clone(),
+            voltage_left: debug.voltage_left,
+            voltage_right: debug.voltage_right,
+            current_left: debug.current_left,
+            current_right: debug.current_right,
+            temperature_left: debug.temperature_left,
+            temperature_right: debug.temperature_right,
+            batt_left: info.inner.batt_left,
+            batt_right: info.inner.batt_right,
+            noise_reduction: info.inner.noise_reduction,
+            ambient_sound: info.inner.ambient_sound_enabled,
+        }
+    }
+}
+
+/// Request debug data at the given interval (opt_param2, in seconds) and write a json line
+/// for each answer. Only one line gets written unless opt_param1 is "follow"
+pub async fn stream(
+    payload: &Request,
+    cd: Arc<Mutex<ConnectionData>>,
+    config: Arc<Mutex<Config>>,
+    write_stream: &mut BufWriter<&UnixStream>,
+) {
+    let follow = payload.opt_param1.as_deref() == Some("follow");
+    let interval = payload
+        .opt_param2
+        .as_ref()
+        .and_then(|i| i.parse::<u64>().ok())
+        .unwrap_or(DEFAULT_INTERVAL)
+        .max(1);
+
+    let address = {
+        let connection_data = cd.lock().await;
+        if connection_data.get_device_count() == 0 {
+            respond(get_err("No connected device found"), write_stream).await;
+            return;
+        }
+
+        let req_dev_addr = payload.device.clone().unwrap_or_default();
+        match connection_data
+            .get_device_address(&req_dev_addr, &config)
+            .await
+        {
+            Some(addr) => addr,
+            None => {
+                respond(get_err("Device not found"), write_stream).await;
+                return;
+            }
+        }
+    };
+
+    loop {
+        if let Some(info) = cd.lock().await.get_device_mut(&address) {
+            if let Err(err) = info.request_debug_data().await {
+                eprintln!("Error sending debug request {:?}", err);
+            }
+        }
+
+        // Give the buds time to answer
+        async_std::task::sleep(Duration::from_secs(interval)).await;
+
+        let sample = match cd.lock().await.get_device(&address) {
+            Some(info) => Sample::new(info),
+            None => return,
+        };
+
+        let line = serde_json::to_string(&sample).unwrap() + "\n";
+        if !respond(line, write_stream).await || !follow {
+            return;
+        }
+    }
+}
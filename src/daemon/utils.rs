@@ -1,31 +1,4 @@
-use galaxy_buds_rs::message::bud_property::Side;
-
-/// Converts a str to a boolean. All undefineable
-/// values are false
-pub fn str_to_bool<S: AsRef<str>>(s: S) -> bool {
-    matches!(
-        s.as_ref().to_lowercase().as_str(),
-        "1" | "true" | "yes" | "y" | "enabled" | "on"
-    )
-}
-
-/// return true if s can be represented as a bool
-pub fn is_str_bool<S: AsRef<str>>(s: S) -> bool {
-    matches!(
-        s.as_ref().to_lowercase().as_str(),
-        "1" | "true"
-            | "yes"
-            | "y"
-            | "0"
-            | "no"
-            | "n"
-            | "false"
-            | "enabled"
-            | "on"
-            | "off"
-            | "disabled"
-    )
-}
+use galaxy_buds_rs::message::bud_property::{Side, TouchpadOption};
 
 pub fn str_to_side<S: AsRef<str>>(s: S) -> Option<Side> {
     Some(match s.as_ref() {
@@ -34,3 +7,28 @@ pub fn str_to_side<S: AsRef<str>>(s: S) -> Option<Side> {
         _ => return None,
     })
 }
+
+/// Parses a touch and hold option by its friendly name
+pub fn str_to_touchpad_option<S: AsRef<str>>(s: S) -> TouchpadOption {
+    match s.as_ref().to_lowercase().as_str() {
+        // Holding the left bud lowers and the right one raises the volume
+        "volume" => TouchpadOption::Volume,
+        "spotify" => TouchpadOption::Spotify,
+        "voice-command" | "assistant" => TouchpadOption::VoiceCommand,
+        "anc" | "ambient" | "noise-control" => TouchpadOption::NoiseCanceling,
+        "disconnect" => TouchpadOption::Disconnect,
+        _ => TouchpadOption::Undetected,
+    }
+}
+
+/// Returns the friendly name of a touch and hold option
+pub fn touchpad_option_to_str(option: TouchpadOption) -> &'static str {
+    match option {
+        TouchpadOption::Volume => "volume",
+        TouchpadOption::Spotify => "spotify",
+        TouchpadOption::VoiceCommand => "assistant",
+        TouchpadOption::NoiseCanceling => "noise-control",
+        TouchpadOption::Disconnect => "disconnect",
+        _ => "undetected",
+    }
+}
@@ -1,18 +1,89 @@
+use crate::cmd::socket_client::{self, SocketClient};
+use crate::daemon::{history, unix_socket::Response};
+
 use std::{
-    env,
-    path::Path,
-    process::{exit, Command, Stdio},
+    env, fs,
+    os::unix::net::UnixStream,
+    path::{Path, PathBuf},
+    process::{exit, Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
 };
 
-/// Start the daemon detached from the current cli
-pub fn start() -> bool {
-    let curr_exe = env::current_exe().expect("Couldn't get current executable!");
+const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_STARTUP_POLL_DELAY: Duration = Duration::from_millis(500);
+
+/// Returns the path of the daemon socket of the current user. Without a
+/// runtime directory it falls back to the old shared path
+pub fn get_daemon_path() -> String {
+    match env::var("XDG_RUNTIME_DIR").ok().filter(|i| !i.is_empty()) {
+        Some(dir) => format!("{}/earbuds.sock", dir),
+        None => "/tmp/earbuds.sock".to_owned(),
+    }
+}
+
+/// Start the daemon detached from the current cli and wait until it accepts requests
+pub fn start<P: AsRef<Path>>(p: P) -> Result<(), String> {
+    let curr_exe = env::current_exe().map_err(|e| e.to_string())?;
+    let log_file = get_log_file();
+
     let mut cmd = Command::new("nohup");
     let cmd = cmd.arg(curr_exe).arg("-d").arg("--no-fork").arg("-q");
     cmd.stdout(Stdio::null());
-    cmd.stderr(Stdio::null());
-    let status = cmd.spawn();
-    status.is_ok()
+
+    // Keep the output of the daemon for failed starts
+    match log_file.as_ref().and_then(|i| fs::File::create(i).ok()) {
+        Some(f) => cmd.stderr(f),
+        None => cmd.stderr(Stdio::null()),
+    };
+
+    let child = cmd
+        .spawn()
+        .map_err(|e| format!("Can't start the daemon: {}", e))?;
+
+    wait_ready(p.as_ref(), child, log_file)
+}
+
+// Poll the socket with an increasing delay until the daemon accepts connections
+fn wait_ready(p: &Path, mut child: Child, log_file: Option<PathBuf>) -> Result<(), String> {
+    let log_hint = log_file
+        .map(|i| format!(". See {} for details", i.display()))
+        .unwrap_or_default();
+
+    let start = Instant::now();
+    let mut delay = Duration::from_millis(10);
+
+    loop {
+        if UnixStream::connect(p).is_ok() {
+            return Ok(());
+        }
+
+        if let Ok(Some(status)) = child.try_wait() {
+            return Err(format!(
+                "The daemon exited before it was ready ({}){}",
+                status, log_hint
+            ));
+        }
+
+        if start.elapsed() > STARTUP_TIMEOUT {
+            return Err(format!(
+                "The daemon didn't get ready within {}s{}",
+                STARTUP_TIMEOUT.as_secs(),
+                log_hint
+            ));
+        }
+
+        thread::sleep(delay);
+        delay = (delay * 2).min(MAX_STARTUP_POLL_DELAY);
+    }
+}
+
+// File the output of a detached daemon gets written to
+fn get_log_file() -> Option<PathBuf> {
+    let dir: PathBuf = history::get_state_dir()?.into();
+    fs::create_dir_all(&dir).ok()?;
+    Some(dir.join("daemon.log"))
 }
 
 /// Returns an error with a human friendly message if a daemon is already running
@@ -45,6 +116,42 @@ pub fn check_running<P: AsRef<Path>>(p: P) -> Result<(), String> {
     Ok(())
 }
 
+/// Ask the running daemon to release its devices and exit. Returns once its socket is gone
+pub fn takeover<P: AsRef<Path>>(p: P) -> Result<(), String> {
+    let p = p.as_ref();
+
+    // Processes of the running daemon, to wait for them to exit
+    let pids: Vec<u32> = ofiles::opath(p)
+        .map(|i| i.into_iter().map(|pid| pid.into()).collect())
+        .unwrap_or_default();
+
+    let mut client = SocketClient::new(p).map_err(|e| e.to_string())?;
+    let res = client
+        .do_request(socket_client::new_handoff_request())
+        .map_err(|e| e.to_string())?;
+    let res: Response<()> = serde_json::from_str(&res)
+        .map_err(|_| "The running daemon doesn't support a handoff".to_owned())?;
+    if !res.is_success() {
+        return Err(res.status_message.unwrap_or_default());
+    }
+
+    // The old daemon might still be connected to the buds after removing its socket
+    let start = Instant::now();
+    while p.exists() || pids.iter().any(|i| is_running(*i)) {
+        if start.elapsed() > HANDOFF_TIMEOUT {
+            return Err("The running daemon didn't hand off in time".to_owned());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    Ok(())
+}
+
+// Check whether a process is still alive
+fn is_running(pid: u32) -> bool {
+    nix::sys::signal::kill(nix::unistd::Pid::from_raw(pid as i32), None).is_ok()
+}
+
 /// Try to delete the socket file
 pub fn try_delete_socket<P: AsRef<Path>>(p: P) -> Result<(), String> {
     std::fs::remove_file(p.as_ref()).map_err(|e| {
@@ -75,8 +182,8 @@ pub fn kill<P: AsRef<Path>>(quiet: bool, daemon_path: P) -> bool {
             println!("Daemon exited!");
         }
 
-        // Hacky way not to display annoying cargo warnings
-        try_delete_socket(daemon_path).unwrap();
+        // The daemon removes the socket itself on SIGTERM, so it might be gone already
+        try_delete_socket(daemon_path).ok();
         return true;
     }
 
@@ -3,6 +3,7 @@ mod cmd;
 mod daemon;
 mod daemon_utils;
 
+use async_std::task;
 use clap::{ArgMatches, Command};
 use clap_complete::{
     generate,
@@ -14,27 +15,28 @@ use human_panic::setup_panic;
 
 use std::process::exit;
 
-const DAEMON_PATH: &str = "/tmp/earbuds.sock";
-
-#[async_std::main]
-async fn main() {
+fn main() {
     setup_panic!();
 
-    pretty_env_logger::formatted_builder()
+    // Recent log lines end up in crash diagnostics
+    let logger = pretty_env_logger::formatted_builder()
         .filter_module("earbuds", log::LevelFilter::Info)
         .filter_module("galaxy_buds_rs", log::LevelFilter::Info)
-        .init();
+        .build();
+    let level = logger.filter();
+    daemon::diagnostics::init_logger(logger, level);
 
     let clap = {
         let s = "";
         cli::build(&s).get_matches()
     };
 
+    // Each user runs its own daemon
+    let daemon_path = daemon_utils::get_daemon_path();
+
     // Kill daemon if desired and running
-    if clap.is_present("kill-daemon")
-        && daemon_utils::check_running(DAEMON_PATH.to_owned()).is_err()
-    {
-        if !daemon_utils::kill(clap.is_present("kill-daemon"), DAEMON_PATH) {
+    if clap.is_present("kill-daemon") && daemon_utils::check_running(&daemon_path).is_err() {
+        if !daemon_utils::kill(clap.is_present("kill-daemon"), &daemon_path) {
             println!("Couldn't kill daemon");
             return;
         }
@@ -42,8 +44,16 @@ async fn main() {
 
     // Run daemon on -k
     if clap.is_present("daemon") {
+        // Let the running daemon hand over its devices
+        if clap.is_present("takeover") && daemon_utils::check_running(&daemon_path).is_err() {
+            if let Err(err) = daemon_utils::takeover(&daemon_path) {
+                eprintln!("Can't take over: {}", err);
+                exit(1);
+            }
+        }
+
         // Check if a daemon is already running
-        if let Err(err) = daemon_utils::check_running(DAEMON_PATH) {
+        if let Err(err) = daemon_utils::check_running(&daemon_path) {
             // Don't print error output if -q is passed
             if !clap.is_present("quiet") {
                 eprintln!("{}", err);
@@ -52,12 +62,20 @@ async fn main() {
         }
         // Block if --no-fork is provided
         if clap.is_present("no-fork") {
-            daemon::run_daemon(DAEMON_PATH.to_owned()).await;
+            // The runtime threads have to inherit the signal mask
+            let signals = daemon::block_signals();
+            task::block_on(daemon::run_daemon(daemon_path, signals));
             return;
-        } else
+        }
+
         // Start daemon detached
-        if daemon_utils::start() && !clap.is_present("quiet") {
-            println!("Daemon started successfully")
+        match daemon_utils::start(&daemon_path) {
+            Ok(()) if !clap.is_present("quiet") => println!("Daemon started successfully"),
+            Ok(()) => (),
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1);
+            }
         }
         return;
     }
@@ -73,24 +91,50 @@ async fn main() {
         return;
     }
 
+    // Print the D-Bus interface for extension authors
+    if clap.subcommand_matches("dbus-xml").is_some() {
+        print!(
+            "{}",
+            include_str!(concat!(env!("OUT_DIR"), "/org.livebuds.Daemon1.xml"))
+        );
+        return;
+    }
+
+    // Installing the service doesn't need a daemon
+    if let Some(service) = clap.subcommand_matches("service") {
+        if let Some(install) = service.subcommand_matches("install") {
+            cmd::service::install(install);
+        }
+
+        if service.subcommand_matches("uninstall").is_some() {
+            cmd::service::uninstall();
+        }
+        return;
+    }
+
+    // Prompts only read the status file and must not wait for a daemon
+    if let Some(subcommand) = clap.subcommand_matches("prompt") {
+        cmd::prompt::show(subcommand);
+        return;
+    }
+
     // From here we need a running daemon, so ensure one is running
-    if daemon_utils::check_running(DAEMON_PATH.to_owned()).is_ok() {
-        if !daemon_utils::start() {
+    if daemon_utils::check_running(&daemon_path).is_ok() {
+        if let Err(err) = daemon_utils::start(&daemon_path) {
+            eprintln!("{}", err);
             exit(1);
-        } else {
-            if !clap.is_present("quiet") {
-                println!("Daemon started successfully")
-            }
-            // TODO wait for deamon to be ready
-            std::thread::sleep(std::time::Duration::from_millis(1000));
+        }
+
+        if !clap.is_present("quiet") {
+            println!("Daemon started successfully")
         }
     }
-    run_subcommands(clap);
+    run_subcommands(clap, &daemon_path);
 }
 
-fn run_subcommands(clap: ArgMatches) {
+fn run_subcommands(clap: ArgMatches, daemon_path: &str) {
     // Create a new daemon connection client
-    let mut socket_client = match SocketClient::new(&DAEMON_PATH) {
+    let mut socket_client = match SocketClient::new(daemon_path) {
         Ok(v) => v,
         Err(err) => {
             eprintln!("Could not connect to daemon: {:?}", err);
@@ -98,9 +142,35 @@ fn run_subcommands(clap: ArgMatches) {
         }
     };
 
+    // Don't block forever if the daemon hangs
+    if let Some(timeout) = clap.value_of("timeout") {
+        let timeout = match daemon::parse::positive_duration(timeout) {
+            Ok(v) => v,
+            Err(err) => {
+                eprintln!("{}", err);
+                exit(1);
+            }
+        };
+
+        if let Err(err) = socket_client.set_timeout(Some(timeout)) {
+            eprintln!("Can't set timeout: {:?}", err);
+            exit(1);
+        }
+    }
+
     // Run status command
     if let Some(subcommand) = clap.subcommand_matches("status") {
-        cmd::info::show(&mut socket_client, subcommand);
+        let bar = subcommand
+            .value_of("output")
+            .filter(|i| cmd::bar::FORMATS.contains(i));
+
+        if let Some(format) = bar {
+            cmd::bar::show(&mut socket_client, subcommand, format);
+        } else if subcommand.is_present("brief") {
+            cmd::brief::show(&mut socket_client, subcommand);
+        } else {
+            cmd::info::show(&mut socket_client, subcommand);
+        }
     }
 
     // Run set command
@@ -113,6 +183,27 @@ fn run_subcommands(clap: ArgMatches) {
         );
     }
 
+    if let Some(subcommand) = clap.subcommand_matches("apply") {
+        cmd::apply::apply(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("pair") {
+        cmd::pair::pair(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("lost") {
+        cmd::lost::list(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("import-wearable") {
+        cmd::wearable::import(&mut socket_client, subcommand);
+    }
+
+    // Run get command
+    if let Some(subcommand) = clap.subcommand_matches("get") {
+        cmd::set_value::get(&mut socket_client, subcommand);
+    }
+
     // Run disable command
     if let Some(subcommand) = clap.subcommand_matches("disable") {
         cmd::set_value::set(&mut socket_client, subcommand, false, "off");
@@ -140,6 +231,127 @@ fn run_subcommands(clap: ArgMatches) {
         }
     }
 
+    if let Some(settings) = clap.subcommand_matches("settings") {
+        if let Some(export) = settings.subcommand_matches("export") {
+            cmd::settings::export(&mut socket_client, export);
+        }
+
+        if let Some(import) = settings.subcommand_matches("import") {
+            cmd::settings::import(&mut socket_client, import);
+        }
+    }
+
+    if let Some(debug) = clap.subcommand_matches("debug") {
+        if let Some(rtt) = debug.subcommand_matches("rtt") {
+            cmd::debug::rtt(&mut socket_client, rtt);
+        }
+        if let Some(telemetry) = debug.subcommand_matches("telemetry") {
+            cmd::debug::telemetry(&mut socket_client, telemetry);
+        }
+    }
+
+    if let Some(mic) = clap.subcommand_matches("mic") {
+        if let Some(list) = mic.subcommand_matches("list") {
+            cmd::mic::list(&mut socket_client, list);
+        }
+
+        if let Some(set_default) = mic.subcommand_matches("set-default") {
+            cmd::mic::set_default(&mut socket_client, set_default);
+        }
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("stats") {
+        cmd::stats::show(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("mute-notifications") {
+        cmd::notifications::mute(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("top") {
+        cmd::top::run(&mut socket_client, subcommand);
+    }
+
+    if let Some(remote) = clap.subcommand_matches("remote") {
+        if let Some(pair) = remote.subcommand_matches("pair") {
+            cmd::remote::pair(&mut socket_client, pair);
+        } else if let Some(revoke) = remote.subcommand_matches("revoke") {
+            cmd::remote::revoke(&mut socket_client, revoke);
+        }
+    }
+
+    if let Some(raw) = clap.subcommand_matches("raw") {
+        if let Some(send) = raw.subcommand_matches("send") {
+            cmd::raw::send(&mut socket_client, send);
+        } else if let Some(watch) = raw.subcommand_matches("watch") {
+            cmd::raw::watch(&mut socket_client, watch);
+        }
+    }
+
+    if let Some(ambient) = clap.subcommand_matches("ambient") {
+        if let Some(set) = ambient.subcommand_matches("set") {
+            cmd::ambient::set(&mut socket_client, set);
+        } else if let Some(up) = ambient.subcommand_matches("up") {
+            cmd::ambient::step(&mut socket_client, up, true);
+        } else if let Some(down) = ambient.subcommand_matches("down") {
+            cmd::ambient::step(&mut socket_client, down, false);
+        }
+    }
+
+    if let Some(eq) = clap.subcommand_matches("eq") {
+        if let Some(cycle) = eq.subcommand_matches("cycle") {
+            cmd::eq::cycle(&mut socket_client, cycle);
+        }
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("fit-test") {
+        cmd::fit_test::run(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("test-channels") {
+        cmd::test_channels::run(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("logs") {
+        cmd::logs::show(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("menu") {
+        cmd::menu::show(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("devices") {
+        cmd::devices::list(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("find") {
+        cmd::find::find(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("grab") {
+        cmd::grab::grab(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("keys") {
+        cmd::keys::list(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("features") {
+        cmd::features::list(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("doctor") {
+        cmd::doctor::check(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("watch") {
+        cmd::watch::watch(&mut socket_client, subcommand);
+    }
+
+    if let Some(subcommand) = clap.subcommand_matches("watch-battery") {
+        cmd::watch_battery::watch(&mut socket_client, subcommand);
+    }
+
     if let Some(subcommand) = clap.subcommand_matches("disconnect") {
         cmd::connection::disconnect(&mut socket_client, subcommand);
     }